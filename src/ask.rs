@@ -0,0 +1,141 @@
+//! Question answering over transcripts: retrieved passages go to the chat model
+//! as numbered sources, and the answer cites them by episode id.
+
+use anyhow::Result;
+
+use crate::embed::format_timestamp;
+use crate::models::PassageHit;
+use crate::summarize::{self, SummaryResult};
+
+const SYSTEM_PROMPT: &str = "You answer questions about a library of podcast episodes using only \
+the transcript excerpts provided. Cite every claim with the episode id of its source in square \
+brackets, like [#42]; cite several sources as [#42][#7]. If the excerpts do not answer the \
+question, say so instead of guessing. Answer in the language of the question.";
+
+/// Send `question` with the retrieved passages to the chat model.
+pub async fn answer(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    api_key: &str,
+    model: &str,
+    max_tokens: u32,
+    question: &str,
+    passages: &[PassageHit],
+) -> Result<SummaryResult> {
+    summarize::chat_completion(
+        client,
+        api_base_url,
+        api_key,
+        model,
+        max_tokens,
+        SYSTEM_PROMPT,
+        &user_message(question, passages),
+    )
+    .await
+}
+
+/// The question followed by each passage, labelled with the id to cite.
+pub fn user_message(question: &str, passages: &[PassageHit]) -> String {
+    let mut out = String::from("Transcript excerpts:\n\n");
+    for p in passages {
+        out.push_str(&format!(
+            "[#{}] {} ({})",
+            p.episode_id, p.episode_title, p.podcast_title
+        ));
+        if let Some(start) = p.start_secs {
+            out.push_str(&format!(" at {}", format_timestamp(start)));
+        }
+        out.push('\n');
+        out.push_str(p.text.trim());
+        out.push_str("\n\n");
+    }
+    out.push_str(&format!("Question: {question}"));
+    out
+}
+
+/// Episode ids cited as `[#id]` in an answer, in order of first citation.
+pub fn cited_episodes(answer: &str) -> Vec<i64> {
+    let mut ids = Vec::new();
+    let mut rest = answer;
+    while let Some(pos) = rest.find("[#") {
+        rest = &rest[pos + 2..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if rest[digits.len()..].starts_with(']')
+            && let Ok(id) = digits.parse::<i64>()
+            && !ids.contains(&id)
+        {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::stub_http_server;
+
+    fn passage(episode_id: i64, title: &str, start: Option<f64>, text: &str) -> PassageHit {
+        PassageHit {
+            episode_id,
+            episode_title: title.to_string(),
+            podcast_title: "Pod".to_string(),
+            published_at: None,
+            text: text.to_string(),
+            start_secs: start,
+            end_secs: None,
+            score: 0.9,
+        }
+    }
+
+    #[test]
+    fn user_message_labels_passages() {
+        let msg = user_message(
+            "What about remote work?",
+            &[passage(
+                42,
+                "Async teams",
+                Some(754.0),
+                " We went fully remote. ",
+            )],
+        );
+        assert!(msg.contains("[#42] Async teams (Pod) at 12:34\nWe went fully remote.\n"));
+        assert!(msg.ends_with("Question: What about remote work?"));
+    }
+
+    #[test]
+    fn cited_episodes_in_order() {
+        let ids = cited_episodes("Remote works [#42][#7], per [#42]. Not [#x] or [#12");
+        assert_eq!(ids, vec![42, 7]);
+        assert!(cited_episodes("No sources.").is_empty());
+    }
+
+    #[tokio::test]
+    async fn answer_sends_passages_and_returns_citations() {
+        let (url, server) = stub_http_server(vec![(
+            200,
+            r#"{"choices":[{"message":{"content":"Guests liked async work [#42]."}}],"usage":{"prompt_tokens":10,"completion_tokens":5}}"#,
+        )])
+        .await;
+
+        let result = answer(
+            &reqwest::Client::new(),
+            &url,
+            "key",
+            "stub-model",
+            256,
+            "What did guests say about remote work?",
+            &[passage(42, "Async teams", None, "We went fully remote.")],
+        )
+        .await
+        .unwrap();
+
+        let request = server.await.unwrap().remove(0);
+        assert!(request.starts_with("POST /chat/completions"));
+        assert!(request.contains("[#42] Async teams (Pod)"));
+        assert!(request.contains("We went fully remote."));
+        assert_eq!(result.content, "Guests liked async work [#42].");
+        assert_eq!(cited_episodes(&result.content), vec![42]);
+        assert_eq!(result.prompt_tokens, Some(10));
+    }
+}
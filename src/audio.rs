@@ -3,10 +3,11 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::{CODEC_TYPE_NULL, Decoder};
-use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
 
 const WHISPER_SAMPLE_RATE: u32 = 16_000;
 
@@ -45,7 +46,8 @@ fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
     output
 }
 
-/// Streaming audio decoder that yields chunks of 16kHz mono f32 samples.
+/// Streaming audio decoder that yields chunks of mono f32 samples, at 16kHz for
+/// whisper unless another output rate is set.
 /// This avoids loading the entire audio file into memory at once.
 pub struct ChunkedAudioDecoder {
     format: Box<dyn FormatReader>,
@@ -53,8 +55,12 @@ pub struct ChunkedAudioDecoder {
     sample_buf: Option<SampleBuffer<f32>>,
     track_id: u32,
     source_rate: u32,
+    output_rate: u32,
     channels: usize,
+    time_base: Option<TimeBase>,
     total_duration_secs: Option<f64>,
+    /// Decoded samples to drop, after a seek that landed before its target
+    skip_samples: usize,
     finished: bool,
 }
 
@@ -90,14 +96,16 @@ impl ChunkedAudioDecoder {
         let source_rate = track.codec_params.sample_rate.unwrap_or(44100);
         let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(1);
 
-        let total_duration_secs = track
-            .codec_params
-            .n_frames
-            .zip(track.codec_params.time_base)
-            .map(|(n_frames, time_base)| {
-                let t = time_base.calc_time(n_frames);
-                t.seconds as f64 + t.frac
-            });
+        let time_base = track.codec_params.time_base;
+        let total_duration_secs =
+            track
+                .codec_params
+                .n_frames
+                .zip(time_base)
+                .map(|(n_frames, time_base)| {
+                    let t = time_base.calc_time(n_frames);
+                    t.seconds as f64 + t.frac
+                });
 
         let decoder = symphonia::default::get_codecs()
             .make(&track.codec_params, &Default::default())
@@ -109,8 +117,11 @@ impl ChunkedAudioDecoder {
             sample_buf: None,
             track_id,
             source_rate,
+            output_rate: WHISPER_SAMPLE_RATE,
             channels,
+            time_base,
             total_duration_secs,
+            skip_samples: 0,
             finished: false,
         })
     }
@@ -119,7 +130,55 @@ impl ChunkedAudioDecoder {
         self.total_duration_secs
     }
 
-    /// Decode up to `max_seconds` of audio, returning 16kHz mono f32 samples.
+    /// Sample rate of the audio file.
+    pub fn source_rate(&self) -> u32 {
+        self.source_rate
+    }
+
+    /// Resample to `rate` instead of whisper's 16kHz.
+    pub fn set_output_rate(&mut self, rate: u32) {
+        self.output_rate = rate;
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Move to `secs` into the audio, returning the position reached (the
+    /// target, unless it's past the end).
+    pub fn seek(&mut self, secs: f64) -> Result<f64> {
+        let to = SeekTo::Time {
+            time: Time::from(secs),
+            track_id: Some(self.track_id),
+        };
+        let seeked = self
+            .format
+            .seek(SeekMode::Coarse, to)
+            .with_context(|| format!("Failed to seek to {secs:.1}s"))?;
+        self.decoder.reset();
+        self.finished = false;
+
+        // Formats seek to a packet boundary at or before the target; decode
+        // from there and drop the difference
+        let required = self.ts_to_secs(seeked.required_ts);
+        let actual = self.ts_to_secs(seeked.actual_ts);
+        let frames = ((required - actual).max(0.0) * self.source_rate as f64).round() as usize;
+        self.skip_samples = frames * self.channels;
+        Ok(required)
+    }
+
+    fn ts_to_secs(&self, ts: u64) -> f64 {
+        match self.time_base {
+            Some(time_base) => {
+                let t = time_base.calc_time(ts);
+                t.seconds as f64 + t.frac
+            }
+            None => ts as f64 / self.source_rate as f64,
+        }
+    }
+
+    /// Decode up to `max_seconds` of audio, returning mono f32 samples at the
+    /// output rate.
     /// Returns `None` when the audio is exhausted.
     pub fn next_chunk(&mut self, max_seconds: u32) -> Result<Option<Vec<f32>>> {
         if self.finished {
@@ -158,7 +217,9 @@ impl ChunkedAudioDecoder {
                 SampleBuffer::<f32>::new(audio_buf.capacity() as u64, *audio_buf.spec())
             });
             buf.copy_interleaved_ref(audio_buf);
-            chunk_samples.extend_from_slice(buf.samples());
+            let skip = self.skip_samples.min(buf.samples().len());
+            self.skip_samples -= skip;
+            chunk_samples.extend_from_slice(&buf.samples()[skip..]);
         }
 
         if chunk_samples.is_empty() {
@@ -171,8 +232,8 @@ impl ChunkedAudioDecoder {
             chunk_samples
         };
 
-        if self.source_rate != WHISPER_SAMPLE_RATE {
-            Ok(Some(resample(&mono, self.source_rate, WHISPER_SAMPLE_RATE)))
+        if self.source_rate != self.output_rate {
+            Ok(Some(resample(&mono, self.source_rate, self.output_rate)))
         } else {
             Ok(Some(mono))
         }
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+
+use crate::digest::DigestFormat;
+use crate::export::ExportFormat;
+use crate::output::OutputFormat;
 
 #[derive(Parser)]
 #[command(name = "podcast-summarize")]
@@ -11,6 +15,17 @@ pub struct Cli {
     /// Enable verbose output
     #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// Output format for listings, `show` and `config show`
+    #[arg(
+        long,
+        global = true,
+        value_name = "FORMAT",
+        value_enum,
+        ignore_case = true,
+        default_value_t
+    )]
+    pub output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -19,6 +34,14 @@ pub enum Command {
     Add {
         /// RSS feed URL
         url: String,
+
+        /// Also process the latest N episodes already in the feed (default: none)
+        #[arg(long, value_name = "N")]
+        backfill: Option<usize>,
+
+        /// Also process episodes published since a date or span (e.g. 2024-01-01, 30d)
+        #[arg(long, value_name = "WHEN", conflicts_with = "backfill")]
+        since: Option<String>,
     },
 
     /// Remove a podcast subscription
@@ -37,8 +60,43 @@ pub enum Command {
 
     /// List subscriptions or episodes
     List {
-        /// Podcast name to show episodes for (partial match)
+        /// Podcast name to show episodes for (partial match); without one, any
+        /// episode filter lists episodes across all podcasts
         name: Option<String>,
+
+        /// Show failed episodes with the reason and retry schedule
+        #[arg(long)]
+        failed: bool,
+
+        /// Only episodes with this tag (their own or their podcast's)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only episodes with these statuses: new, downloaded, transcribed, done,
+        /// failed, skipped (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        status: Vec<String>,
+
+        /// Only episodes published since a date or span (e.g. 2024-01-01, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only episodes published on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Order episodes by published (newest first), duration (longest first)
+        /// or status
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Episodes to show (tables default to 50; other outputs to all)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+
+        /// Skip this many episodes, for paging
+        #[arg(long, default_value = "0")]
+        offset: usize,
     },
 
     /// Fetch new episodes, download, transcribe, and summarize
@@ -54,13 +112,65 @@ pub enum Command {
         #[arg(long)]
         download_only: bool,
 
-        /// Force re-transcribe and re-summarize (delete old results)
+        /// Force re-transcribe and re-summarize (earlier summaries are kept as versions)
         #[arg(long)]
         redo: bool,
 
+        /// Generate a new summary version from the existing transcript
+        #[arg(long)]
+        resummarize: bool,
+
+        /// Override the summarization model for this run
+        #[arg(long)]
+        model: Option<String>,
+
         /// CPU usage percentage for transcription (1-100)
         #[arg(long)]
         cpu: Option<u32>,
+
+        /// Summary style (e.g. brief, detailed, bullet-points, or a user template)
+        #[arg(long)]
+        style: Option<String>,
+
+        /// Language to write summaries in (e.g. en, zh-TW)
+        #[arg(long)]
+        lang: Option<String>,
+
+        /// Also retry failed episodes whose backoff has passed
+        #[arg(long, conflicts_with = "episode")]
+        retry_failed: bool,
+
+        /// Keep running and sync each podcast on its check interval
+        #[arg(long, conflicts_with_all = ["name", "episode", "redo", "resummarize"])]
+        watch: bool,
+
+        /// Default check interval for --watch (e.g. 30m, 1h); overrides general.check_interval_hours
+        #[arg(long, requires = "watch")]
+        interval: Option<String>,
+    },
+
+    /// Choose which episodes sync processes, and in what order
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
+
+    /// Keep episodes out of the queue; sync ignores them
+    Skip {
+        #[command(flatten)]
+        selection: EpisodeSelection,
+    },
+
+    /// Undo `skip` and queue the episodes again
+    Unskip {
+        #[command(flatten)]
+        selection: EpisodeSelection,
+    },
+
+    /// Check a podcast's include/exclude filters
+    Filter {
+        #[command(subcommand)]
+        action: FilterAction,
     },
 
     /// Show an episode's summary or transcript
@@ -71,6 +181,201 @@ pub enum Command {
         /// Show transcript instead of summary
         #[arg(short, long)]
         transcript: bool,
+
+        /// Show a specific summary version (1 = oldest)
+        #[arg(long, conflicts_with = "model")]
+        version: Option<i64>,
+
+        /// Show the latest summary generated by this model
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Show the latest summary written in this language
+        #[arg(long, conflicts_with_all = ["version", "model"])]
+        lang: Option<String>,
+
+        /// List all summary versions
+        #[arg(long)]
+        versions: bool,
+
+        /// Diff the shown summary against another version
+        #[arg(long, value_name = "VERSION")]
+        diff: Option<i64>,
+    },
+
+    /// List summary styles, or print one style's template
+    Styles {
+        /// Style name to print
+        name: Option<String>,
+    },
+
+    /// Full-text search across episode titles, descriptions, summaries and transcripts
+    Search {
+        /// Words to search for; episodes must contain all of them
+        #[arg(required_unless_present = "reindex")]
+        query: Vec<String>,
+
+        /// Only search one podcast (ID or partial name)
+        #[arg(short, long)]
+        podcast: Option<String>,
+
+        /// Only episodes published on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only episodes published on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only episodes with this tag (their own or their podcast's)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Rank transcript passages by meaning (embeddings) instead of keywords
+        #[arg(long)]
+        semantic: bool,
+
+        /// Rebuild the search index (e.g. after editing transcript files); with
+        /// --semantic, also embed transcripts that have no embeddings yet
+        #[arg(long)]
+        reindex: bool,
+    },
+
+    /// Answer a question from transcript passages, citing the episodes used
+    Ask {
+        /// The question
+        #[arg(required = true)]
+        question: Vec<String>,
+
+        /// Only use one podcast (ID or partial name)
+        #[arg(short, long)]
+        podcast: Option<String>,
+
+        /// Only episodes published on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only episodes published on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only episodes with this tag (their own or their podcast's)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Number of passages to send to the model
+        #[arg(short = 'n', long, default_value = "8")]
+        passages: usize,
+    },
+
+    /// Combine recent summaries from all podcasts into one digest
+    Digest {
+        /// Window to cover: a span like 24h, 7d or 2w, or a date (YYYY-MM-DD)
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, ignore_case = true, default_value_t = DigestFormat::Markdown)]
+        format: DigestFormat,
+
+        /// Digest language (e.g. "en", "zh-TW"); defaults to summarization.summary_language
+        #[arg(long)]
+        lang: Option<String>,
+
+        /// Only episodes with this tag (their own or their podcast's)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Write the digest to a file instead of stdout
+        #[arg(short = 'o', long = "out", value_name = "FILE")]
+        out: Option<String>,
+    },
+
+    /// Write summaries to files: markdown, a static html site, or an obsidian vault
+    Export {
+        /// Directory to write to; re-exporting only rewrites files that changed
+        dir: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, ignore_case = true, default_value_t)]
+        format: ExportFormat,
+
+        /// Only export one podcast (ID or partial name)
+        #[arg(short, long)]
+        podcast: Option<String>,
+
+        /// Only export one episode
+        #[arg(short, long, conflicts_with_all = ["podcast", "tag"])]
+        episode: Option<i64>,
+
+        /// Only episodes with this tag (their own or their podcast's)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Include transcripts below the summaries
+        #[arg(short, long)]
+        transcripts: bool,
+    },
+
+    /// Write an RSS feed of the latest summaries, for reading in a feed reader
+    Rss {
+        /// Only one podcast's summaries (ID or partial name)
+        #[arg(short, long)]
+        podcast: Option<String>,
+
+        /// Number of summaries in the feed, newest first
+        #[arg(short = 'n', long, default_value = "50")]
+        limit: usize,
+
+        /// Link for the feed itself; required without --podcast, whose feed
+        /// defaults to the podcast's website
+        #[arg(long, required_unless_present = "podcast")]
+        link: Option<String>,
+
+        /// Write the feed to a file instead of stdout
+        #[arg(short = 'o', long = "out", value_name = "FILE")]
+        out: Option<String>,
+    },
+
+    /// Play an episode, following along in its transcript
+    Play {
+        /// Episode ID
+        episode_id: i64,
+
+        /// Start here: h:mm:ss, m:ss or seconds
+        #[arg(long, value_name = "TIME")]
+        at: Option<String>,
+
+        /// Player command reading raw samples from stdin ({rate} = sample rate),
+        /// or "null" to follow the transcript without sound
+        #[arg(long, value_name = "CMD")]
+        player: Option<String>,
+    },
+
+    /// Serve a web UI and JSON API for browsing summaries and starting syncs
+    Serve {
+        /// Address to listen on (default: server.bind, 127.0.0.1:8080)
+        #[arg(long, value_name = "ADDR")]
+        bind: Option<String>,
+    },
+
+    /// Browse podcasts, summaries and transcripts, and run syncs, in the terminal
+    Tui,
+
+    /// List topics across summaries, or the episodes that cover one
+    Topics {
+        /// Topic to look up (case-insensitive substring)
+        topic: Option<String>,
+    },
+
+    /// Tag podcasts and episodes, or list tags
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
     },
 
     /// Show or update configuration
@@ -80,6 +385,116 @@ pub enum Command {
     },
 }
 
+impl Command {
+    /// Whether the command prints records that `--output` can format.
+    pub fn supports_output(&self) -> bool {
+        match self {
+            Self::List { .. } | Self::Show { .. } | Self::Search { .. } | Self::Topics { .. } => {
+                true
+            }
+            Self::Queue { action } => matches!(action, QueueAction::List),
+            Self::Tag { action } => matches!(action, TagAction::List { .. }),
+            Self::Config { action } => matches!(action, Some(ConfigAction::Show) | None),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum QueueAction {
+    /// Queue episodes for the next sync
+    Add {
+        #[command(flatten)]
+        selection: EpisodeSelection,
+
+        /// Higher priorities are processed first (default 0)
+        #[arg(long, allow_negative_numbers = true)]
+        priority: Option<i64>,
+    },
+    /// Take episodes out of the queue
+    Remove {
+        #[command(flatten)]
+        selection: EpisodeSelection,
+    },
+    /// Show queued episodes in processing order
+    List,
+    /// Empty the queue
+    Clear {
+        /// Only clear one podcast's episodes (ID or partial name)
+        #[arg(short, long)]
+        podcast: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// Add a tag to episodes, or to a podcast and all its episodes
+    Add {
+        /// Tag name
+        tag: String,
+
+        #[command(flatten)]
+        target: TagTarget,
+    },
+    /// Remove a tag from episodes or a podcast
+    Remove {
+        /// Tag name
+        tag: String,
+
+        #[command(flatten)]
+        target: TagTarget,
+    },
+    /// List tags, or the podcasts and episodes carrying one
+    List {
+        /// Tag to look up
+        tag: Option<String>,
+    },
+}
+
+/// What `tag add` and `tag remove` apply to.
+#[derive(Args, Debug)]
+#[group(required = true, multiple = true)]
+pub struct TagTarget {
+    /// Episode IDs
+    pub ids: Vec<i64>,
+
+    /// A podcast (ID or partial name); its tags apply to all its episodes
+    #[arg(short, long)]
+    pub podcast: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum FilterAction {
+    /// Dry-run a podcast's filters over its latest episodes
+    Test {
+        /// Podcast ID or name (partial match)
+        podcast: String,
+
+        /// Number of episodes to check, newest first
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+/// Episodes picked by ID, or in bulk by podcast and publication date.
+#[derive(Args, Debug)]
+pub struct EpisodeSelection {
+    /// Episode IDs
+    pub ids: Vec<i64>,
+
+    /// All episodes of a podcast (ID or partial name)
+    #[arg(short, long)]
+    pub podcast: Option<String>,
+
+    /// Episodes published on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Episodes published on or before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub until: Option<String>,
+}
+
 #[derive(Subcommand)]
 pub enum ConfigAction {
     /// Show current configuration
@@ -88,9 +503,49 @@ pub enum ConfigAction {
     Path,
     /// Set a configuration value
     Set {
-        /// Config key (e.g. cpu_percent, whisper_model, language, initial_prompt)
+        /// Config key (e.g. cpu_percent, whisper_model, language, initial_prompt, style, summary_language)
         key: String,
         /// Value to set
         value: String,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(args: &[&str]) -> Command {
+        Cli::try_parse_from([&["podcast-summarize"], args].concat())
+            .unwrap()
+            .command
+    }
+
+    #[test]
+    fn output_only_applies_to_commands_printing_records() {
+        assert!(command(&["list", "--output", "json"]).supports_output());
+        assert!(command(&["queue", "list"]).supports_output());
+        assert!(command(&["config"]).supports_output());
+        assert!(!command(&["queue", "clear"]).supports_output());
+        assert!(!command(&["sync"]).supports_output());
+        assert!(Cli::try_parse_from(["podcast-summarize", "list", "--output", "xml"]).is_err());
+    }
+
+    #[test]
+    fn formats_are_checked_while_parsing() {
+        assert!(matches!(
+            command(&["digest", "--format", "HTML"]),
+            Command::Digest {
+                format: DigestFormat::Html,
+                ..
+            }
+        ));
+        assert!(matches!(
+            command(&["export", "out", "-f", "md"]),
+            Command::Export {
+                format: ExportFormat::Markdown,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["podcast-summarize", "export", "out", "-f", "pdf"]).is_err());
+    }
+}
@@ -2,9 +2,13 @@ use anyhow::Result;
 
 use crate::config::AppConfig;
 use crate::db::Database;
-use crate::feed;
+use crate::feed::{self, Backfill};
+use crate::filter::Policy;
+use crate::models::{EpisodeStatus, JobStage};
+use crate::pipeline;
+use crate::prompts::SummaryOptions;
 
-pub async fn run(url: &str, config: &AppConfig) -> Result<()> {
+pub async fn run(url: &str, backfill: Backfill, config: &AppConfig) -> Result<()> {
     let db = Database::open(&config.db_path()?)?;
     let client = reqwest::Client::new();
 
@@ -24,10 +28,23 @@ pub async fn run(url: &str, config: &AppConfig) -> Result<()> {
         feed_info.description.as_deref(),
     )?;
 
-    // Insert all discovered episodes
+    // Don't leave a subscription behind whose episodes were never recorded
+    let policy = match Policy::for_podcast(config, &podcast) {
+        Ok(policy) => policy,
+        Err(e) => {
+            db.delete_podcast(podcast.id)?;
+            return Err(e);
+        }
+    };
+
+    // Record the whole back catalogue, but only queue what the backfill and the
+    // podcast's filters select; the rest stays skipped until queued by hand
+    let selected = backfill.select(&feed_info.entries);
+    let now = chrono::Utc::now();
     let mut count = 0;
-    for entry in &feed_info.entries {
-        db.insert_episode(
+    let mut queued = 0;
+    for (entry, selected) in feed_info.entries.iter().zip(selected) {
+        let id = db.insert_episode(
             podcast.id,
             &entry.guid,
             &entry.title,
@@ -36,6 +53,38 @@ pub async fn run(url: &str, config: &AppConfig) -> Result<()> {
             entry.published_at,
             entry.duration_secs,
         )?;
+        // Feeds occasionally repeat a guid; insert_episode returns 0 for those
+        if id == 0 {
+            continue;
+        }
+        let reason = if selected {
+            policy.skip_reason(
+                &entry.title,
+                entry.description.as_deref(),
+                entry.published_at,
+                now,
+            )
+        } else {
+            Some(backfill.skip_reason().to_string())
+        };
+        match reason {
+            None => {
+                pipeline::enqueue(
+                    &db,
+                    id,
+                    JobStage::Summarize,
+                    false,
+                    &SummaryOptions::default(),
+                )?;
+                queued += 1;
+            }
+            Some(reason) => db.update_episode_status(
+                id,
+                &EpisodeStatus::Skipped {
+                    reason: Some(reason),
+                },
+            )?,
+        }
         count += 1;
     }
 
@@ -44,7 +93,7 @@ pub async fn run(url: &str, config: &AppConfig) -> Result<()> {
     if let Some(ref url) = podcast.website_url {
         println!("  Website: {url}");
     }
-    println!("  Episodes: {count}");
+    println!("  Episodes: {count} ({queued} queued for the next sync)");
     if let Some(latest) = feed_info.entries.first() {
         let date = latest
             .published_at
@@ -52,6 +101,9 @@ pub async fn run(url: &str, config: &AppConfig) -> Result<()> {
             .unwrap_or_else(|| "unknown".to_string());
         println!("  Latest: \"{}\" ({date})", latest.title);
     }
+    if queued < count {
+        println!("  Older episodes are skipped; process one with: podcast-summarize sync -e <ID>");
+    }
     println!();
 
     Ok(())
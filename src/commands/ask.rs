@@ -0,0 +1,65 @@
+use anyhow::Result;
+
+use crate::ask;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::embed::format_timestamp;
+
+use super::search::{SearchOptions, semantic_search};
+
+pub async fn run(question: &str, options: &SearchOptions, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    let filter = options.resolve(&db)?;
+    let client = reqwest::Client::new();
+    let api_key = config.api_key()?;
+
+    let passages = semantic_search(&db, &client, question, filter, options.limit, config).await?;
+    if passages.is_empty() {
+        println!("No transcript passages match the filters.");
+        return Ok(());
+    }
+
+    let result = ask::answer(
+        &client,
+        &config.summarization.api_base_url,
+        &api_key,
+        &config.summarization.model,
+        config.summarization.max_tokens,
+        question,
+        &passages,
+    )
+    .await?;
+
+    println!();
+    for line in result.content.lines() {
+        println!("  {line}");
+    }
+    println!();
+
+    let cited = ask::cited_episodes(&result.content);
+    if !cited.is_empty() {
+        println!("  Sources:");
+        for id in cited {
+            // Only list ids that were actually among the passages
+            let hits: Vec<_> = passages.iter().filter(|p| p.episode_id == id).collect();
+            let Some(first) = hits.first() else {
+                continue;
+            };
+            let times: Vec<String> = hits
+                .iter()
+                .filter_map(|p| p.start_secs.map(format_timestamp))
+                .collect();
+            let at = if times.is_empty() {
+                String::new()
+            } else {
+                format!(" @ {}", times.join(", "))
+            };
+            println!(
+                "    [#{id}] {} - {}{at}",
+                first.episode_title, first.podcast_title
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
@@ -39,6 +39,34 @@ fn validate_and_apply(config: &mut AppConfig, key: &str, value: &str) -> Result<
         "model" => {
             config.summarization.model = value.to_string();
         }
+        "style" => {
+            crate::prompts::find_template(value)?;
+            config.summarization.style = Some(value.to_string());
+        }
+        "summary_language" => {
+            config.summarization.summary_language = Some(value.to_string());
+        }
+        "json_mode" => {
+            let v: bool = value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Expected true or false"))?;
+            config.summarization.json_mode = v;
+        }
+        "embeddings_enabled" => {
+            let v: bool = value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Expected true or false"))?;
+            config.embeddings.enabled = v;
+        }
+        "embedding_model" => {
+            config.embeddings.model = value.to_string();
+        }
+        "embedding_api_base_url" => {
+            config.embeddings.api_base_url = Some(value.to_string());
+        }
+        "embedding_api_key_env" => {
+            config.embeddings.api_key_env = Some(value.to_string());
+        }
         "max_tokens" => {
             let v: u32 = value
                 .parse()
@@ -57,6 +85,12 @@ fn validate_and_apply(config: &mut AppConfig, key: &str, value: &str) -> Result<
                 .map_err(|_| anyhow::anyhow!("Invalid number"))?;
             config.transcription.chunk_minutes = v;
         }
+        "server_bind" => {
+            config.server.bind = value.to_string();
+        }
+        "server_token_env" => {
+            config.server.token_env = Some(value.to_string());
+        }
         "chinese_conversion" => {
             let valid = [
                 "s2t", "s2tw", "s2twp", "s2hk", "t2s", "tw2s", "tw2sp", "hk2s", "t2tw", "t2hk",
@@ -72,7 +106,7 @@ fn validate_and_apply(config: &mut AppConfig, key: &str, value: &str) -> Result<
         }
         _ => {
             anyhow::bail!(
-                "Unknown config key: {key}\n\nAvailable keys:\n  cpu_percent, whisper_model, language, initial_prompt, chinese_conversion, chunk_minutes,\n  api_base_url, api_key_env, model, max_tokens, auto_cleanup_audio"
+                "Unknown config key: {key}\n\nAvailable keys:\n  cpu_percent, whisper_model, language, initial_prompt, chinese_conversion, chunk_minutes,\n  api_base_url, api_key_env, model, max_tokens, style, summary_language, json_mode,\n  embeddings_enabled, embedding_model, embedding_api_base_url, embedding_api_key_env, auto_cleanup_audio,\n  server_bind, server_token_env"
             );
         }
     }
@@ -147,6 +181,20 @@ mod tests {
         assert_eq!(c.summarization.model, "gpt-4o");
     }
 
+    #[test]
+    fn style_builtin_valid() {
+        let mut c = default_config();
+        validate_and_apply(&mut c, "style", "brief").unwrap();
+        assert_eq!(c.summarization.style.as_deref(), Some("brief"));
+    }
+
+    #[test]
+    fn style_unknown_fails() {
+        let mut c = default_config();
+        assert!(validate_and_apply(&mut c, "style", "no-such-style-xyz").is_err());
+        assert!(c.summarization.style.is_none());
+    }
+
     #[test]
     fn max_tokens_valid() {
         let mut c = default_config();
@@ -160,6 +208,33 @@ mod tests {
         assert!(validate_and_apply(&mut c, "max_tokens", "not_a_number").is_err());
     }
 
+    #[test]
+    fn json_mode_bool() {
+        let mut c = default_config();
+        validate_and_apply(&mut c, "json_mode", "false").unwrap();
+        assert!(!c.summarization.json_mode);
+        assert!(validate_and_apply(&mut c, "json_mode", "nope").is_err());
+    }
+
+    #[test]
+    fn embedding_keys() {
+        let mut c = default_config();
+        validate_and_apply(&mut c, "embeddings_enabled", "true").unwrap();
+        validate_and_apply(&mut c, "embedding_model", "nomic-embed-text").unwrap();
+        validate_and_apply(
+            &mut c,
+            "embedding_api_base_url",
+            "http://localhost:11434/v1",
+        )
+        .unwrap();
+        assert!(c.embeddings.enabled);
+        assert_eq!(c.embeddings.model, "nomic-embed-text");
+        assert_eq!(
+            c.embeddings.api_base_url.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+    }
+
     #[test]
     fn unknown_key_fails() {
         let mut c = default_config();
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::digest::{self, DigestEntry, DigestFormat, DigestGroups};
+use crate::language::SummaryLanguage;
+use crate::{dates, summarize};
+
+pub async fn run(
+    since: &str,
+    format: DigestFormat,
+    language: Option<&str>,
+    tag: Option<&str>,
+    output: Option<&str>,
+    config: &AppConfig,
+) -> Result<()> {
+    let now = Utc::now();
+    let since = dates::parse_since(since, now)?;
+    let language = language
+        .map(String::from)
+        .or_else(|| config.summarization.summary_language.clone())
+        .map(|l| SummaryLanguage::parse(&l));
+
+    let db = Database::open(&config.db_path()?)?;
+    let tagged = tag
+        .map(|name| crate::tags::resolve(&db, name).and_then(|id| db.tagged_episode_ids(id)))
+        .transpose()?;
+    let mut groups = DigestGroups::new();
+    for summary in db.list_summaries_since(since)? {
+        if tagged
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(&summary.episode_id))
+        {
+            continue;
+        }
+        let episode = db.get_episode(summary.episode_id)?;
+        let podcast = db.get_podcast(episode.podcast_id)?;
+        groups.entry(podcast.title).or_default().push(DigestEntry {
+            episode_id: episode.id,
+            episode_title: episode.title,
+            published_at: episode.published_at,
+            summary: summary.content,
+        });
+    }
+
+    let count: usize = groups.values().map(Vec::len).sum();
+    if count == 0 {
+        let tagged = tag.map(|t| format!(" tagged \"{t}\"")).unwrap_or_default();
+        println!(
+            "No summaries{tagged} created since {}.",
+            since.format("%Y-%m-%d %H:%M")
+        );
+        return Ok(());
+    }
+    eprintln!(
+        "Writing digest of {count} episode(s) from {} podcast(s)...",
+        groups.len()
+    );
+
+    let api_key = config.api_key()?;
+    let result = summarize::chat_completion(
+        &reqwest::Client::new(),
+        &config.summarization.api_base_url,
+        &api_key,
+        &config.summarization.model,
+        config.summarization.max_tokens,
+        &digest::system_prompt(format, language.as_ref()),
+        &digest::user_message(&groups, since, now),
+    )
+    .await?;
+    let body = match language {
+        Some(ref lang) => lang.localize(&result.content)?,
+        None => result.content,
+    };
+
+    let title = format!(
+        "Podcast digest: {} to {}",
+        since.format("%Y-%m-%d"),
+        now.format("%Y-%m-%d")
+    );
+    let document = digest::render(format, &title, &body, &groups);
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, &document)?;
+            eprintln!("Digest written to {path}");
+        }
+        None => print!("{document}"),
+    }
+    Ok(())
+}
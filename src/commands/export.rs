@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::config::AppConfig;
+use crate::db::{Database, EpisodeQuery};
+use crate::export::{self, ExportEntry, ExportFormat};
+use crate::models::Episode;
+use crate::transcribe;
+
+/// What `export` writes, as given on the command line.
+#[derive(Debug, Default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub podcast: Option<String>,
+    pub episode: Option<i64>,
+    pub tag: Option<String>,
+    pub transcripts: bool,
+}
+
+pub fn run(dir: &str, options: &ExportOptions, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    export(&db, dir, options)
+}
+
+fn export(db: &Database, dir: &str, options: &ExportOptions) -> Result<()> {
+    let episodes = match options.episode {
+        Some(id) => vec![
+            db.get_episode(id)
+                .map_err(|_| anyhow::anyhow!("Episode #{id} not found"))?,
+        ],
+        None => {
+            let filter = super::search::SearchOptions {
+                podcast: options.podcast.clone(),
+                tag: options.tag.clone(),
+                ..Default::default()
+            }
+            .resolve(db)?;
+            db.query_episodes(&EpisodeQuery {
+                podcast_id: filter.podcast_id,
+                tag_id: filter.tag_id,
+                ..Default::default()
+            })?
+        }
+    };
+
+    let entries = load_entries(db, episodes, options.transcripts)?;
+    if entries.is_empty() {
+        println!("No summarized episodes to export.");
+        return Ok(());
+    }
+
+    // Indexes always list every summarized episode, so exporting a subset
+    // into an earlier export doesn't drop the rest from them
+    let filtered = options.episode.is_some() || options.podcast.is_some() || options.tag.is_some();
+    let all;
+    let indexed = if filtered {
+        all = load_entries(db, db.query_episodes(&EpisodeQuery::default())?, false)?;
+        &all
+    } else {
+        &entries
+    };
+
+    let files = export::render(options.format, &entries, indexed);
+    let (written, unchanged) = write_files(Path::new(dir), &files)?;
+    println!(
+        "Exported {} episode(s) to {dir}: {written} file(s) written, {unchanged} unchanged.",
+        entries.len()
+    );
+    Ok(())
+}
+
+/// The summarized ones of `episodes`, with their transcripts if asked for.
+fn load_entries(
+    db: &Database,
+    episodes: Vec<Episode>,
+    transcripts: bool,
+) -> Result<Vec<ExportEntry>> {
+    let mut entries = Vec::new();
+    for episode in episodes {
+        let Some(summary) = db.get_summary_by_episode(episode.id)? else {
+            continue;
+        };
+        let topics = db
+            .get_summary_sections(summary.id)?
+            .map(|s| s.topics)
+            .unwrap_or_default();
+        let transcript = match episode.transcript_path {
+            Some(ref path) if transcripts && Path::new(path).exists() => {
+                Some(transcribe::load_transcript(Path::new(path))?.text)
+            }
+            _ => None,
+        };
+        entries.push(ExportEntry {
+            podcast: db.get_podcast(episode.podcast_id)?,
+            tags: db.episode_tag_names(episode.id)?,
+            episode,
+            summary,
+            topics,
+            transcript,
+        });
+    }
+    Ok(entries)
+}
+
+/// Write each file whose content differs from what is on disk, so re-running
+/// an export only touches what changed. Returns (written, unchanged).
+fn write_files(dir: &Path, files: &[export::ExportFile]) -> Result<(usize, usize)> {
+    let mut written = 0;
+    let mut unchanged = 0;
+    for file in files {
+        let path = dir.join(&file.path);
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == file.content) {
+            unchanged += 1;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, &file.content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written += 1;
+    }
+    Ok((written, unchanged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn write_files_skips_unchanged() {
+        let dir = std::env::temp_dir().join(format!("podsum-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let files = vec![
+            export::ExportFile {
+                path: PathBuf::from("a/one.md"),
+                content: "one".to_string(),
+            },
+            export::ExportFile {
+                path: PathBuf::from("two.md"),
+                content: "two".to_string(),
+            },
+        ];
+        assert_eq!(write_files(&dir, &files).unwrap(), (2, 0));
+        assert_eq!(write_files(&dir, &files).unwrap(), (0, 2));
+
+        let mut changed = files.clone();
+        changed[1].content = "two, edited".to_string();
+        assert_eq!(write_files(&dir, &changed).unwrap(), (1, 1));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("two.md")).unwrap(),
+            "two, edited"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exporting_one_episode_keeps_the_indexes_whole() {
+        let db = Database::open_in_memory().unwrap();
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        for (guid, title) in [("g1", "First"), ("g2", "Second")] {
+            let ep = db
+                .insert_episode(p.id, guid, title, None, "https://ex.com/e.mp3", None, None)
+                .unwrap();
+            db.insert_summary(ep, "Summary.", "model", None, None, None, None, None)
+                .unwrap();
+        }
+        let dir = std::env::temp_dir().join(format!("podsum-export-one-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let out = dir.to_str().unwrap();
+
+        let mut options = ExportOptions {
+            format: ExportFormat::Html,
+            ..Default::default()
+        };
+        export(&db, out, &options).unwrap();
+        options.episode = Some(1);
+        export(&db, out, &options).unwrap();
+
+        let index = std::fs::read_to_string(dir.join("pod/index.html")).unwrap();
+        assert!(index.contains(">First</a>"), "{index}");
+        assert!(index.contains(">Second</a>"), "{index}");
+        let top = std::fs::read_to_string(dir.join("index.html")).unwrap();
+        assert!(top.contains("(2 episodes)"), "{top}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::filter::Policy;
+
+use super::list::truncate;
+
+/// Show what sync would do with a podcast's latest episodes under its current
+/// filters, without changing anything.
+pub fn test(name: &str, limit: usize, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    let podcast = if let Ok(id) = name.parse::<i64>() {
+        db.get_podcast(id).ok()
+    } else {
+        db.find_podcast_by_name(name)?
+    }
+    .ok_or_else(|| anyhow::anyhow!("No podcast matching \"{name}\" found"))?;
+
+    let policy = Policy::for_podcast(config, &podcast)?;
+    let podcast_config = config.podcast_config(&podcast)?;
+    println!();
+    println!("  {}", podcast.title);
+    for (label, patterns) in [
+        ("Include", &podcast_config.include),
+        ("Exclude", &podcast_config.exclude),
+    ] {
+        if let Some(patterns) = patterns.as_ref().filter(|p| !p.is_empty()) {
+            let patterns: Vec<String> = patterns.iter().map(|p| format!("`{p}`")).collect();
+            println!("  {label}: {}", patterns.join(", "));
+        }
+    }
+    if let Some(age) = config.max_episode_age(&podcast)? {
+        println!("  Max age: {} days", age.num_days());
+    }
+    if policy.is_empty() {
+        println!("  No filters: every new episode is processed.");
+    }
+    println!("  {}", "─".repeat(50));
+
+    let now = Utc::now();
+    let episodes = db.list_episodes(podcast.id)?;
+    let mut kept = 0;
+    for ep in episodes.iter().take(limit) {
+        let verdict =
+            match policy.skip_reason(&ep.title, ep.description.as_deref(), ep.published_at, now) {
+                None => {
+                    kept += 1;
+                    "queue".to_string()
+                }
+                Some(reason) => format!("skip   {reason}"),
+            };
+        println!("  #{:<5} {:<40} {verdict}", ep.id, truncate(&ep.title, 40));
+    }
+    println!();
+    println!(
+        "  {kept} of {} episode(s) would be queued.",
+        episodes.len().min(limit)
+    );
+    println!();
+    Ok(())
+}
@@ -1,100 +1,269 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 
 use crate::config::AppConfig;
+use crate::dates;
 use crate::db::Database;
+use crate::models::{Episode, EpisodeStatus, JobStage, Podcast};
+use crate::output::{self, OutputFormat};
+use crate::query::ListOptions;
 
-pub fn run(name: Option<&str>, config: &AppConfig) -> Result<()> {
+/// Episodes per page in table output when `--limit` isn't given.
+const PAGE_SIZE: usize = 50;
+
+/// A subscription with its episode counts, for `--output`.
+#[derive(Serialize)]
+struct PodcastRow<'a> {
+    #[serde(flatten)]
+    podcast: &'a Podcast,
+    episodes: i64,
+    new: i64,
+}
+
+/// A failed episode with its retry state, for `--output`.
+#[derive(Serialize)]
+struct FailedRow<'a> {
+    #[serde(flatten)]
+    episode: &'a Episode,
+    podcast_title: String,
+    attempts: i64,
+    last_attempt_at: Option<DateTime<Utc>>,
+    retry_at: Option<DateTime<Utc>>,
+    resumes_at: JobStage,
+}
+
+pub fn run(
+    name: Option<&str>,
+    failed: bool,
+    options: &ListOptions,
+    format: OutputFormat,
+    config: &AppConfig,
+) -> Result<()> {
     let db = Database::open(&config.db_path()?)?;
+    let podcast = name
+        .map(|name| {
+            if let Ok(id) = name.parse::<i64>() {
+                db.get_podcast(id).ok()
+            } else {
+                db.find_podcast_by_name(name)?
+            }
+            .ok_or_else(|| anyhow::anyhow!("No podcast matching \"{name}\" found"))
+        })
+        .transpose()?;
 
-    if let Some(name) = name {
-        // Show episodes for a specific podcast (by ID or name)
-        let podcast = if let Ok(id) = name.parse::<i64>() {
-            db.get_podcast(id).ok()
-        } else {
-            db.find_podcast_by_name(name)?
-        }
-        .ok_or_else(|| anyhow::anyhow!("No podcast matching \"{name}\" found"))?;
+    if failed {
+        let mut query = options.query(&db, podcast.as_ref().map(|p| p.id))?;
+        query.statuses = vec!["failed".to_string()];
+        return list_failed(&db, &db.query_episodes(&query)?, format);
+    }
 
-        let episodes = db.list_episodes(podcast.id)?;
-        println!();
-        println!("  {} ({} episodes)", podcast.title, episodes.len());
-        println!("  {}", "─".repeat(50));
+    match podcast {
+        Some(ref podcast) => list_episodes(&db, Some(podcast), options, format),
+        None if options.selects_episodes() => list_episodes(&db, None, options, format),
+        None => list_podcasts(&db, format),
+    }
+}
+
+/// One podcast's episodes, or with `podcast` unset, episodes across all
+/// podcasts. Tables show one page at a time.
+fn list_episodes(
+    db: &Database,
+    podcast: Option<&Podcast>,
+    options: &ListOptions,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut query = options.query(db, podcast.map(|p| p.id))?;
+    if format.is_table() && query.limit.is_none() {
+        query.limit = Some(PAGE_SIZE);
+    }
+    let episodes = db.query_episodes(&query)?;
+    if !format.is_table() {
+        return output::print_records(format, &episodes);
+    }
+    let total = db.count_episodes(&query)?;
+
+    println!();
+    match podcast {
+        Some(p) => println!("  {} ({total} episodes)", p.title),
+        None => println!("  All podcasts ({total} episodes)"),
+    }
+    println!("  {}", "─".repeat(if podcast.is_some() { 50 } else { 72 }));
 
-        for ep in &episodes {
-            let date = ep
-                .published_at
-                .map(|d| d.format("%Y-%m-%d").to_string())
-                .unwrap_or_else(|| "          ".to_string());
+    for ep in &episodes {
+        let date = ep
+            .published_at
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "          ".to_string());
 
-            let duration = ep.duration_secs.map(format_duration).unwrap_or_default();
+        let duration = ep
+            .duration_secs
+            .map(dates::format_duration)
+            .unwrap_or_default();
 
-            let status = match &ep.status {
-                crate::models::EpisodeStatus::New => "[new]",
-                crate::models::EpisodeStatus::Downloaded => "[dl]",
-                crate::models::EpisodeStatus::Transcribed => "[txt]",
-                crate::models::EpisodeStatus::Summarized => "[done]",
-                crate::models::EpisodeStatus::Failed(_) => "[err]",
-            };
+        let status = ep.status.badge();
 
+        // Say which policy or filter rule left a skipped episode out
+        let note = match &ep.status {
+            EpisodeStatus::Skipped {
+                reason: Some(reason),
+            } => format!(" {}", truncate(reason, 40)),
+            _ => String::new(),
+        };
+
+        if podcast.is_some() {
             println!(
-                "  #{:<5} {:<40} {} {:>6} {}",
+                "  #{:<5} {:<40} {} {:>6} {}{}",
                 ep.id,
                 truncate(&ep.title, 40),
                 date,
                 duration,
                 status,
+                note,
+            );
+        } else {
+            let podcast = db.get_podcast(ep.podcast_id)?;
+            println!(
+                "  #{:<5} {:<36} {:<20} {} {:>6} {}{}",
+                ep.id,
+                truncate(&ep.title, 36),
+                truncate(&podcast.title, 20),
+                date,
+                duration,
+                status,
+                note,
             );
         }
+    }
+    if episodes.len() < total {
         println!();
-    } else {
-        // Show all podcasts
-        let podcasts = db.list_podcasts()?;
-        if podcasts.is_empty() {
-            println!("No subscriptions yet. Add one with: podcast-summarize add <RSS_URL>");
-            return Ok(());
-        }
+        println!(
+            "  Showing {}-{} of {total}. Page with --limit and --offset.",
+            (query.offset + 1).min(total),
+            query.offset + episodes.len()
+        );
+    }
+    println!();
+    Ok(())
+}
+
+/// All subscriptions with episode counts.
+fn list_podcasts(db: &Database, format: OutputFormat) -> Result<()> {
+    let podcasts = db.list_podcasts()?;
+    if !format.is_table() {
+        let rows = podcasts
+            .iter()
+            .map(|p| {
+                Ok(PodcastRow {
+                    podcast: p,
+                    episodes: db.episode_count(p.id)?,
+                    new: db.episode_count_by_status(p.id, "new")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        return output::print_records(format, &rows);
+    }
+    if podcasts.is_empty() {
+        println!("No subscriptions yet. Add one with: podcast-summarize add <RSS_URL>");
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "  {:<4} {:<30} {:>8} {:>8} {:>12}",
+        "ID", "PODCAST", "EPISODES", "NEW", "LAST CHECKED"
+    );
+    println!("  {}", "─".repeat(66));
+
+    for p in &podcasts {
+        let total = db.episode_count(p.id)?;
+        let new = db.episode_count_by_status(p.id, "new")?;
+        let last_checked = p
+            .last_checked
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "never".to_string());
 
-        println!();
         println!(
             "  {:<4} {:<30} {:>8} {:>8} {:>12}",
-            "ID", "PODCAST", "EPISODES", "NEW", "LAST CHECKED"
+            p.id,
+            truncate(&p.title, 30),
+            total,
+            new,
+            last_checked,
         );
-        println!("  {}", "─".repeat(66));
-
-        for p in &podcasts {
-            let total = db.episode_count(p.id)?;
-            let new = db.episode_count_by_status(p.id, "new")?;
-            let last_checked = p
-                .last_checked
-                .map(|d| d.format("%Y-%m-%d").to_string())
-                .unwrap_or_else(|| "never".to_string());
+    }
+    println!();
+    Ok(())
+}
 
-            println!(
-                "  {:<4} {:<30} {:>8} {:>8} {:>12}",
-                p.id,
-                truncate(&p.title, 30),
-                total,
-                new,
-                last_checked,
-            );
+/// Failed episodes with a readable reason and when they will be retried.
+fn list_failed(db: &Database, episodes: &[Episode], format: OutputFormat) -> Result<()> {
+    if !format.is_table() {
+        let mut rows = Vec::new();
+        for ep in episodes {
+            let job = db.get_job(ep.id)?;
+            rows.push(FailedRow {
+                episode: ep,
+                podcast_title: db.get_podcast(ep.podcast_id)?.title,
+                attempts: job.as_ref().map_or(1, |j| j.attempts.max(1)),
+                last_attempt_at: job.as_ref().and_then(|j| j.last_attempt_at),
+                retry_at: job.as_ref().and_then(|j| j.next_run_at),
+                resumes_at: job.as_ref().map_or(ep.status.resume_stage(), |j| j.stage),
+            });
         }
-        println!();
+        return output::print_records(format, &rows);
+    }
+    if episodes.is_empty() {
+        println!("No failed episodes.");
+        return Ok(());
     }
 
+    println!();
+    for ep in episodes {
+        let EpisodeStatus::Failed { failure, .. } = &ep.status else {
+            continue;
+        };
+        let podcast = db.get_podcast(ep.podcast_id)?;
+        println!(
+            "  #{:<5} {} / {}",
+            ep.id,
+            truncate(&podcast.title, 30),
+            truncate(&ep.title, 40)
+        );
+
+        let job = db.get_job(ep.id)?;
+        let attempts = job.as_ref().map_or(1, |j| j.attempts.max(1));
+        let last = job
+            .as_ref()
+            .and_then(|j| j.last_attempt_at)
+            .map(|t| format!(", last {}", local_time(t)))
+            .unwrap_or_default();
+        let retry = match job.as_ref().and_then(|j| j.next_run_at) {
+            Some(t) => format!("retry after {}", local_time(t)),
+            None => "no automatic retry".to_string(),
+        };
+        println!(
+            "         {} ({attempts} attempt(s){last}; {retry}; resumes at {})",
+            failure.kind.describe(),
+            job.as_ref()
+                .map_or(ep.status.resume_stage(), |j| j.stage)
+                .as_str()
+        );
+        println!("         {}", truncate(failure.message.trim(), 100));
+    }
+    println!();
+    println!("  Retry with: podcast-summarize sync --retry-failed");
+    println!();
     Ok(())
 }
 
-fn format_duration(secs: i64) -> String {
-    let h = secs / 3600;
-    let m = (secs % 3600) / 60;
-    if h > 0 {
-        format!("{h}h{m:02}m")
-    } else {
-        format!("{m}m")
-    }
+fn local_time(t: DateTime<Utc>) -> String {
+    t.with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
 }
 
-fn truncate(s: &str, max: usize) -> String {
+pub fn truncate(s: &str, max: usize) -> String {
     if s.chars().count() <= max {
         s.to_string()
     } else {
@@ -107,24 +276,6 @@ fn truncate(s: &str, max: usize) -> String {
 mod tests {
     use super::*;
 
-    #[test]
-    fn format_duration_minutes_only() {
-        assert_eq!(format_duration(300), "5m");
-        assert_eq!(format_duration(59 * 60), "59m");
-    }
-
-    #[test]
-    fn format_duration_hours_and_minutes() {
-        assert_eq!(format_duration(3600), "1h00m");
-        assert_eq!(format_duration(3661), "1h01m");
-        assert_eq!(format_duration(7200 + 1800), "2h30m");
-    }
-
-    #[test]
-    fn format_duration_zero() {
-        assert_eq!(format_duration(0), "0m");
-    }
-
     #[test]
     fn truncate_short_string() {
         assert_eq!(truncate("hello", 10), "hello");
@@ -1,6 +1,19 @@
 pub mod add;
+pub mod ask;
 pub mod config_set;
+pub mod digest;
+pub mod export;
+pub mod filter;
 pub mod list;
+pub mod play;
+pub mod queue;
 pub mod remove;
+pub mod rss;
+pub mod search;
+pub mod serve;
 pub mod show;
+pub mod styles;
 pub mod sync;
+pub mod tag;
+pub mod topics;
+pub mod tui;
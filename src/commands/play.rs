@@ -0,0 +1,107 @@
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::audio::ChunkedAudioDecoder;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::embed::format_timestamp;
+use crate::player::{self, CommandSink, NullSink, Sink};
+use crate::{dates, download, transcribe};
+
+pub async fn run(
+    episode_id: i64,
+    at: Option<&str>,
+    player: Option<&str>,
+    config: &AppConfig,
+) -> Result<()> {
+    let at = at.map(dates::parse_timestamp).transpose()?.unwrap_or(0.0);
+    let db = Database::open(&config.db_path()?)?;
+    let episode = db
+        .get_episode(episode_id)
+        .map_err(|_| anyhow::anyhow!("Episode #{episode_id} not found"))?;
+    let podcast = db.get_podcast(episode.podcast_id)?;
+
+    // Audio is usually cleaned up after summarizing; fetch it again
+    let audio = match episode.audio_path.as_deref().map(PathBuf::from) {
+        Some(path) if path.exists() => path,
+        _ => {
+            println!("Downloading audio for \"{}\"...", episode.title);
+            let path = download::download_episode(
+                &reqwest::Client::new(),
+                &episode.audio_url,
+                &config.audio_dir()?,
+                episode.podcast_id,
+            )
+            .await?;
+            db.update_episode_audio_path(episode_id, &path.to_string_lossy())?;
+            path
+        }
+    };
+
+    let mut decoder = ChunkedAudioDecoder::open(&audio)?;
+    if let Some(total) = decoder.total_duration_secs()
+        && at >= total
+    {
+        anyhow::bail!(
+            "Episode #{episode_id} is only {} long",
+            format_timestamp(total)
+        );
+    }
+    let rate = decoder.source_rate();
+    decoder.set_output_rate(rate);
+    let start = if at > 0.0 { decoder.seek(at)? } else { 0.0 };
+    let mut sink: Box<dyn Sink> = match player.or(config.player.command.as_deref()) {
+        Some("null") => Box::new(NullSink::realtime(rate)),
+        configured => {
+            let command = player::find_player(configured).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No audio player found. Install ffplay, mpv, sox or aplay, or pass one with --player"
+                )
+            })?;
+            Box::new(CommandSink::spawn(&command, rate)?)
+        }
+    };
+
+    let segments = match episode.transcript_path {
+        Some(ref path) if Path::new(path).exists() => {
+            let segments = transcribe::load_transcript(Path::new(path))?.segments;
+            if segments.is_empty() {
+                println!("The transcript has no timestamps; playing audio only.");
+            }
+            segments
+        }
+        _ => {
+            println!("No transcript yet; playing audio only.");
+            Vec::new()
+        }
+    };
+
+    println!();
+    println!(
+        "  Playing \"{}\" - {} from {}{}",
+        episode.title,
+        podcast.title,
+        format_timestamp(start),
+        decoder
+            .total_duration_secs()
+            .map(|t| format!(" of {}", format_timestamp(t)))
+            .unwrap_or_default()
+    );
+    println!("  {}", "─".repeat(60));
+    let (bold, reset) = if std::io::stdout().is_terminal() {
+        ("\x1b[1;33m", "\x1b[0m")
+    } else {
+        ("", "")
+    };
+    player::play(&mut decoder, sink.as_mut(), start, &segments, |segment| {
+        println!(
+            "  {bold}{:>8}{reset}  {}",
+            format_timestamp(segment.start_secs),
+            segment.text.trim()
+        );
+    })?;
+    println!();
+    Ok(())
+}
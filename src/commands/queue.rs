@@ -0,0 +1,286 @@
+//! `queue`, `skip` and `unskip`: choosing which episodes sync processes. The
+//! queue is the pipeline's job table; sync only works on queued episodes.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::cli::{EpisodeSelection, QueueAction};
+use crate::config::AppConfig;
+use crate::db::{Database, EpisodeQuery};
+use crate::models::{Episode, EpisodeStatus};
+use crate::output::{self, OutputFormat};
+use crate::pipeline;
+
+use super::list::truncate;
+use super::search::SearchOptions;
+
+pub fn run(action: &QueueAction, format: OutputFormat, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    match action {
+        QueueAction::Add {
+            selection,
+            priority,
+        } => add(&db, selection, *priority),
+        QueueAction::Remove { selection } => remove(&db, selection),
+        QueueAction::List => list(&db, format),
+        QueueAction::Clear { podcast } => clear(&db, podcast.as_deref()),
+    }
+}
+
+pub fn skip(selection: &EpisodeSelection, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    skip_episodes(&db, selection)
+}
+
+pub fn unskip(selection: &EpisodeSelection, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    unskip_episodes(&db, selection)
+}
+
+/// The selected episodes, each with whether it was named by ID. Skipped
+/// episodes are only acted on in bulk by `unskip`; naming one always counts.
+fn select(db: &Database, selection: &EpisodeSelection) -> Result<Vec<(Episode, bool)>> {
+    let bulk =
+        selection.podcast.is_some() || selection.since.is_some() || selection.until.is_some();
+    if selection.ids.is_empty() && !bulk {
+        anyhow::bail!("Give episode IDs, or select episodes with --podcast, --since or --until");
+    }
+
+    let mut seen = HashSet::new();
+    let mut episodes = Vec::new();
+    for &id in &selection.ids {
+        let episode = db
+            .get_episode(id)
+            .map_err(|_| anyhow::anyhow!("Episode #{id} not found"))?;
+        if seen.insert(id) {
+            episodes.push((episode, true));
+        }
+    }
+    if bulk {
+        let filter = SearchOptions {
+            podcast: selection.podcast.clone(),
+            since: selection.since.clone(),
+            until: selection.until.clone(),
+            ..Default::default()
+        }
+        .resolve(db)?;
+        let query = EpisodeQuery {
+            podcast_id: filter.podcast_id,
+            since: filter.since,
+            until: filter.until,
+            ..Default::default()
+        };
+        for episode in db.query_episodes(&query)? {
+            if seen.insert(episode.id) {
+                episodes.push((episode, false));
+            }
+        }
+    }
+    Ok(episodes)
+}
+
+fn add(db: &Database, selection: &EpisodeSelection, priority: Option<i64>) -> Result<()> {
+    let mut queued = 0;
+    let mut updated = 0;
+    let mut summarized = 0;
+    let mut skipped = 0;
+    for (episode, named) in select(db, selection)? {
+        match episode.status {
+            EpisodeStatus::Summarized => {
+                summarized += 1;
+                continue;
+            }
+            EpisodeStatus::Skipped { .. } if !named => {
+                skipped += 1;
+                continue;
+            }
+            _ => {}
+        }
+        if pipeline::queue_episode(db, &episode)? {
+            queued += 1;
+        } else {
+            updated += 1;
+        }
+        if let Some(priority) = priority {
+            db.set_job_priority(episode.id, priority)?;
+        }
+    }
+
+    println!("Queued {queued} episode(s) for the next sync.");
+    if updated > 0 {
+        if priority.is_some() {
+            println!("  {updated} already queued; priority updated.");
+        } else {
+            println!("  {updated} already queued.");
+        }
+    }
+    if summarized > 0 {
+        println!(
+            "  {summarized} already summarized (for a new version: podcast-summarize sync -e <ID> --resummarize)."
+        );
+    }
+    if skipped > 0 {
+        println!("  {skipped} skipped episode(s) left out; name them by ID or use `unskip`.");
+    }
+    Ok(())
+}
+
+fn remove(db: &Database, selection: &EpisodeSelection) -> Result<()> {
+    let mut removed = 0;
+    for (episode, _) in select(db, selection)? {
+        if db.delete_job(episode.id)? {
+            removed += 1;
+        }
+    }
+    println!("Removed {removed} episode(s) from the queue.");
+    Ok(())
+}
+
+fn list(db: &Database, format: OutputFormat) -> Result<()> {
+    let jobs = db.list_jobs()?;
+    if !format.is_table() {
+        return output::print_records(format, &jobs);
+    }
+    if jobs.is_empty() {
+        println!("The queue is empty. Add episodes with: podcast-summarize queue add <ID>...");
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    println!();
+    println!(
+        "  {:>4} {:<6} {:<50} {:<11} STATE",
+        "PRI", "ID", "PODCAST / EPISODE", "NEXT STAGE"
+    );
+    println!("  {}", "─".repeat(90));
+    for job in &jobs {
+        let episode = db.get_episode(job.episode_id)?;
+        let podcast = db.get_podcast(episode.podcast_id)?;
+        let state = match job.next_run_at {
+            None => "failed; waits for `sync --retry-failed`".to_string(),
+            Some(t) if t > now => format!(
+                "retry after {}",
+                t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            ),
+            Some(_) => "ready".to_string(),
+        };
+        println!(
+            "  {:>4} #{:<5} {:<50} {:<11} {state}",
+            job.priority,
+            job.episode_id,
+            truncate(
+                &format!("{} / {}", truncate(&podcast.title, 20), episode.title),
+                50
+            ),
+            job.stage.as_str(),
+        );
+    }
+    println!();
+    Ok(())
+}
+
+fn clear(db: &Database, podcast: Option<&str>) -> Result<()> {
+    let filter = SearchOptions {
+        podcast: podcast.map(str::to_string),
+        ..Default::default()
+    }
+    .resolve(db)?;
+    let removed = db.clear_jobs(filter.podcast_id)?;
+    println!("Removed {removed} episode(s) from the queue.");
+    Ok(())
+}
+
+fn skip_episodes(db: &Database, selection: &EpisodeSelection) -> Result<()> {
+    let mut count = 0;
+    let mut summarized = 0;
+    for (episode, _) in select(db, selection)? {
+        match episode.status {
+            EpisodeStatus::Summarized => summarized += 1,
+            _ if pipeline::skip_episode(db, &episode)? => count += 1,
+            _ => {}
+        }
+    }
+    println!("Skipped {count} episode(s).");
+    if summarized > 0 {
+        println!("  {summarized} already summarized, left as they are.");
+    }
+    Ok(())
+}
+
+fn unskip_episodes(db: &Database, selection: &EpisodeSelection) -> Result<()> {
+    let mut count = 0;
+    for (episode, _) in select(db, selection)? {
+        if pipeline::unskip_episode(db, &episode)? {
+            count += 1;
+        }
+    }
+    println!("Unskipped and queued {count} episode(s).");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(ids: &[i64], podcast: Option<&str>) -> EpisodeSelection {
+        EpisodeSelection {
+            ids: ids.to_vec(),
+            podcast: podcast.map(str::to_string),
+            since: None,
+            until: None,
+        }
+    }
+
+    fn queued(db: &Database) -> Vec<(i64, i64)> {
+        db.list_jobs()
+            .unwrap()
+            .iter()
+            .map(|j| (j.episode_id, j.priority))
+            .collect()
+    }
+
+    #[test]
+    fn queue_skip_and_unskip() {
+        let db = Database::open_in_memory().unwrap();
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Weekly", None, None)
+            .unwrap();
+        let ep = |guid: &str| {
+            db.insert_episode(p.id, guid, guid, None, "https://ex.com/x.mp3", None, None)
+                .unwrap()
+        };
+        let (a, b, c) = (ep("a"), ep("b"), ep("c"));
+
+        assert!(add(&db, &selection(&[], None), None).is_err());
+
+        skip_episodes(&db, &selection(&[b], None)).unwrap();
+        add(&db, &selection(&[c], None), Some(3)).unwrap();
+        assert_eq!(queued(&db), vec![(c, 3)]);
+
+        // Bulk selection leaves the skipped episode out and keeps priorities
+        add(&db, &selection(&[], Some("Weekly")), None).unwrap();
+        assert_eq!(queued(&db), vec![(c, 3), (a, 0)]);
+        assert_eq!(
+            db.get_episode(b).unwrap().status,
+            EpisodeStatus::Skipped { reason: None }
+        );
+
+        // Naming it queues it anyway
+        add(&db, &selection(&[b], None), Some(-1)).unwrap();
+        assert_eq!(db.get_episode(b).unwrap().status, EpisodeStatus::New);
+        assert_eq!(queued(&db), vec![(c, 3), (a, 0), (b, -1)]);
+
+        skip_episodes(&db, &selection(&[a, b], None)).unwrap();
+        assert_eq!(queued(&db), vec![(c, 3)]);
+        unskip_episodes(&db, &selection(&[], Some("Weekly"))).unwrap();
+        assert_eq!(queued(&db).len(), 3);
+        assert_eq!(db.get_episode(a).unwrap().status, EpisodeStatus::New);
+
+        remove(&db, &selection(&[c], None)).unwrap();
+        assert_eq!(queued(&db).len(), 2);
+        clear(&db, Some("Weekly")).unwrap();
+        assert!(queued(&db).is_empty());
+    }
+}
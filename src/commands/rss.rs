@@ -0,0 +1,30 @@
+use anyhow::Result;
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::rss;
+
+pub fn run(
+    podcast: Option<&str>,
+    limit: usize,
+    link: Option<&str>,
+    output: Option<&str>,
+    config: &AppConfig,
+) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    let filter = super::search::SearchOptions {
+        podcast: podcast.map(str::to_string),
+        ..Default::default()
+    }
+    .resolve(&db)?;
+    let document = rss::build(&db, filter.podcast_id, limit, link)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, &document)?;
+            eprintln!("Feed written to {path}");
+        }
+        None => print!("{document}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::IsTerminal;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::config::AppConfig;
+use crate::dates::parse_date;
+use crate::db::Database;
+use crate::models::PassageHit;
+use crate::output::{self, OutputFormat};
+use crate::{embed, search, transcribe};
+
+use super::list::truncate;
+
+/// Filters for `search`, as given on the command line.
+#[derive(Debug, Default)]
+pub struct SearchOptions {
+    pub podcast: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub tag: Option<String>,
+    pub limit: usize,
+    /// Rank transcript passages by embedding similarity instead of keywords
+    pub semantic: bool,
+}
+
+/// Search filters resolved against the database.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchFilter {
+    pub podcast_id: Option<i64>,
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub until: Option<DateTime<Utc>>,
+    pub tag_id: Option<i64>,
+}
+
+impl SearchOptions {
+    pub fn resolve(&self, db: &Database) -> Result<SearchFilter> {
+        let podcast_id = match self.podcast {
+            Some(ref name) => Some(
+                if let Ok(id) = name.parse::<i64>() {
+                    db.get_podcast(id).ok()
+                } else {
+                    db.find_podcast_by_name(name)?
+                }
+                .ok_or_else(|| anyhow::anyhow!("No podcast matching \"{name}\" found"))?
+                .id,
+            ),
+            None => None,
+        };
+        let since = self.since.as_deref().map(parse_date).transpose()?;
+        // --until is inclusive of the whole day
+        let until = self
+            .until
+            .as_deref()
+            .map(|d| parse_date(d).map(|d| d + chrono::Duration::days(1)))
+            .transpose()?;
+        let tag_id = self
+            .tag
+            .as_deref()
+            .map(|name| crate::tags::resolve(db, name))
+            .transpose()?;
+        Ok(SearchFilter {
+            podcast_id,
+            since,
+            until,
+            tag_id,
+        })
+    }
+}
+
+pub async fn run(
+    query: &str,
+    options: &SearchOptions,
+    format: OutputFormat,
+    config: &AppConfig,
+) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    let filter = options.resolve(&db)?;
+
+    if options.semantic {
+        let client = reqwest::Client::new();
+        let hits = semantic_search(&db, &client, query, filter, options.limit, config).await?;
+        if !format.is_table() {
+            return output::print_records(format, &hits);
+        }
+        print_passages(&hits, query);
+        return Ok(());
+    }
+
+    let hits = db.search(
+        query,
+        filter.podcast_id,
+        filter.since,
+        filter.until,
+        filter.tag_id,
+        options.limit,
+    )?;
+    if !format.is_table() {
+        // Machine-readable snippets carry no highlight markers
+        let hits: Vec<_> = hits
+            .into_iter()
+            .map(|mut hit| {
+                hit.snippet = search::render_highlights(&hit.snippet, "", "");
+                hit
+            })
+            .collect();
+        return output::print_records(format, &hits);
+    }
+    if hits.is_empty() {
+        println!("No episodes match \"{query}\".");
+        return Ok(());
+    }
+
+    let (start, end) = if std::io::stdout().is_terminal() {
+        ("\x1b[1;33m", "\x1b[0m")
+    } else {
+        ("**", "**")
+    };
+
+    println!();
+    for hit in &hits {
+        let date = hit
+            .published_at
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        println!(
+            "  #{:<5} {} - {} {}",
+            hit.episode_id,
+            truncate(&hit.episode_title, 50),
+            truncate(&hit.podcast_title, 30),
+            date
+        );
+        if !hit.snippet.is_empty() {
+            println!(
+                "         {}",
+                search::render_highlights(&hit.snippet, start, end)
+            );
+        }
+        println!();
+    }
+    println!(
+        "  {} result(s). Read one with: podcast-summarize show <ID>",
+        hits.len()
+    );
+    println!();
+
+    Ok(())
+}
+
+/// Rank embedded transcript passages by similarity to `query`, best first.
+pub async fn semantic_search(
+    db: &Database,
+    client: &reqwest::Client,
+    query: &str,
+    filter: SearchFilter,
+    limit: usize,
+    config: &AppConfig,
+) -> Result<Vec<PassageHit>> {
+    let e = &config.embeddings;
+    let mut chunks = db.list_transcript_chunks(&e.model, filter.podcast_id)?;
+    if chunks.is_empty() {
+        anyhow::bail!(
+            "No transcripts are embedded with {}. Enable embeddings with\n  podcast-summarize config set embeddings_enabled true\nand run: podcast-summarize search --reindex --semantic",
+            e.model
+        );
+    }
+
+    if let Some(tag_id) = filter.tag_id {
+        let tagged = db.tagged_episode_ids(tag_id)?;
+        chunks.retain(|c| tagged.contains(&c.episode_id));
+    }
+
+    let query_vector = embed::embed(
+        client,
+        e.api_base_url(config),
+        e.api_key(config).as_deref(),
+        &e.model,
+        &[query.to_string()],
+    )
+    .await?
+    .pop()
+    .unwrap_or_default();
+
+    let mut scored: Vec<_> = chunks
+        .into_iter()
+        .map(|c| (embed::cosine_similarity(&query_vector, &c.embedding), c))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut episodes = HashMap::new();
+    let mut podcasts = HashMap::new();
+    let mut hits = Vec::new();
+    for (score, chunk) in scored {
+        if hits.len() >= limit {
+            break;
+        }
+        let episode = match episodes.entry(chunk.episode_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(db.get_episode(chunk.episode_id)?),
+        };
+        let published = episode.published_at;
+        if filter
+            .since
+            .is_some_and(|s| published.is_none_or(|p| p < s))
+            || filter
+                .until
+                .is_some_and(|u| published.is_none_or(|p| p >= u))
+        {
+            continue;
+        }
+        let podcast = match podcasts.entry(episode.podcast_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(db.get_podcast(episode.podcast_id)?),
+        };
+        hits.push(PassageHit {
+            episode_id: episode.id,
+            episode_title: episode.title.clone(),
+            podcast_title: podcast.title.clone(),
+            published_at: published,
+            text: chunk.text,
+            start_secs: chunk.start_secs,
+            end_secs: chunk.end_secs,
+            score,
+        });
+    }
+    Ok(hits)
+}
+
+fn print_passages(hits: &[PassageHit], query: &str) {
+    if hits.is_empty() {
+        println!("No passages match \"{query}\".");
+        return;
+    }
+
+    println!();
+    for hit in hits {
+        let at = hit
+            .start_secs
+            .map(|s| format!(" @ {}", embed::format_timestamp(s)))
+            .unwrap_or_default();
+        println!(
+            "  #{:<5} {} - {}{}  ({:.2})",
+            hit.episode_id,
+            truncate(&hit.episode_title, 50),
+            truncate(&hit.podcast_title, 30),
+            at,
+            hit.score
+        );
+        println!("         {}", truncate(&hit.text, 240));
+        println!();
+    }
+    println!(
+        "  {} passage(s). Read one with: podcast-summarize show <ID> -t",
+        hits.len()
+    );
+    println!();
+}
+
+pub async fn reindex(semantic: bool, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    let count = db.rebuild_search_index()?;
+    println!("Indexed {count} episode(s).");
+
+    if semantic {
+        let client = reqwest::Client::new();
+        let missing = db.list_episodes_missing_embeddings(&config.embeddings.model)?;
+        println!(
+            "Embedding {} transcript(s) with {}...",
+            missing.len(),
+            config.embeddings.model
+        );
+        for episode in &missing {
+            let Some(ref path) = episode.transcript_path else {
+                continue;
+            };
+            let result = match transcribe::load_transcript(std::path::Path::new(path)) {
+                Ok(transcript) => {
+                    crate::pipeline::embed_transcript(&db, &client, episode.id, &transcript, config)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(n) => println!("  #{} {}: {n} chunk(s)", episode.id, episode.title),
+                Err(e) => eprintln!("  #{} {}: {e}", episode.id, episode.title),
+            }
+        }
+    }
+    Ok(())
+}
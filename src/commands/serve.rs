@@ -0,0 +1,30 @@
+use anyhow::{Context, Result};
+use tokio::net::TcpListener;
+
+use crate::config::AppConfig;
+use crate::server;
+
+pub async fn run(bind: Option<&str>, config: &AppConfig) -> Result<()> {
+    let bind = bind.unwrap_or(&config.server.bind);
+    let token = config.server.token()?;
+    let listener = TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("Failed to listen on {bind}"))?;
+    let addr = listener.local_addr()?;
+    // Anyone on the network could read summaries and start syncs
+    if !addr.ip().is_loopback() && token.is_none() {
+        anyhow::bail!(
+            "Refusing to listen on {addr} without a token. Set one with:\n  podcast-summarize config set server_token_env <ENV_VAR_NAME>"
+        );
+    }
+
+    let url = match token {
+        Some(ref token) => format!("http://{addr}/?token={token}"),
+        None => format!("http://{addr}/"),
+    };
+    println!("Serving on {url}");
+    println!("Press Ctrl-C to stop.");
+    tokio::task::LocalSet::new()
+        .run_until(server::run(listener, config.clone(), token))
+        .await
+}
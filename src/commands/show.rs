@@ -1,29 +1,89 @@
 use anyhow::Result;
+use serde::Serialize;
 
 use crate::config::AppConfig;
+use crate::dates;
 use crate::db::Database;
+use crate::diff::{DiffLine, line_diff};
+use crate::models::{Episode, Podcast, StructuredSummary, Summary};
+use crate::output::{self, OutputFormat};
+use crate::summarize;
 
-pub fn run(episode_id: i64, transcript: bool, config: &AppConfig) -> Result<()> {
+/// Which summary version `show` displays, and what else to print alongside it.
+#[derive(Debug, Default)]
+pub struct SummarySelection {
+    pub version: Option<i64>,
+    pub model: Option<String>,
+    pub language: Option<String>,
+    pub list_versions: bool,
+    pub diff_against: Option<i64>,
+}
+
+/// Everything `show` prints about an episode, for `--output`.
+#[derive(Serialize)]
+struct EpisodeRecord {
+    episode: Episode,
+    podcast: Podcast,
+    tags: Vec<String>,
+    summary: Option<Summary>,
+    sections: Option<StructuredSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transcript: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versions: Option<Vec<Summary>>,
+}
+
+pub fn run(
+    episode_id: i64,
+    transcript: bool,
+    selection: &SummarySelection,
+    format: OutputFormat,
+    config: &AppConfig,
+) -> Result<()> {
     let db = Database::open(&config.db_path()?)?;
 
     let episode = db.get_episode(episode_id)?;
     let podcast = db.get_podcast(episode.podcast_id)?;
 
+    if !format.is_table() {
+        if selection.diff_against.is_some() {
+            anyhow::bail!("--diff is only available with table output");
+        }
+        let summary = selected_summary(&db, episode_id, selection)?;
+        let sections = match summary {
+            Some(ref s) => db.get_summary_sections(s.id)?,
+            None => None,
+        };
+        let transcript = match episode.transcript_path {
+            Some(ref path) if transcript && std::path::Path::new(path).exists() => {
+                Some(std::fs::read_to_string(path)?)
+            }
+            _ => None,
+        };
+        let versions = if selection.list_versions {
+            Some(db.list_summaries_by_episode(episode_id)?)
+        } else {
+            None
+        };
+        let record = EpisodeRecord {
+            tags: db.episode_tag_names(episode.id)?,
+            episode,
+            podcast,
+            summary,
+            sections,
+            transcript,
+            versions,
+        };
+        return output::print_record(format, &record);
+    }
+
     println!();
     println!("  {}", "═".repeat(60));
     println!("  {} - {}", episode.title, podcast.title);
     if let Some(date) = episode.published_at {
         let duration = episode
             .duration_secs
-            .map(|d| {
-                let h = d / 3600;
-                let m = (d % 3600) / 60;
-                if h > 0 {
-                    format!("{h}h{m:02}m")
-                } else {
-                    format!("{m}m")
-                }
-            })
+            .map(dates::format_duration)
             .unwrap_or_default();
         println!(
             "  Published: {} | Duration: {}",
@@ -31,18 +91,23 @@ pub fn run(episode_id: i64, transcript: bool, config: &AppConfig) -> Result<()>
             duration
         );
     }
+    let tags = db.episode_tag_names(episode.id)?;
+    if !tags.is_empty() {
+        println!("  Tags: {}", tags.join(", "));
+    }
     println!("  {}", "═".repeat(60));
 
     if transcript {
         match &episode.transcript_path {
             Some(path) if std::path::Path::new(path).exists() => {
                 let content = std::fs::read_to_string(path)?;
-                let word_count = super::sync::count_text_length(&content);
+                let word_count = crate::text::count_text_length(&content);
                 println!();
                 println!("{}", indent(&content, 2));
                 println!();
                 println!("  {}", "─".repeat(60));
                 println!("  Transcript: {word_count} chars");
+                println!("  Listen along: podcast-summarize play {episode_id} --at <m:ss>");
             }
             _ => {
                 println!();
@@ -50,27 +115,59 @@ pub fn run(episode_id: i64, transcript: bool, config: &AppConfig) -> Result<()>
             }
         }
     } else {
-        match db.get_summary_by_episode(episode_id)? {
+        match selected_summary(&db, episode_id, selection)? {
             Some(summary) => {
-                println!();
-                println!("{}", indent(&summary.content, 2));
-                println!();
-                println!("  {}", "─".repeat(60));
+                if let Some(other) = selection.diff_against {
+                    let base = db.get_summary_version(episode_id, other)?.ok_or_else(|| {
+                        anyhow::anyhow!("Episode #{episode_id} has no summary version {other}")
+                    })?;
+                    print_diff(&base, &summary);
+                } else {
+                    println!();
+                    match db.get_summary_sections(summary.id)? {
+                        Some(sections) => print_sections(&sections),
+                        None => println!("{}", indent(&summary.content, 2)),
+                    }
+                    println!();
+                    println!("  {}", "─".repeat(60));
+                }
                 println!(
-                    "  Model: {} | Generated: {}",
+                    "  Version {} | Model: {} | Generated: {}",
+                    summary.version,
                     summary.model,
                     summary.created_at.format("%Y-%m-%d %H:%M")
                 );
+                if let Some(ref style) = summary.style {
+                    match summary.language {
+                        Some(ref lang) => println!("  Style: {style} | Language: {lang}"),
+                        None => println!("  Style: {style}"),
+                    }
+                }
                 if let (Some(pt), Some(ot)) = (summary.prompt_tokens, summary.output_tokens) {
                     println!("  Tokens: {pt} in / {ot} out");
                 }
             }
             None => {
                 println!();
-                println!("  No summary yet. Run: podcast-summarize sync -e {episode_id}");
-                println!("  Status: {:?}", episode.status);
+                match (selection.version, &selection.model, &selection.language) {
+                    (Some(v), _, _) => println!("  No summary version {v} for this episode."),
+                    (None, Some(m), _) => {
+                        println!("  No summary generated by {m} for this episode.")
+                    }
+                    (None, None, Some(l)) => println!(
+                        "  No {l} summary yet. Run: podcast-summarize sync -e {episode_id} --resummarize --lang {l}"
+                    ),
+                    (None, None, None) => {
+                        println!("  No summary yet. Run: podcast-summarize sync -e {episode_id}");
+                        println!("  Status: {:?}", episode.status);
+                    }
+                }
             }
         }
+
+        if selection.list_versions {
+            print_versions(&db.list_summaries_by_episode(episode_id)?);
+        }
     }
     println!("  {}", "═".repeat(60));
     println!();
@@ -78,6 +175,82 @@ pub fn run(episode_id: i64, transcript: bool, config: &AppConfig) -> Result<()>
     Ok(())
 }
 
+fn selected_summary(
+    db: &Database,
+    episode_id: i64,
+    selection: &SummarySelection,
+) -> Result<Option<Summary>> {
+    if let Some(version) = selection.version {
+        db.get_summary_version(episode_id, version)
+    } else if let Some(ref model) = selection.model {
+        db.get_latest_summary_by_model(episode_id, model)
+    } else if let Some(ref lang) = selection.language {
+        db.get_latest_summary_by_language(episode_id, lang)
+    } else {
+        db.get_summary_by_episode(episode_id)
+    }
+}
+
+/// The typed sections of a summary, in the order the text format uses.
+fn print_sections(sections: &StructuredSummary) {
+    if !sections.topics.is_empty() {
+        println!("  Topics: {}", sections.topics.join(", "));
+        println!();
+    }
+    println!("{}", indent(&sections.summary, 2));
+    if !sections.key_takeaways.is_empty() {
+        println!();
+        println!("  Key takeaways:");
+        for takeaway in &sections.key_takeaways {
+            println!("    - {takeaway}");
+        }
+    }
+    if !sections.notable_quotes.is_empty() {
+        println!();
+        println!("  Notable quotes:");
+        for quote in &sections.notable_quotes {
+            println!("    - {}", summarize::format_quote(quote));
+        }
+    }
+}
+
+fn print_versions(summaries: &[Summary]) {
+    println!("  {}", "─".repeat(60));
+    println!(
+        "  {:<4} {:<24} {:<14} {:<8} {:<16} GENERATED",
+        "VER", "MODEL", "STYLE", "LANG", "PROMPT"
+    );
+    for s in summaries {
+        println!(
+            "  {:<4} {:<24} {:<14} {:<8} {:<16} {}",
+            s.version,
+            s.model,
+            s.style.as_deref().unwrap_or("-"),
+            s.language.as_deref().unwrap_or("-"),
+            s.prompt_hash.as_deref().unwrap_or("-"),
+            s.created_at.format("%Y-%m-%d %H:%M"),
+        );
+    }
+}
+
+fn print_diff(base: &Summary, summary: &Summary) {
+    println!();
+    println!(
+        "  --- version {} ({})\n  +++ version {} ({})",
+        base.version, base.model, summary.version, summary.model
+    );
+    println!();
+    for line in line_diff(&base.content, &summary.content) {
+        match line {
+            DiffLine::Same(l) => println!("    {l}"),
+            DiffLine::Removed(l) => println!("  - {l}"),
+            DiffLine::Added(l) => println!("  + {l}"),
+        }
+    }
+    println!();
+    println!("  {}", "─".repeat(60));
+}
+
 fn indent(s: &str, spaces: usize) -> String {
     let prefix = " ".repeat(spaces);
     s.lines()
@@ -0,0 +1,45 @@
+use anyhow::Result;
+
+use crate::config::AppConfig;
+use crate::prompts::{self, TemplateSource};
+
+pub fn run(name: Option<&str>, config: &AppConfig) -> Result<()> {
+    if let Some(name) = name {
+        let template = prompts::find_template(name)?;
+        println!("{}", template.body);
+        return Ok(());
+    }
+
+    let default_style = config
+        .summarization
+        .style
+        .as_deref()
+        .unwrap_or(prompts::DEFAULT_STYLE);
+
+    println!();
+    println!("  {:<20} {:<8} SOURCE", "STYLE", "DEFAULT");
+    println!("  {}", "─".repeat(60));
+    for template in prompts::list_templates()? {
+        let source = match &template.source {
+            TemplateSource::Builtin => "built-in".to_string(),
+            TemplateSource::User(path) => path.display().to_string(),
+        };
+        let marker = if template.name == default_style {
+            "*"
+        } else {
+            ""
+        };
+        println!("  {:<20} {:<8} {}", template.name, marker, source);
+    }
+    println!();
+    println!(
+        "  User templates: {}",
+        AppConfig::templates_dir()?.display()
+    );
+    println!(
+        "  Variables: {{{{podcast_title}}}}, {{{{episode_title}}}}, {{{{published_date}}}}, {{{{duration}}}}, {{{{language}}}}"
+    );
+    println!();
+
+    Ok(())
+}
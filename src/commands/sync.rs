@@ -1,31 +1,107 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::HashMap;
 
 use anyhow::Result;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use tokio::sync::Semaphore;
+use chrono::{DateTime, Utc};
 
 use crate::config::AppConfig;
 use crate::db::Database;
-use crate::models::EpisodeStatus;
-use crate::{download, feed, summarize, transcribe};
+use crate::filter::Policy;
+use crate::models::{EpisodeStatus, JobStage, Podcast};
+use crate::prompts::{self, SummaryOptions};
+use crate::{daemon, feed, pipeline};
+
+/// How `sync` treats the episodes it processes, as given on the command line.
+#[derive(Debug, Default)]
+pub struct SyncOptions {
+    pub download_only: bool,
+    /// Transcribe and summarize `-e`'s episode again
+    pub redo: bool,
+    /// Summarize `-e`'s episode again, keeping its transcript
+    pub resummarize: bool,
+    pub retry_failed: bool,
+    pub summary: SummaryOptions,
+}
+
+impl SyncOptions {
+    fn final_stage(&self) -> JobStage {
+        if self.download_only {
+            JobStage::Download
+        } else {
+            JobStage::Summarize
+        }
+    }
+}
 
 pub async fn run(
     name: Option<&str>,
     episode_id: Option<i64>,
-    download_only: bool,
-    redo: bool,
+    options: &SyncOptions,
     config: &AppConfig,
 ) -> Result<()> {
+    let summary = &options.summary;
+    // Fail fast on a typo'd style rather than after hours of transcription
+    if let Some(ref style) = summary.style {
+        prompts::find_template(style)?;
+    }
+
+    // Whole-library syncs exclude each other; narrower runs rely on episode leases
+    let _lock = if name.is_none() && episode_id.is_none() {
+        Some(daemon::SyncLock::acquire(&config.data_dir()?)?)
+    } else {
+        None
+    };
     let db = Database::open(&config.db_path()?)?;
     let client = reqwest::Client::new();
 
+    let final_stage = options.final_stage();
+
     // If a specific episode ID is given, process it first
     if let Some(ep_id) = episode_id {
-        if redo {
-            clear_episode_results(&db, ep_id)?;
+        let episode = db.get_episode(ep_id)?;
+        let podcast = db.get_podcast(episode.podcast_id)?;
+
+        // Don't reset a job another process is working on
+        let mut leases = daemon::Leases::new(&db);
+        if !leases.claim(ep_id)? {
+            println!(
+                "Skipping \"{}\": claimed by {}",
+                episode.title,
+                leases.holder(ep_id)
+            );
+            return Ok(());
+        }
+
+        println!("Processing: \"{}\" ({})", episode.title, podcast.title);
+        if let Some(job) = db.get_job(ep_id)?
+            && job.next_run_at.is_none()
+        {
+            println!(
+                "  Retrying after {} failed attempt(s): {}",
+                job.attempts,
+                job.last_error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        if options.redo {
+            pipeline::clear_episode_results(&db, ep_id)?;
+        }
+        pipeline::enqueue(
+            &db,
+            ep_id,
+            final_stage,
+            options.redo || options.resummarize,
+            summary,
+        )?;
+        let report = pipeline::run(&db, &client, &pipeline::Scope::Episode(ep_id), config).await?;
+        if let Some((_, reason)) = report.failed.first() {
+            anyhow::bail!("Episode #{ep_id} failed at {reason}");
+        }
+        if report.completed.contains(&ep_id) {
+            if options.download_only {
+                println!("\nDone (download only).");
+            } else {
+                println!("\nDone! Run `podcast-summarize show {ep_id}` to read the summary.");
+            }
         }
-        run_single_episode(&db, &client, ep_id, download_only, config).await?;
         if name.is_none() {
             return Ok(());
         }
@@ -49,530 +125,208 @@ pub async fn run(
         return Ok(());
     }
 
-    // Phase 1: Fetch feeds and discover new episodes
-    println!("Checking feeds...");
-    let mut all_new_episodes = Vec::new();
-
-    for podcast in &podcasts {
-        match feed::sync_feed(&client, &db, podcast).await {
-            Ok(new_eps) => {
-                if new_eps.is_empty() {
-                    println!("  {}: up to date", podcast.title);
-                } else {
-                    println!("  {}: {} new episode(s)", podcast.title, new_eps.len());
-                    all_new_episodes.extend(new_eps);
-                }
-            }
-            Err(e) => {
-                eprintln!("  {}: failed to fetch feed: {e}", podcast.title);
-            }
-        }
-    }
-
-    if all_new_episodes.is_empty() {
-        println!("\nAll feeds up to date.");
-        return Ok(());
-    }
-
-    // Phase 2: Download new episodes
-    let downloaded = download_episodes(&db, &client, &all_new_episodes, config).await?;
-
-    if download_only || downloaded.is_empty() {
-        println!("\nDone. {} episode(s) downloaded.", downloaded.len());
-        return Ok(());
-    }
-
-    // Phase 3: Transcribe
-    let transcribed = transcribe_episodes(&db, &downloaded, config).await?;
-
-    if transcribed.is_empty() {
-        println!("\nNo episodes transcribed successfully.");
-        return Ok(());
-    }
-
-    // Phase 4: Summarize
-    summarize_episodes(&db, &client, &transcribed, config).await?;
-
-    // Cleanup audio if configured
-    if config.general.auto_cleanup_audio {
-        for (_, audio_path) in &downloaded {
-            if audio_path.exists() {
-                let _ = std::fs::remove_file(audio_path);
-            }
-        }
-    }
-
-    println!("\nSync complete.");
-    Ok(())
-}
-
-/// Clear old transcript and summary so they get regenerated.
-fn clear_episode_results(db: &Database, ep_id: i64) -> Result<()> {
-    let episode = db.get_episode(ep_id)?;
-
-    // Delete transcript file
-    if let Some(ref path) = episode.transcript_path {
-        let p = std::path::Path::new(path);
-        if p.exists() {
-            std::fs::remove_file(p)?;
-        }
+    let scope = if name.is_some() {
+        pipeline::Scope::Podcasts(podcasts.iter().map(|p| p.id).collect())
+    } else {
+        pipeline::Scope::All
+    };
+    if options.retry_failed {
+        requeue_failed(&db, &scope, config)?;
     }
-    db.clear_episode_transcript(ep_id)?;
-
-    // Delete summary from DB
-    db.delete_summary_by_episode(ep_id)?;
-
-    println!("  Cleared old transcript and summary for episode #{ep_id}.");
-    Ok(())
+    sync_podcasts(
+        &db,
+        &client,
+        &podcasts,
+        &scope,
+        final_stage,
+        summary,
+        config,
+    )
+    .await
 }
 
-/// Process a single episode by ID through the full pipeline.
-async fn run_single_episode(
-    db: &Database,
-    client: &reqwest::Client,
-    ep_id: i64,
-    download_only: bool,
+/// Run the sync on a schedule until SIGTERM or Ctrl-C. Each podcast is checked
+/// when its interval (see [`AppConfig::check_interval`]) has passed since its
+/// last check.
+pub async fn watch(
+    interval: Option<chrono::Duration>,
+    options: &SyncOptions,
     config: &AppConfig,
 ) -> Result<()> {
-    let episode = db.get_episode(ep_id)?;
-    let podcast = db.get_podcast(episode.podcast_id)?;
-
-    println!("Processing: \"{}\" ({})", episode.title, podcast.title);
+    let summary = &options.summary;
+    if let Some(ref style) = summary.style {
+        prompts::find_template(style)?;
+    }
 
-    // Download if needed
-    let audio_path = if let Some(ref existing) = episode.audio_path {
-        let p = std::path::Path::new(existing);
-        if p.exists() {
-            println!("  Audio already downloaded.");
-            PathBuf::from(existing)
-        } else {
-            println!("  Downloading...");
-            let audio_dir = config.audio_dir()?;
-            let path = download::download_episode(
-                client,
-                &episode.audio_url,
-                &audio_dir,
-                episode.podcast_id,
-            )
-            .await?;
-            let path_str = path.to_string_lossy().to_string();
-            db.update_episode_audio_path(ep_id, &path_str)?;
-            println!("  Downloaded.");
-            path
+    let final_stage = options.final_stage();
+    let lock = daemon::SyncLock::acquire(&config.data_dir()?)?;
+    let db = Database::open(&config.db_path()?)?;
+    let client = reqwest::Client::new();
+    daemon::handle_signals();
+    println!(
+        "Watching feeds (lock: {}). Press Ctrl-C to stop.",
+        lock.path().display()
+    );
+
+    // Failed feed fetches don't update last_checked; remember attempts so a
+    // broken feed waits for its next interval instead of being retried at once
+    let mut attempted: HashMap<i64, DateTime<Utc>> = HashMap::new();
+    loop {
+        let now = Utc::now();
+        let podcasts = db.list_podcasts()?;
+        let mut due = Vec::new();
+        let mut next_check: Option<DateTime<Utc>> = None;
+        for podcast in podcasts {
+            let every = config.check_interval(&podcast, interval)?;
+            let last = podcast
+                .last_checked
+                .max(attempted.get(&podcast.id).copied());
+            let mut at = daemon::due_at(last, every);
+            if at <= now {
+                attempted.insert(podcast.id, now);
+                at = now + every;
+                due.push(podcast);
+            }
+            next_check = Some(next_check.map_or(at, |n| n.min(at)));
         }
-    } else {
-        println!("  Downloading...");
-        let audio_dir = config.audio_dir()?;
-        let path =
-            download::download_episode(client, &episode.audio_url, &audio_dir, episode.podcast_id)
-                .await?;
-        let path_str = path.to_string_lossy().to_string();
-        db.update_episode_audio_path(ep_id, &path_str)?;
-        println!("  Downloaded.");
-        path
-    };
 
-    if download_only {
-        println!("\nDone (download only).");
-        return Ok(());
-    }
-
-    // Transcribe if needed
-    let transcript = if let Some(ref existing) = episode.transcript_path {
-        let p = std::path::Path::new(existing);
-        if p.exists() {
-            println!("  Transcript already exists.");
-            std::fs::read_to_string(existing)?
-        } else {
-            // Transcript path set but file missing, re-transcribe
-            let pb = ProgressBar::new(100);
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("  [{bar:30.cyan/dim}] {pos}% Transcribing...")
-                    .unwrap()
-                    .progress_chars("##-"),
+        if !due.is_empty() {
+            println!(
+                "\n[{}] Checking {} podcast(s)",
+                now.format("%Y-%m-%d %H:%M"),
+                due.len()
             );
-
-            let progress = Arc::new(std::sync::atomic::AtomicI32::new(0));
-            let progress_clone = progress.clone();
-
-            let audio_path_clone = audio_path.clone();
-            let config_clone = config.clone();
-            let handle = tokio::task::spawn_blocking(move || {
-                transcribe::transcribe(&audio_path_clone, &config_clone, progress_clone)
-            });
-
-            loop {
-                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-                let pct = progress.load(std::sync::atomic::Ordering::Relaxed);
-                pb.set_position(pct.max(0) as u64);
-                if handle.is_finished() {
-                    break;
-                }
+            // Jobs of podcasts that aren't due (retries, interrupted work) run too
+            let scope = pipeline::Scope::All;
+            if options.retry_failed {
+                requeue_failed(&db, &scope, config)?;
             }
-            let result = handle.await??;
-
-            pb.set_position(100);
-            pb.finish_and_clear();
-
-            let transcript_dir = config.transcript_dir()?;
-            let transcript_file = transcript_dir
-                .join(episode.podcast_id.to_string())
-                .join(format!("{ep_id}.txt"));
-            if let Some(parent) = transcript_file.parent() {
-                std::fs::create_dir_all(parent)?;
+            if let Err(e) =
+                sync_podcasts(&db, &client, &due, &scope, final_stage, summary, config).await
+            {
+                eprintln!("Sync failed: {e:#}");
             }
-            std::fs::write(&transcript_file, &result)?;
-            db.update_episode_transcript_path(ep_id, &transcript_file.to_string_lossy())?;
-
-            let word_count = count_text_length(&result);
-            println!("  Transcribed ({word_count} words).");
-            result
         }
-    } else {
-        let pb = ProgressBar::new(100);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("  [{bar:30.cyan/dim}] {pos}% Transcribing...")
-                .unwrap()
-                .progress_chars("##-"),
-        );
-
-        let progress = Arc::new(std::sync::atomic::AtomicI32::new(0));
-        let progress_clone = progress.clone();
-
-        let audio_path_clone = audio_path.clone();
-        let config_clone = config.clone();
-        let handle = tokio::task::spawn_blocking(move || {
-            transcribe::transcribe(&audio_path_clone, &config_clone, progress_clone)
-        });
-
-        // Poll progress until transcription finishes
-        loop {
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-            let pct = progress.load(std::sync::atomic::Ordering::Relaxed);
-            pb.set_position(pct.max(0) as u64);
-            if handle.is_finished() {
-                break;
-            }
+        if daemon::shutdown_requested() {
+            break;
         }
-        let result = handle.await??;
 
-        pb.set_position(100);
-        pb.finish_and_clear();
-
-        // Save transcript
-        let transcript_dir = config.transcript_dir()?;
-        let transcript_file = transcript_dir
-            .join(episode.podcast_id.to_string())
-            .join(format!("{ep_id}.txt"));
-        if let Some(parent) = transcript_file.parent() {
-            std::fs::create_dir_all(parent)?;
+        let next_check = next_check.unwrap_or_else(|| {
+            now + interval.unwrap_or(chrono::Duration::hours(
+                config.general.check_interval_hours as i64,
+            ))
+        });
+        println!(
+            "Next check at {}.",
+            next_check
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+        );
+        let wait = (next_check - Utc::now()).max(chrono::Duration::minutes(1));
+        if !daemon::sleep(wait).await {
+            break;
         }
-        std::fs::write(&transcript_file, &result)?;
-        db.update_episode_transcript_path(ep_id, &transcript_file.to_string_lossy())?;
-
-        let word_count = count_text_length(&result);
-        println!("  Transcribed ({word_count} words).");
-        result
-    };
-
-    // Summarize
-    if db.get_summary_by_episode(ep_id)?.is_some() {
-        println!("  Summary already exists. Use `pod-sum show {ep_id}` to read it.");
-        return Ok(());
     }
-
-    let api_key = config.api_key()?;
-
-    let spinner_style = ProgressStyle::default_spinner()
-        .template("  {spinner} Summarizing...")
-        .unwrap();
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(spinner_style);
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
-
-    let result = summarize::generate_summary(
-        client,
-        &config.summarization.api_base_url,
-        &api_key,
-        &config.summarization.model,
-        config.summarization.max_tokens,
-        config.summarization.system_prompt.as_deref(),
-        &transcript,
-    )
-    .await?;
-
-    db.insert_summary(
-        ep_id,
-        &result.content,
-        &result.model,
-        result.prompt_tokens,
-        result.output_tokens,
-    )?;
-    pb.finish_and_clear();
-    println!("  Summarized.");
-
-    // Cleanup audio if configured
-    if config.general.auto_cleanup_audio && audio_path.exists() {
-        let _ = std::fs::remove_file(&audio_path);
-    }
-
-    println!("\nDone! Run `podcast-summarize show {ep_id}` to read the summary.");
+    println!("Stopped.");
     Ok(())
 }
 
-// --- Helper functions for batch processing ---
-
-async fn download_episodes(
+/// Fetch the podcasts' feeds, queue their new episodes, and run the queued jobs
+/// in `scope` (which also resumes work left by an interrupted sync).
+async fn sync_podcasts(
     db: &Database,
     client: &reqwest::Client,
-    episodes: &[crate::models::Episode],
+    podcasts: &[Podcast],
+    scope: &pipeline::Scope,
+    final_stage: JobStage,
+    summary: &SummaryOptions,
     config: &AppConfig,
-) -> Result<Vec<(i64, PathBuf)>> {
-    let audio_dir = config.audio_dir()?;
-    let semaphore = Arc::new(Semaphore::new(config.general.max_concurrent_downloads));
-    let _mp = MultiProgress::new();
-
-    println!("\nDownloading {} episode(s)...", episodes.len());
-
-    let mut download_tasks = Vec::new();
-    for episode in episodes {
-        let permit = semaphore.clone().acquire_owned().await?;
-        let client = client.clone();
-        let audio_url = episode.audio_url.clone();
-        let audio_dir = audio_dir.clone();
-        let podcast_id = episode.podcast_id;
-        let ep_id = episode.id;
-        let title = episode.title.clone();
-
-        download_tasks.push(tokio::spawn(async move {
-            let result =
-                download::download_episode(&client, &audio_url, &audio_dir, podcast_id).await;
-            drop(permit);
-            (ep_id, title, result)
-        }));
-    }
+) -> Result<()> {
+    println!("Checking feeds...");
+    let mut new_episodes = 0;
 
-    let mut downloaded = Vec::new();
-    for task in download_tasks {
-        let (ep_id, title, result) = task.await?;
-        match result {
-            Ok(path) => {
-                let path_str = path.to_string_lossy().to_string();
-                db.update_episode_audio_path(ep_id, &path_str)?;
-                println!("  Downloaded: {title}");
-                downloaded.push((ep_id, path));
-            }
+    for podcast in podcasts {
+        // A broken filter would queue episodes it should skip; leave the feed alone
+        let policy = match Policy::for_podcast(config, podcast) {
+            Ok(policy) => policy,
             Err(e) => {
-                eprintln!("  Failed to download \"{title}\": {e}");
-                db.update_episode_status(ep_id, &EpisodeStatus::Failed(format!("download: {e}")))?;
+                eprintln!("  {}: {e:#}", podcast.title);
+                continue;
             }
-        }
-    }
-    Ok(downloaded)
-}
-
-async fn transcribe_episodes(
-    db: &Database,
-    downloaded: &[(i64, PathBuf)],
-    config: &AppConfig,
-) -> Result<Vec<(i64, String)>> {
-    let transcript_dir = config.transcript_dir()?;
-    std::fs::create_dir_all(&transcript_dir)?;
-
-    println!("\nTranscribing {} episode(s)...", downloaded.len());
-
-    let bar_style = ProgressStyle::default_bar()
-        .template("  [{bar:30.cyan/dim}] {pos}% {msg}")
-        .unwrap()
-        .progress_chars("##-");
-
-    let mut transcribed = Vec::new();
-
-    for (ep_id, audio_path) in downloaded {
-        let episode = db.get_episode(*ep_id)?;
-
-        let pb = ProgressBar::new(100);
-        pb.set_style(bar_style.clone());
-        pb.set_message(format!("Transcribing: {}", episode.title));
-
-        let progress = Arc::new(std::sync::atomic::AtomicI32::new(0));
-        let progress_clone = progress.clone();
-
-        let audio_path_clone = audio_path.clone();
-        let config_clone = config.clone();
-        let handle = tokio::task::spawn_blocking(move || {
-            transcribe::transcribe(&audio_path_clone, &config_clone, progress_clone)
-        });
-
-        loop {
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-            let pct = progress.load(std::sync::atomic::Ordering::Relaxed);
-            pb.set_position(pct.max(0) as u64);
-            if handle.is_finished() {
-                break;
-            }
-        }
-        let result = handle.await?;
-
-        match result {
-            Ok(transcript) => {
-                let transcript_file = transcript_dir
-                    .join(episode.podcast_id.to_string())
-                    .join(format!("{}.txt", ep_id));
-                if let Some(parent) = transcript_file.parent() {
-                    std::fs::create_dir_all(parent)?;
+        };
+        match feed::sync_feed(client, db, podcast).await {
+            Ok(new_eps) => {
+                if new_eps.is_empty() {
+                    println!("  {}: up to date", podcast.title);
+                } else {
+                    let auto_queue = config.auto_queue(podcast)?;
+                    let now = Utc::now();
+                    let mut skipped = 0;
+                    for episode in &new_eps {
+                        let reason = policy.skip_reason(
+                            &episode.title,
+                            episode.description.as_deref(),
+                            episode.published_at,
+                            now,
+                        );
+                        if let Some(reason) = reason {
+                            let status = EpisodeStatus::Skipped {
+                                reason: Some(reason),
+                            };
+                            db.update_episode_status(episode.id, &status)?;
+                            skipped += 1;
+                        } else if auto_queue {
+                            pipeline::enqueue(db, episode.id, final_stage, false, summary)?;
+                        }
+                    }
+                    let mut line = format!("  {}: {} new episode(s)", podcast.title, new_eps.len());
+                    if skipped > 0 {
+                        line.push_str(&format!(", {skipped} skipped by filters"));
+                    }
+                    if !auto_queue {
+                        line.push_str(" (not queued; pick with `queue add`)");
+                    }
+                    println!("{line}");
+                    if auto_queue {
+                        new_episodes += new_eps.len() - skipped;
+                    }
                 }
-                std::fs::write(&transcript_file, &transcript)?;
-
-                let path_str = transcript_file.to_string_lossy().to_string();
-                db.update_episode_transcript_path(*ep_id, &path_str)?;
-
-                let word_count = count_text_length(&transcript);
-                pb.finish_with_message(format!(
-                    "Transcribed: {} ({} words)",
-                    episode.title, word_count,
-                ));
-
-                transcribed.push((*ep_id, transcript));
             }
             Err(e) => {
-                pb.finish_with_message(format!("Failed: {}", episode.title));
-                eprintln!("    Error transcribing: {e}");
-                db.update_episode_status(
-                    *ep_id,
-                    &EpisodeStatus::Failed(format!("transcribe: {e}")),
-                )?;
+                eprintln!("  {}: failed to fetch feed: {e}", podcast.title);
             }
         }
     }
-    Ok(transcribed)
-}
-
-async fn summarize_episodes(
-    db: &Database,
-    client: &reqwest::Client,
-    transcribed: &[(i64, String)],
-    config: &AppConfig,
-) -> Result<()> {
-    let api_key = match config.api_key() {
-        Ok(key) => key,
-        Err(e) => {
-            eprintln!("\nSkipping summarization: {e}");
-            println!("Transcripts are saved. Re-run sync after setting API key.");
-            return Ok(());
-        }
-    };
 
-    println!("\nSummarizing {} episode(s)...", transcribed.len());
-
-    let spinner_style = ProgressStyle::default_spinner()
-        .template("  {spinner} {msg}")
-        .unwrap();
-
-    for (ep_id, transcript) in transcribed {
-        let episode = db.get_episode(*ep_id)?;
-
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(spinner_style.clone());
-        pb.set_message(format!("Summarizing: {}", episode.title));
-        pb.enable_steady_tick(std::time::Duration::from_millis(100));
-
-        match summarize::generate_summary(
-            client,
-            &config.summarization.api_base_url,
-            &api_key,
-            &config.summarization.model,
-            config.summarization.max_tokens,
-            config.summarization.system_prompt.as_deref(),
-            transcript,
-        )
-        .await
-        {
-            Ok(result) => {
-                db.insert_summary(
-                    *ep_id,
-                    &result.content,
-                    &result.model,
-                    result.prompt_tokens,
-                    result.output_tokens,
-                )?;
-                pb.finish_with_message(format!("Summarized: {} [done]", episode.title));
-            }
-            Err(e) => {
-                pb.finish_with_message(format!("Summary failed: {}", episode.title));
-                eprintln!("    Error: {e}");
-                db.update_episode_status(
-                    *ep_id,
-                    &EpisodeStatus::Failed(format!("summarize: {e}")),
-                )?;
-            }
-        }
-    }
-    Ok(())
-}
-
-/// Count text length: characters for CJK-heavy text, words for others.
-pub fn count_text_length(s: &str) -> usize {
-    let cjk_count = s.chars().filter(|c| is_cjk(*c)).count();
-    let total_chars = s.chars().filter(|c| !c.is_whitespace()).count();
-    if total_chars > 0 && cjk_count * 100 / total_chars > 30 {
-        total_chars
+    let report = pipeline::run(db, client, scope, config).await?;
+    if new_episodes == 0 && report.is_empty() {
+        println!("\nAll feeds up to date.");
+    } else if final_stage == JobStage::Download {
+        println!("\nDone. {} episode(s) downloaded.", report.completed.len());
     } else {
-        s.split_whitespace().count()
+        println!(
+            "\nSync complete: {} episode(s) done, {} failed.",
+            report.completed.len(),
+            report.failed.len()
+        );
     }
+    Ok(())
 }
 
-fn is_cjk(c: char) -> bool {
-    matches!(c,
-        '\u{4E00}'..='\u{9FFF}' |
-        '\u{3400}'..='\u{4DBF}' |
-        '\u{F900}'..='\u{FAFF}' |
-        '\u{3000}'..='\u{303F}' |
-        '\u{FF00}'..='\u{FFEF}'
-    )
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn count_text_length_english() {
-        assert_eq!(count_text_length("hello world foo bar"), 4);
-    }
-
-    #[test]
-    fn count_text_length_cjk() {
-        // All CJK chars - should count characters (excluding whitespace)
-        let text = "今天天氣很好我們去散步";
-        let result = count_text_length(text);
-        assert_eq!(result, 11);
-    }
-
-    #[test]
-    fn count_text_length_mixed_below_threshold() {
-        // Mostly English with a few CJK chars (below 30% threshold)
-        let text = "This is a long English sentence with one 字";
-        let result = count_text_length(text);
-        // CJK ratio is low, so word count
-        assert_eq!(result, text.split_whitespace().count());
-    }
-
-    #[test]
-    fn count_text_length_empty() {
-        assert_eq!(count_text_length(""), 0);
-    }
-
-    #[test]
-    fn count_text_length_whitespace_only() {
-        assert_eq!(count_text_length("   \n\t  "), 0);
-    }
-
-    #[test]
-    fn is_cjk_chinese_char() {
-        assert!(is_cjk('中'));
-        assert!(is_cjk('國'));
+/// Queue failed episodes whose backoff has passed and say which are still waiting.
+fn requeue_failed(db: &Database, scope: &pipeline::Scope, config: &AppConfig) -> Result<()> {
+    let plan = pipeline::retry_failed(db, scope, config)?;
+    let queued = plan.iter().filter(|(_, wait)| wait.is_none()).count();
+    println!("Retrying {queued} failed episode(s).");
+    for (episode, wait) in &plan {
+        if let Some(at) = wait {
+            println!(
+                "  #{} \"{}\" backs off until {}",
+                episode.id,
+                episode.title,
+                at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            );
+        }
     }
+    Ok(())
 }
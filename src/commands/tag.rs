@@ -0,0 +1,193 @@
+//! `tag`: user-defined labels on podcasts and episodes. A podcast's tags apply
+//! to all of its episodes, so `--tag` filters match either.
+
+use anyhow::Result;
+
+use crate::cli::{TagAction, TagTarget};
+use crate::config::AppConfig;
+use crate::db::{Database, EpisodeQuery};
+use crate::models::{Episode, Podcast};
+use crate::output::{self, OutputFormat};
+use crate::tags;
+
+use super::list::truncate;
+
+pub fn run(action: &TagAction, format: OutputFormat, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    match action {
+        TagAction::Add { tag, target } => add(&db, tag, target),
+        TagAction::Remove { tag, target } => remove(&db, tag, target),
+        TagAction::List { tag } => list(&db, tag.as_deref(), format),
+    }
+}
+
+fn tag_name(tag: &str) -> Result<&str> {
+    let name = tag.trim();
+    if name.is_empty() {
+        anyhow::bail!("Tag names can't be empty");
+    }
+    Ok(name)
+}
+
+/// The podcast and episodes a `tag add`/`tag remove` names, checked before
+/// anything changes.
+fn targets(db: &Database, target: &TagTarget) -> Result<(Option<Podcast>, Vec<Episode>)> {
+    let podcast = match target.podcast {
+        Some(ref name) => Some(
+            if let Ok(id) = name.parse::<i64>() {
+                db.get_podcast(id).ok()
+            } else {
+                db.find_podcast_by_name(name)?
+            }
+            .ok_or_else(|| anyhow::anyhow!("No podcast matching \"{name}\" found"))?,
+        ),
+        None => None,
+    };
+    let episodes = target
+        .ids
+        .iter()
+        .map(|&id| {
+            db.get_episode(id)
+                .map_err(|_| anyhow::anyhow!("Episode #{id} not found"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((podcast, episodes))
+}
+
+fn add(db: &Database, tag: &str, target: &TagTarget) -> Result<()> {
+    let name = tag_name(tag)?;
+    let (podcast, episodes) = targets(db, target)?;
+    let tag_id = db.ensure_tag(name)?;
+
+    if let Some(podcast) = podcast {
+        if db.tag_podcast(podcast.id, tag_id)? {
+            println!(
+                "Tagged {} \"{name}\"; the tag applies to all its episodes.",
+                podcast.title
+            );
+        } else {
+            println!("{} is already tagged \"{name}\".", podcast.title);
+        }
+    }
+    if !episodes.is_empty() {
+        let mut added = 0;
+        for episode in &episodes {
+            if db.tag_episode(episode.id, tag_id, "manual")? {
+                added += 1;
+            }
+        }
+        let already = episodes.len() - added;
+        let note = if already > 0 {
+            format!(" ({already} already had it)")
+        } else {
+            String::new()
+        };
+        println!("Tagged {added} episode(s) \"{name}\"{note}.");
+    }
+    Ok(())
+}
+
+fn remove(db: &Database, tag: &str, target: &TagTarget) -> Result<()> {
+    let name = tag_name(tag)?;
+    let (podcast, episodes) = targets(db, target)?;
+    let tag_id = tags::resolve(db, name)?;
+
+    if let Some(podcast) = podcast {
+        if db.untag_podcast(podcast.id, tag_id)? {
+            println!("Removed \"{name}\" from {}.", podcast.title);
+        } else {
+            println!("{} isn't tagged \"{name}\".", podcast.title);
+        }
+    }
+    if !episodes.is_empty() {
+        let mut removed = 0;
+        for episode in &episodes {
+            if db.untag_episode(episode.id, tag_id)? {
+                removed += 1;
+            }
+        }
+        println!("Removed \"{name}\" from {removed} episode(s).");
+        let untouched = episodes.len() - removed;
+        if untouched > 0 {
+            println!(
+                "  {untouched} episode(s) didn't carry it themselves; tags from a podcast are removed with --podcast."
+            );
+        }
+    }
+    if db.delete_unused_tags()? > 0 {
+        println!("Tag \"{name}\" is no longer used and was deleted.");
+    }
+    Ok(())
+}
+
+fn list(db: &Database, tag: Option<&str>, format: OutputFormat) -> Result<()> {
+    let Some(name) = tag else {
+        let tags = db.list_tags()?;
+        if !format.is_table() {
+            return output::print_records(format, &tags);
+        }
+        if tags.is_empty() {
+            println!("No tags yet. Add one with: podcast-summarize tag add <TAG> <EPISODE_ID>...");
+            return Ok(());
+        }
+        println!();
+        println!("  {:<40} {:>8} {:>8}", "TAG", "PODCASTS", "EPISODES");
+        println!("  {}", "─".repeat(58));
+        for tag in &tags {
+            println!(
+                "  {:<40} {:>8} {:>8}",
+                truncate(&tag.name, 40),
+                tag.podcasts,
+                tag.episodes
+            );
+        }
+        println!();
+        return Ok(());
+    };
+
+    let tag_id = tags::resolve(db, name)?;
+    let podcast_ids = db.tagged_podcast_ids(tag_id)?;
+    let episode_ids = db.tagged_episode_ids(tag_id)?;
+    let episodes: Vec<_> = db
+        .query_episodes(&EpisodeQuery::default())?
+        .into_iter()
+        .filter(|ep| episode_ids.contains(&ep.id))
+        .collect();
+    if !format.is_table() {
+        return output::print_records(format, &episodes);
+    }
+
+    println!();
+    let podcasts: Vec<_> = db
+        .list_podcasts()?
+        .into_iter()
+        .filter(|p| podcast_ids.contains(&p.id))
+        .collect();
+    if !podcasts.is_empty() {
+        println!("  Podcasts tagged \"{name}\"");
+        println!("  {}", "─".repeat(66));
+        for p in &podcasts {
+            println!("  {:<4} {}", p.id, truncate(&p.title, 60));
+        }
+        println!();
+    }
+
+    println!("  Episodes tagged \"{name}\" ({})", episodes.len());
+    println!("  {}", "─".repeat(66));
+    for ep in &episodes {
+        let podcast = db.get_podcast(ep.podcast_id)?;
+        let date = ep
+            .published_at
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "          ".to_string());
+        println!(
+            "  #{:<5} {:<36} {:<20} {}",
+            ep.id,
+            truncate(&ep.title, 36),
+            truncate(&podcast.title, 20),
+            date,
+        );
+    }
+    println!();
+    Ok(())
+}
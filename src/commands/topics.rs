@@ -0,0 +1,76 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::output::{self, OutputFormat};
+
+use super::list::truncate;
+
+/// A topic with the number of episodes covering it, for `--output`.
+#[derive(Serialize)]
+struct TopicRow<'a> {
+    topic: &'a str,
+    episodes: i64,
+}
+
+pub fn run(topic: Option<&str>, format: OutputFormat, config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+
+    if let Some(topic) = topic {
+        let episodes = db.list_episodes_by_topic(topic)?;
+        if !format.is_table() {
+            return output::print_records(format, &episodes);
+        }
+        if episodes.is_empty() {
+            println!("No episodes tagged with a topic matching \"{topic}\".");
+            return Ok(());
+        }
+
+        println!();
+        println!("  Topic \"{topic}\" ({} episodes)", episodes.len());
+        println!("  {}", "─".repeat(66));
+        for ep in &episodes {
+            let podcast = db.get_podcast(ep.podcast_id)?;
+            let date = ep
+                .published_at
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "          ".to_string());
+            println!(
+                "  #{:<5} {:<36} {:<20} {}",
+                ep.id,
+                truncate(&ep.title, 36),
+                truncate(&podcast.title, 20),
+                date,
+            );
+        }
+        println!();
+        return Ok(());
+    }
+
+    let topics = db.list_topics()?;
+    if !format.is_table() {
+        let rows: Vec<_> = topics
+            .iter()
+            .map(|(topic, episodes)| TopicRow {
+                topic,
+                episodes: *episodes,
+            })
+            .collect();
+        return output::print_records(format, &rows);
+    }
+    if topics.is_empty() {
+        println!("No topics yet. Topics are extracted when episodes are summarized.");
+        return Ok(());
+    }
+
+    println!();
+    println!("  {:<50} {:>8}", "TOPIC", "EPISODES");
+    println!("  {}", "─".repeat(60));
+    for (topic, count) in &topics {
+        println!("  {:<50} {:>8}", truncate(topic, 50), count);
+    }
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::io::IsTerminal;
+
+use anyhow::Result;
+
+use crate::config::AppConfig;
+use crate::tui;
+
+pub fn run(config: &AppConfig) -> Result<()> {
+    if !std::io::stdout().is_terminal() {
+        anyhow::bail!("The TUI needs a terminal; use `list` and `show` in scripts");
+    }
+    tui::run(config)
+}
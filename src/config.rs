@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
@@ -11,6 +12,20 @@ pub struct AppConfig {
     pub transcription: TranscriptionConfig,
     #[serde(default)]
     pub summarization: SummarizationConfig,
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub player: PlayerConfig,
+    /// Where new summaries are delivered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifiers: Vec<NotifierConfig>,
+    /// Per-podcast overrides, keyed by podcast ID or full title (case-insensitive)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub podcasts: BTreeMap<String, PodcastConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +35,24 @@ pub struct GeneralConfig {
     pub max_concurrent_downloads: usize,
     #[serde(default = "default_true")]
     pub auto_cleanup_audio: bool,
+    /// How often `sync --watch` checks each feed
+    #[serde(default = "default_check_interval_hours")]
+    pub check_interval_hours: u64,
+    /// Attempts per episode before sync stops retrying it on its own
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry of a failed episode; doubles with each attempt
+    #[serde(default = "default_retry_backoff_minutes")]
+    pub retry_backoff_minutes: u64,
+    /// Skip newly found episodes published longer ago than this (0 = no limit)
+    #[serde(default)]
+    pub max_episode_age_days: u32,
+    /// Queue newly found episodes automatically; when off, pick them with `queue add`
+    #[serde(default = "default_true")]
+    pub auto_queue: bool,
+    /// Tag summarized episodes with existing tags their summary topics mention
+    #[serde(default)]
+    pub auto_tag_topics: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,11 +93,150 @@ pub struct SummarizationConfig {
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
     pub system_prompt: Option<String>,
+    /// Named summary style (a built-in or a template file in the templates directory)
+    pub style: Option<String>,
+    /// Language summaries are written in (e.g. "en", "zh-TW"); defaults to the transcript's
+    pub summary_language: Option<String>,
+    /// Ask for JSON output (default style only) and store topics, takeaways and quotes
+    #[serde(default = "default_true")]
+    pub json_mode: bool,
+}
+
+/// Transcript embeddings for semantic search, from an OpenAI-compatible `/embeddings`
+/// endpoint. Unset endpoint settings fall back to the summarization provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingsConfig {
+    /// Embed transcripts during sync
+    #[serde(default)]
+    pub enabled: bool,
+    pub api_base_url: Option<String>,
+    pub api_key_env: Option<String>,
+    #[serde(default = "default_embedding_model")]
+    pub model: String,
+    /// Maximum characters per embedded transcript chunk
+    #[serde(default = "default_chunk_chars")]
+    pub chunk_chars: usize,
+}
+
+/// The `serve` web UI and API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Address to listen on; anything but loopback requires a token
+    #[serde(default = "default_server_bind")]
+    pub bind: String,
+    /// Environment variable holding the access token (unset = no auth)
+    pub token_env: Option<String>,
+}
+
+/// Audio output for `play`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerConfig {
+    /// Player reading raw mono f32 samples from stdin, with `{rate}` for the
+    /// sample rate (default: the first of ffplay, mpv, sox or aplay found)
+    pub command: Option<String>,
+}
+
+/// Settings that override the global configuration for a single podcast.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PodcastConfig {
+    /// Summary style for this podcast
+    pub style: Option<String>,
+    /// Summary language for this podcast
+    pub summary_language: Option<String>,
+    /// Names of the notifiers that receive this podcast's summaries (default: all)
+    pub notify: Option<Vec<String>>,
+    /// How often `sync --watch` checks this feed, overriding the global interval
+    pub check_interval_hours: Option<u64>,
+    /// Maximum age of episodes to process, overriding the global limit (0 = none)
+    pub max_episode_age_days: Option<u32>,
+    /// Whether sync queues this podcast's new episodes, overriding the global setting
+    pub auto_queue: Option<bool>,
+    /// Only queue new episodes whose title or description matches one of these regexes
+    pub include: Option<Vec<String>>,
+    /// Never queue new episodes whose title or description matches one of these regexes
+    pub exclude: Option<Vec<String>>,
+}
+
+/// Delivery settings shared by all notifiers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Attempts after the first failure
+    #[serde(default = "default_notify_retries")]
+    pub retries: u32,
+    /// Delay before the first retry; doubles on each further retry
+    #[serde(default = "default_notify_retry_delay")]
+    pub retry_delay_secs: u64,
+}
+
+/// A destination for new summaries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotifierConfig {
+    /// Email through an SMTP server
+    Smtp(SmtpConfig),
+    /// JSON POST with the full summary
+    Webhook(WebhookConfig),
+    /// Slack incoming webhook
+    Slack(WebhookConfig),
+    /// Discord webhook
+    Discord(WebhookConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    /// Environment variable holding the SMTP password
+    pub password_env: Option<String>,
+    /// Sender, e.g. "Podcast Bot <bot@example.com>"
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Plain connection, e.g. a local relay or test sink
+    None,
+    /// Upgrade with STARTTLS (usually port 587)
+    #[default]
+    Starttls,
+    /// TLS from the start (usually port 465)
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub url: String,
+}
+
+impl NotifierConfig {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Smtp(c) => &c.name,
+            Self::Webhook(c) | Self::Slack(c) | Self::Discord(c) => &c.name,
+        }
+    }
 }
 
 fn default_max_downloads() -> usize {
     3
 }
+fn default_check_interval_hours() -> u64 {
+    6
+}
+fn default_max_attempts() -> u32 {
+    5
+}
+fn default_retry_backoff_minutes() -> u64 {
+    30
+}
 fn default_true() -> bool {
     true
 }
@@ -86,6 +258,24 @@ fn default_api_key_env() -> String {
 fn default_model() -> String {
     "gemini-2.0-flash".to_string()
 }
+fn default_embedding_model() -> String {
+    "text-embedding-004".to_string()
+}
+fn default_chunk_chars() -> usize {
+    1000
+}
+fn default_notify_retries() -> u32 {
+    3
+}
+fn default_notify_retry_delay() -> u64 {
+    5
+}
+fn default_smtp_port() -> u16 {
+    587
+}
+fn default_server_bind() -> String {
+    "127.0.0.1:8080".to_string()
+}
 fn default_max_tokens() -> u32 {
     4096
 }
@@ -96,6 +286,12 @@ impl Default for GeneralConfig {
             data_dir: None,
             max_concurrent_downloads: default_max_downloads(),
             auto_cleanup_audio: true,
+            check_interval_hours: default_check_interval_hours(),
+            max_attempts: default_max_attempts(),
+            retry_backoff_minutes: default_retry_backoff_minutes(),
+            max_episode_age_days: 0,
+            auto_queue: true,
+            auto_tag_topics: false,
         }
     }
 }
@@ -122,6 +318,39 @@ impl Default for SummarizationConfig {
             model: default_model(),
             max_tokens: default_max_tokens(),
             system_prompt: None,
+            style: None,
+            summary_language: None,
+            json_mode: true,
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            retries: default_notify_retries(),
+            retry_delay_secs: default_notify_retry_delay(),
+        }
+    }
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_base_url: None,
+            api_key_env: None,
+            model: default_embedding_model(),
+            chunk_chars: default_chunk_chars(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: default_server_bind(),
+            token_env: None,
         }
     }
 }
@@ -156,6 +385,99 @@ impl AppConfig {
         Ok(config_dir.join("podcast-summarize").join("config.toml"))
     }
 
+    /// Directory holding user summary templates (`<name>.txt` or `<name>.md`).
+    pub fn templates_dir() -> Result<PathBuf> {
+        let config_path = Self::config_path()?;
+        let dir = config_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+        Ok(dir.join("templates"))
+    }
+
+    /// Overrides for a podcast. A key matches the podcast ID or the whole title
+    /// (case-insensitive); more than one matching key is an error rather than a
+    /// guess at which applies.
+    pub fn podcast_config(&self, podcast: &crate::models::Podcast) -> Result<PodcastConfig> {
+        let id = podcast.id.to_string();
+        let title = podcast.title.to_lowercase();
+        let matches: Vec<_> = self
+            .podcasts
+            .iter()
+            .filter(|(key, _)| **key == id || key.to_lowercase() == title)
+            .collect();
+        match matches.as_slice() {
+            [] => Ok(PodcastConfig::default()),
+            [(_, cfg)] => Ok((*cfg).clone()),
+            _ => {
+                let keys: Vec<String> = matches
+                    .iter()
+                    .map(|(k, _)| format!("[podcasts.\"{k}\"]"))
+                    .collect();
+                anyhow::bail!(
+                    "More than one config section matches \"{}\" ({}); keep one",
+                    podcast.title,
+                    keys.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Notifiers that receive a podcast's summaries: those named in its `notify`
+    /// list, or every notifier when the podcast has none.
+    pub fn notifiers_for(&self, podcast: &crate::models::Podcast) -> Result<Vec<&NotifierConfig>> {
+        Ok(match self.podcast_config(podcast)?.notify {
+            Some(names) => self
+                .notifiers
+                .iter()
+                .filter(|n| names.iter().any(|name| name == n.name()))
+                .collect(),
+            None => self.notifiers.iter().collect(),
+        })
+    }
+
+    /// How often `sync --watch` checks a podcast: its own `check_interval_hours`,
+    /// then `default` (from `--interval`), then `general.check_interval_hours`.
+    pub fn check_interval(
+        &self,
+        podcast: &crate::models::Podcast,
+        default: Option<chrono::Duration>,
+    ) -> Result<chrono::Duration> {
+        Ok(match self.podcast_config(podcast)?.check_interval_hours {
+            Some(hours) => chrono::Duration::hours(hours as i64),
+            None => default.unwrap_or_else(|| {
+                chrono::Duration::hours(self.general.check_interval_hours as i64)
+            }),
+        })
+    }
+
+    /// How old a podcast's episodes may be and still be processed, if limited.
+    pub fn max_episode_age(
+        &self,
+        podcast: &crate::models::Podcast,
+    ) -> Result<Option<chrono::Duration>> {
+        let days = self
+            .podcast_config(podcast)?
+            .max_episode_age_days
+            .unwrap_or(self.general.max_episode_age_days);
+        Ok((days > 0).then(|| chrono::Duration::days(days as i64)))
+    }
+
+    /// Whether sync queues a podcast's newly found episodes on its own.
+    pub fn auto_queue(&self, podcast: &crate::models::Podcast) -> Result<bool> {
+        Ok(self
+            .podcast_config(podcast)?
+            .auto_queue
+            .unwrap_or(self.general.auto_queue))
+    }
+
+    /// Wait after a job's `attempts`-th failure before retrying it: the backoff
+    /// doubles with each attempt, capped at a day.
+    pub fn retry_delay(&self, attempts: i64) -> chrono::Duration {
+        let base = chrono::Duration::minutes(self.general.retry_backoff_minutes as i64);
+        let factor = 1i32 << attempts.clamp(1, 11).saturating_sub(1);
+        (base * factor).min(chrono::Duration::days(1))
+    }
+
     pub fn data_dir(&self) -> Result<PathBuf> {
         if let Some(ref dir) = self.general.data_dir {
             let path = PathBuf::from(shellexpand(dir));
@@ -180,7 +502,7 @@ impl AppConfig {
     }
 
     pub fn api_key(&self) -> Result<String> {
-        std::env::var(&self.summarization.api_key_env).with_context(|| {
+        secret(&self.summarization.api_key_env).with_context(|| {
             format!(
                 "API key not set. Set the {} environment variable or update config with:\n  podcast-summarize config set api_key_env <ENV_VAR_NAME>",
                 self.summarization.api_key_env
@@ -189,6 +511,68 @@ impl AppConfig {
     }
 }
 
+impl EmbeddingsConfig {
+    pub fn api_base_url<'a>(&'a self, config: &'a AppConfig) -> &'a str {
+        self.api_base_url
+            .as_deref()
+            .unwrap_or(&config.summarization.api_base_url)
+    }
+
+    /// API key for the embeddings endpoint, `None` when the variable is unset
+    /// (local servers usually need no key).
+    pub fn api_key(&self, config: &AppConfig) -> Option<String> {
+        let env = self.api_key_env(config)?;
+        secret(env).filter(|k| !k.is_empty())
+    }
+
+    /// The variable holding the key. The summarization key is only borrowed
+    /// along with its endpoint, so it never goes to another provider.
+    fn api_key_env<'a>(&'a self, config: &'a AppConfig) -> Option<&'a str> {
+        match (&self.api_key_env, &self.api_base_url) {
+            (Some(env), _) => Some(env),
+            (None, None) => Some(&config.summarization.api_key_env),
+            (None, Some(_)) => None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The access token clients must present, `None` when auth is off.
+    pub fn token(&self) -> Result<Option<String>> {
+        let Some(ref env) = self.token_env else {
+            return Ok(None);
+        };
+        let token = secret(env)
+            .with_context(|| format!("Server token not set. Set the {env} environment variable"))?;
+        if token.is_empty() {
+            anyhow::bail!("Server token in {env} is empty");
+        }
+        Ok(Some(token))
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    static TEST_SECRETS: std::cell::RefCell<BTreeMap<String, String>> =
+        const { std::cell::RefCell::new(BTreeMap::new()) };
+}
+
+/// The secret held in environment variable `env`.
+pub fn secret(env: &str) -> Option<String> {
+    #[cfg(test)]
+    if let Some(value) = TEST_SECRETS.with(|s| s.borrow().get(env).cloned()) {
+        return Some(value);
+    }
+    std::env::var(env).ok()
+}
+
+/// Give `env` a value for [`secret`] lookups on this thread. Tests run in
+/// parallel and share the process environment, so they can't set it safely.
+#[cfg(test)]
+pub fn set_test_secret(env: &str, value: &str) {
+    TEST_SECRETS.with(|s| s.borrow_mut().insert(env.to_string(), value.to_string()));
+}
+
 fn shellexpand(s: &str) -> String {
     if let Some(rest) = s.strip_prefix("~/")
         && let Some(home) = dirs::home_dir()
@@ -202,6 +586,18 @@ fn shellexpand(s: &str) -> String {
 mod tests {
     use super::*;
 
+    fn podcast(id: i64, title: &str) -> crate::models::Podcast {
+        crate::models::Podcast {
+            id,
+            title: title.to_string(),
+            feed_url: String::new(),
+            website_url: None,
+            description: None,
+            last_checked: None,
+            added_at: chrono::Utc::now(),
+        }
+    }
+
     #[test]
     fn default_transcription_config() {
         let config = TranscriptionConfig::default();
@@ -218,6 +614,35 @@ mod tests {
         assert_eq!(config.model, "gemini-2.0-flash");
         assert_eq!(config.max_tokens, 4096);
         assert!(config.system_prompt.is_none());
+        assert!(config.json_mode);
+    }
+
+    #[test]
+    fn embeddings_fall_back_to_summarization_endpoint() {
+        let mut config = AppConfig::default();
+        assert!(!config.embeddings.enabled);
+        assert_eq!(
+            config.embeddings.api_base_url(&config),
+            config.summarization.api_base_url
+        );
+        config.embeddings.api_base_url = Some("http://localhost:11434/v1".to_string());
+        assert_eq!(
+            config.embeddings.api_base_url(&config),
+            "http://localhost:11434/v1"
+        );
+    }
+
+    #[test]
+    fn embeddings_key_only_goes_with_its_endpoint() {
+        let mut config = AppConfig::default();
+        assert_eq!(
+            config.embeddings.api_key_env(&config),
+            Some(config.summarization.api_key_env.as_str())
+        );
+        config.embeddings.api_base_url = Some("https://other.example/v1".to_string());
+        assert_eq!(config.embeddings.api_key_env(&config), None);
+        config.embeddings.api_key_env = Some("OTHER_KEY".to_string());
+        assert_eq!(config.embeddings.api_key_env(&config), Some("OTHER_KEY"));
     }
 
     #[test]
@@ -266,6 +691,153 @@ whisper_model = "large"
         assert!(config.general.auto_cleanup_audio);
     }
 
+    #[test]
+    fn podcast_overrides_match_by_id_or_title() {
+        let toml_str = r#"
+[summarization]
+style = "detailed"
+
+[podcasts."Rust"]
+style = "brief"
+summary_language = "zh-TW"
+
+[podcasts."7"]
+style = "bullet-points"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.summarization.style.as_deref(), Some("detailed"));
+
+        let by_title = config.podcast_config(&podcast(1, "rust")).unwrap();
+        assert_eq!(by_title.style.as_deref(), Some("brief"));
+        assert_eq!(by_title.summary_language.as_deref(), Some("zh-TW"));
+        let by_id = config.podcast_config(&podcast(7, "Rust Monthly")).unwrap();
+        assert_eq!(by_id.style.as_deref(), Some("bullet-points"));
+        // Part of a title is not enough
+        let none = config.podcast_config(&podcast(2, "Trust Issues")).unwrap();
+        assert!(none.style.is_none());
+
+        let err = config.podcast_config(&podcast(7, "Rust")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("[podcasts.\"7\"], [podcasts.\"Rust\"]")
+        );
+    }
+
+    #[test]
+    fn notifiers_parse_and_route_per_podcast() {
+        let toml_str = r#"
+[[notifiers]]
+type = "smtp"
+name = "email"
+host = "localhost"
+port = 2525
+tls = "none"
+from = "Bot <bot@example.com>"
+to = ["team@example.com"]
+
+[[notifiers]]
+type = "slack"
+name = "slack"
+url = "https://hooks.slack.com/services/x"
+
+[podcasts."Rust Weekly"]
+notify = ["slack"]
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.notifiers.len(), 2);
+        match &config.notifiers[0] {
+            NotifierConfig::Smtp(smtp) => {
+                assert_eq!(smtp.port, 2525);
+                assert_eq!(smtp.tls, SmtpTls::None);
+            }
+            other => panic!("expected smtp, got {other:?}"),
+        }
+        assert_eq!(config.notifications.retries, 3);
+
+        let routed: Vec<_> = config
+            .notifiers_for(&podcast(1, "Rust Weekly"))
+            .unwrap()
+            .iter()
+            .map(|n| n.name())
+            .collect();
+        assert_eq!(routed, vec!["slack"]);
+        assert_eq!(
+            config.notifiers_for(&podcast(1, "Go Time")).unwrap().len(),
+            2
+        );
+
+        // Survives `config set`, which rewrites the whole file
+        let reparsed: AppConfig =
+            toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(reparsed.notifiers.len(), 2);
+        assert_eq!(reparsed.notifiers[1].name(), "slack");
+    }
+
+    #[test]
+    fn check_interval_precedence() {
+        let mut config = AppConfig::default();
+        config.podcasts.insert(
+            "News".to_string(),
+            PodcastConfig {
+                check_interval_hours: Some(1),
+                ..Default::default()
+            },
+        );
+        let hours = chrono::Duration::hours;
+
+        assert_eq!(
+            config
+                .check_interval(&podcast(1, "news"), Some(hours(3)))
+                .unwrap(),
+            hours(1)
+        );
+        assert_eq!(
+            config
+                .check_interval(&podcast(1, "Rust Weekly"), Some(hours(3)))
+                .unwrap(),
+            hours(3)
+        );
+        assert_eq!(
+            config
+                .check_interval(&podcast(1, "Rust Weekly"), None)
+                .unwrap(),
+            hours(6)
+        );
+    }
+
+    #[test]
+    fn max_episode_age_precedence() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.max_episode_age(&podcast(1, "News")).unwrap(), None);
+
+        config.general.max_episode_age_days = 30;
+        config.podcasts.insert(
+            "Archive".to_string(),
+            PodcastConfig {
+                max_episode_age_days: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            config.max_episode_age(&podcast(1, "News")).unwrap(),
+            Some(chrono::Duration::days(30))
+        );
+        // A per-podcast 0 lifts the global limit
+        assert_eq!(
+            config.max_episode_age(&podcast(1, "archive")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_a_day() {
+        let config = AppConfig::default();
+        assert_eq!(config.retry_delay(1), chrono::Duration::minutes(30));
+        assert_eq!(config.retry_delay(2), chrono::Duration::minutes(60));
+        assert_eq!(config.retry_delay(4), chrono::Duration::minutes(240));
+        assert_eq!(config.retry_delay(20), chrono::Duration::days(1));
+    }
+
     #[test]
     fn shellexpand_without_tilde() {
         let result = shellexpand("/absolute/path");
@@ -0,0 +1,274 @@
+//! Coordination between sync processes: the library lock, per-episode leases,
+//! graceful shutdown and the `sync --watch` schedule.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::Database;
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// How long an episode lease lasts without renewal. A crashed process's claims
+/// become available to others after this.
+const LEASE_TTL_SECS: i64 = 15 * 60;
+
+/// How often held leases are renewed while work is in progress.
+const LEASE_HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Exclusive lock on the data directory, held by whole-library syncs so two of
+/// them never run at once. Released when dropped or the process exits.
+#[derive(Debug)]
+pub struct SyncLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl SyncLock {
+    pub fn acquire(data_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(data_dir)?;
+        let path = data_dir.join("sync.lock");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .read(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lockfile {}", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = std::fs::read_to_string(&path).unwrap_or_default();
+                let holder = holder.trim();
+                anyhow::bail!(
+                    "Another sync is already running{} (lock: {})",
+                    if holder.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", pid {holder}")
+                    },
+                    path.display()
+                );
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+        // Record the holder for the error message above; the OS lock is what counts
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Identifies this process in episode leases.
+fn lease_owner() -> &'static str {
+    static OWNER: OnceLock<String> = OnceLock::new();
+    OWNER.get_or_init(|| {
+        format!(
+            "pid {} since {}",
+            std::process::id(),
+            Utc::now().format("%Y-%m-%d %H:%M:%S")
+        )
+    })
+}
+
+/// Episodes this process has claimed. Other processes skip claimed episodes
+/// until the claims are released (on drop) or go stale.
+pub struct Leases<'a> {
+    db: &'a Database,
+    held: Vec<i64>,
+    renewed: Instant,
+}
+
+impl<'a> Leases<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self {
+            db,
+            held: Vec::new(),
+            renewed: Instant::now(),
+        }
+    }
+
+    /// Claim an episode. Returns false if another process is working on it.
+    pub fn claim(&mut self, episode_id: i64) -> Result<bool> {
+        if !self
+            .db
+            .claim_episode(episode_id, lease_owner(), LEASE_TTL_SECS)?
+        {
+            return Ok(false);
+        }
+        if !self.held.contains(&episode_id) {
+            self.held.push(episode_id);
+        }
+        Ok(true)
+    }
+
+    /// Renew held leases if the heartbeat interval has passed; cheap to call often.
+    pub fn heartbeat(&mut self) {
+        if self.renewed.elapsed() < LEASE_HEARTBEAT {
+            return;
+        }
+        for &episode_id in &self.held {
+            match self
+                .db
+                .claim_episode(episode_id, lease_owner(), LEASE_TTL_SECS)
+            {
+                Ok(true) => {}
+                Ok(false) => tracing::warn!(
+                    "Lease on episode {episode_id} was taken over by another process"
+                ),
+                Err(e) => tracing::warn!("Failed to renew lease on episode {episode_id}: {e}"),
+            }
+        }
+        self.renewed = Instant::now();
+    }
+
+    /// Who is working on an episode we failed to claim, for messages.
+    pub fn holder(&self, episode_id: i64) -> String {
+        self.db
+            .episode_lease_owner(episode_id)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "another process".to_string())
+    }
+}
+
+impl Drop for Leases<'_> {
+    fn drop(&mut self) {
+        for &episode_id in &self.held {
+            let _ = self.db.release_episode(episode_id, lease_owner());
+        }
+    }
+}
+
+/// Listen for SIGTERM and Ctrl-C. The first signal asks the current sync to stop
+/// after the episode being transcribed; a second one exits immediately.
+pub fn handle_signals() {
+    tokio::spawn(async {
+        loop {
+            wait_for_signal().await;
+            if SHUTDOWN.swap(true, Ordering::SeqCst) {
+                eprintln!("\nForced shutdown.");
+                std::process::exit(130);
+            }
+            eprintln!("\nShutting down after the current episode (signal again to force)...");
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+    match signal(SignalKind::terminate()) {
+        Ok(mut term) => {
+            tokio::select! {
+                _ = term.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Whether a shutdown signal has been received.
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN.load(Ordering::SeqCst)
+}
+
+/// Sleep for `duration`, waking early on shutdown. Returns false if interrupted.
+pub async fn sleep(duration: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + duration.to_std().unwrap_or_default();
+    while tokio::time::Instant::now() < deadline {
+        if shutdown_requested() {
+            return false;
+        }
+        let remaining = deadline - tokio::time::Instant::now();
+        tokio::time::sleep(remaining.min(std::time::Duration::from_secs(1))).await;
+    }
+    !shutdown_requested()
+}
+
+/// When a podcast last checked at `last_checked` is next due; never-checked
+/// podcasts are due immediately.
+pub fn due_at(last_checked: Option<DateTime<Utc>>, interval: Duration) -> DateTime<Utc> {
+    match last_checked {
+        Some(t) => t + interval,
+        None => DateTime::<Utc>::MIN_UTC,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = std::env::temp_dir().join(format!("podsum-lock-{}", std::process::id()));
+        let lock = SyncLock::acquire(&dir).unwrap();
+        let err = SyncLock::acquire(&dir).unwrap_err().to_string();
+        assert!(err.contains("already running"), "{err}");
+        assert!(
+            err.contains(&format!("pid {}", std::process::id())),
+            "{err}"
+        );
+
+        drop(lock);
+        let lock = SyncLock::acquire(&dir).unwrap();
+        assert!(lock.path().ends_with("sync.lock"));
+        drop(lock);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn leases_skip_claimed_episodes_and_release_on_drop() {
+        let db = Database::open_in_memory().unwrap();
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        let ep = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        db.claim_episode(ep, "pid 1 since earlier", 600).unwrap();
+
+        let mut leases = Leases::new(&db);
+        assert!(!leases.claim(ep).unwrap());
+        assert_eq!(leases.holder(ep), "pid 1 since earlier");
+
+        db.release_episode(ep, "pid 1 since earlier").unwrap();
+        assert!(leases.claim(ep).unwrap());
+        assert_eq!(
+            db.episode_lease_owner(ep).unwrap().as_deref(),
+            Some(lease_owner())
+        );
+        drop(leases);
+        assert!(db.episode_lease_owner(ep).unwrap().is_none());
+    }
+
+    #[test]
+    fn due_times() {
+        let t: DateTime<Utc> = "2024-03-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            due_at(Some(t), Duration::hours(6)),
+            "2024-03-01T06:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(due_at(None, Duration::hours(6)) < t);
+    }
+}
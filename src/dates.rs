@@ -0,0 +1,165 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+/// Parse a `YYYY-MM-DD` date as midnight UTC.
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid date \"{s}\", expected YYYY-MM-DD"))?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+/// Parse a span such as `90m`, `24h`, `7d` or `2w`.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let invalid = || anyhow::anyhow!("Invalid duration \"{s}\", expected e.g. 30m, 24h, 7d or 2w");
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    if n < 0 {
+        return Err(invalid());
+    }
+    match unit {
+        'm' => Ok(Duration::minutes(n)),
+        'h' => Ok(Duration::hours(n)),
+        'd' => Ok(Duration::days(n)),
+        'w' => Ok(Duration::weeks(n)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a position in an episode as `h:mm:ss`, `m:ss` or seconds, into seconds.
+pub fn parse_timestamp(s: &str) -> Result<f64> {
+    let invalid = || anyhow::anyhow!("Invalid timestamp \"{s}\", expected e.g. 12:34 or 1:02:03");
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let mut secs = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let value: f64 = part.parse().map_err(|_| invalid())?;
+        // Minutes and seconds after the first field stay below 60
+        if !value.is_finite() || value < 0.0 || (i > 0 && value >= 60.0) {
+            return Err(invalid());
+        }
+        secs = secs * 60.0 + value;
+    }
+    Ok(secs)
+}
+
+/// Format an episode length in seconds as e.g. `1h05m` or `42m`.
+pub fn format_duration(secs: i64) -> String {
+    let h = secs / 3600;
+    let m = (secs % 3600) / 60;
+    if h > 0 {
+        format!("{h}h{m:02}m")
+    } else {
+        format!("{m}m")
+    }
+}
+
+/// Start of a window given as a span back from `now` (`7d`) or a date (`2024-03-01`).
+pub fn parse_since(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(date) = parse_date(s) {
+        return Ok(date);
+    }
+    parse_duration(s)
+        .map(|d| now - d)
+        .map_err(|_| anyhow::anyhow!("Invalid time \"{s}\", expected e.g. 7d, 24h or YYYY-MM-DD"))
+}
+
+/// Parse a timestamp stored by SQLite, either RFC 3339 or `datetime('now')`'s
+/// `YYYY-MM-DD HH:MM:SS` (UTC).
+pub fn parse_db_time(s: &str) -> Option<DateTime<Utc>> {
+    s.parse().ok().or_else(|| {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .map(|d| d.and_utc())
+    })
+}
+
+/// Format a timestamp the way `datetime('now')` stores it, for comparisons in SQL.
+pub fn to_db_time(t: DateTime<Utc>) -> String {
+    t.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_duration_minutes_only() {
+        assert_eq!(format_duration(300), "5m");
+        assert_eq!(format_duration(59 * 60), "59m");
+    }
+
+    #[test]
+    fn format_duration_hours_and_minutes() {
+        assert_eq!(format_duration(3600), "1h00m");
+        assert_eq!(format_duration(3661), "1h01m");
+        assert_eq!(format_duration(7200 + 1800), "2h30m");
+    }
+
+    #[test]
+    fn format_duration_zero() {
+        assert_eq!(format_duration(0), "0m");
+    }
+
+    #[test]
+    fn parse_date_valid() {
+        let d = parse_date("2024-03-01").unwrap();
+        assert_eq!(d.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_date_invalid() {
+        assert!(parse_date("03/01/2024").is_err());
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn parse_timestamp_forms() {
+        assert_eq!(parse_timestamp("754").unwrap(), 754.0);
+        assert_eq!(parse_timestamp("12:34").unwrap(), 754.0);
+        assert_eq!(parse_timestamp("1:02:03").unwrap(), 3723.0);
+        assert_eq!(parse_timestamp("0:05.5").unwrap(), 5.5);
+        assert!(parse_timestamp("12:60").is_err());
+        assert!(parse_timestamp("1:2:3:4").is_err());
+        assert!(parse_timestamp("-5").is_err());
+        assert!(parse_timestamp("").is_err());
+    }
+
+    #[test]
+    fn parse_since_span_or_date() {
+        let now = parse_date("2024-03-10").unwrap();
+        assert_eq!(
+            parse_since("7d", now).unwrap(),
+            parse_date("2024-03-03").unwrap()
+        );
+        assert_eq!(
+            parse_since("2024-01-01", now).unwrap(),
+            parse_date("2024-01-01").unwrap()
+        );
+        assert!(parse_since("last week", now).is_err());
+    }
+
+    #[test]
+    fn db_time_formats() {
+        let t = parse_db_time("2024-03-01 12:30:00").unwrap();
+        assert_eq!(t.to_rfc3339(), "2024-03-01T12:30:00+00:00");
+        assert_eq!(to_db_time(t), "2024-03-01 12:30:00");
+        assert!(parse_db_time("2024-03-01T12:30:00+00:00").is_some());
+        assert!(parse_db_time("garbage").is_none());
+    }
+}
@@ -1,8 +1,120 @@
+use std::collections::HashSet;
+
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{Connection, params};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+
+use crate::models::{
+    Episode, EpisodeStatus, Job, JobStage, Podcast, Quote, SearchHit, StructuredSummary, Summary,
+    Tag, TranscriptChunk,
+};
+use crate::{dates, embed, search};
+
+/// IDs of episodes carrying the tag `?{param}`, directly or through their podcast.
+fn tagged_episodes(param: usize) -> String {
+    format!(
+        "SELECT episode_id FROM episode_tags WHERE tag_id = ?{param}
+         UNION SELECT te.id FROM episodes te
+         JOIN podcast_tags pt ON pt.podcast_id = te.podcast_id WHERE pt.tag_id = ?{param}"
+    )
+}
+
+/// Which episodes [`Database::query_episodes`] returns, and in what order.
+#[derive(Debug, Clone, Default)]
+pub struct EpisodeQuery {
+    pub podcast_id: Option<i64>,
+    /// Any of these statuses (as stored, e.g. `summarized`); empty for all
+    pub statuses: Vec<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub until: Option<DateTime<Utc>>,
+    pub tag_id: Option<i64>,
+    pub sort: EpisodeSort,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EpisodeSort {
+    /// Newest first
+    #[default]
+    Published,
+    /// Longest first
+    Duration,
+    /// Pipeline order (new to summarized, then failed and skipped), newest first within each
+    Status,
+}
+
+impl EpisodeSort {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "published" | "date" => Ok(Self::Published),
+            "duration" => Ok(Self::Duration),
+            "status" => Ok(Self::Status),
+            _ => anyhow::bail!("Unknown sort: {s} (expected published, duration or status)"),
+        }
+    }
+
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::Published => "published_at DESC, id DESC",
+            Self::Duration => "duration_secs DESC, published_at DESC, id DESC",
+            Self::Status => {
+                "CASE status WHEN 'new' THEN 0 WHEN 'downloaded' THEN 1 WHEN 'transcribed' THEN 2
+                    WHEN 'summarized' THEN 3 WHEN 'failed' THEN 4 ELSE 5 END,
+                 published_at DESC, id DESC"
+            }
+        }
+    }
+}
+
+impl EpisodeQuery {
+    /// The `WHERE` clause and its parameters (`?1`..).
+    fn conditions(&self) -> (String, Vec<Value>) {
+        let mut values = vec![
+            self.podcast_id.map_or(Value::Null, Value::Integer),
+            self.since
+                .map_or(Value::Null, |d| Value::Text(d.to_rfc3339())),
+            self.until
+                .map_or(Value::Null, |d| Value::Text(d.to_rfc3339())),
+            self.tag_id.map_or(Value::Null, Value::Integer),
+        ];
+        let mut sql = format!(
+            "(?1 IS NULL OR podcast_id = ?1)
+             AND (?2 IS NULL OR published_at >= ?2)
+             AND (?3 IS NULL OR published_at < ?3)
+             AND (?4 IS NULL OR id IN ({}))",
+            tagged_episodes(4)
+        );
+        if !self.statuses.is_empty() {
+            let mut placeholders = Vec::new();
+            for status in &self.statuses {
+                values.push(Value::Text(status.clone()));
+                placeholders.push(format!("?{}", values.len()));
+            }
+            sql.push_str(&format!(" AND status IN ({})", placeholders.join(", ")));
+        }
+        (sql, values)
+    }
+}
 
-use crate::models::{Episode, EpisodeStatus, Podcast, Summary};
+/// Summaries of one episode (`?1`) numbered by generation order.
+const SUMMARY_VERSIONS: &str = "SELECT id, episode_id, content, model, prompt_tokens, output_tokens, created_at, style, prompt_hash, language,
+        ROW_NUMBER() OVER (ORDER BY id) AS version
+     FROM summaries WHERE episode_id = ?1";
+
+/// Jobs with their episode's podcast, for [`Database::map_job`].
+const JOB_SELECT: &str =
+    "SELECT j.id, j.episode_id, e.podcast_id, j.stage, j.final_stage, j.force_summary,
+        j.style, j.language, j.attempts, j.last_error, j.next_run_at, j.last_attempt_at,
+        j.priority
+     FROM jobs j JOIN episodes e ON e.id = j.episode_id";
+
+/// Job times are compared as text, so they share SQLite's `strftime` format.
+fn format_job_time(t: DateTime<Utc>) -> String {
+    t.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
 
 pub struct Database {
     conn: Connection,
@@ -16,6 +128,8 @@ impl Database {
         let conn = Connection::open(path)
             .with_context(|| format!("Failed to open database at {}", path.display()))?;
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
+        // Other sync processes may be writing; wait for them rather than failing
+        conn.busy_timeout(std::time::Duration::from_secs(10))?;
         let db = Self { conn };
         db.migrate()?;
         Ok(db)
@@ -60,13 +174,169 @@ impl Database {
                 created_at    TEXT NOT NULL DEFAULT (datetime('now'))
             );
 
+            CREATE TABLE IF NOT EXISTS summary_topics (
+                summary_id INTEGER NOT NULL REFERENCES summaries(id) ON DELETE CASCADE,
+                topic      TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS summary_takeaways (
+                summary_id INTEGER NOT NULL REFERENCES summaries(id) ON DELETE CASCADE,
+                position   INTEGER NOT NULL,
+                text       TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS summary_quotes (
+                summary_id INTEGER NOT NULL REFERENCES summaries(id) ON DELETE CASCADE,
+                position   INTEGER NOT NULL,
+                text       TEXT NOT NULL,
+                speaker    TEXT,
+                timestamp  TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS transcript_chunks (
+                id         INTEGER PRIMARY KEY AUTOINCREMENT,
+                episode_id INTEGER NOT NULL REFERENCES episodes(id) ON DELETE CASCADE,
+                position   INTEGER NOT NULL,
+                text       TEXT NOT NULL,
+                start_secs REAL,
+                end_secs   REAL,
+                model      TEXT NOT NULL,
+                embedding  BLOB NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS jobs (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                episode_id    INTEGER NOT NULL UNIQUE REFERENCES episodes(id) ON DELETE CASCADE,
+                stage         TEXT NOT NULL,
+                final_stage   TEXT NOT NULL,
+                force_summary INTEGER NOT NULL DEFAULT 0,
+                style         TEXT,
+                language      TEXT,
+                attempts      INTEGER NOT NULL DEFAULT 0,
+                last_error    TEXT,
+                next_run_at   TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS episode_leases (
+                episode_id INTEGER PRIMARY KEY REFERENCES episodes(id) ON DELETE CASCADE,
+                owner      TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS tags (
+                id   INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE
+            );
+
+            -- A podcast's tags apply to all of its episodes, including future ones
+            CREATE TABLE IF NOT EXISTS podcast_tags (
+                podcast_id INTEGER NOT NULL REFERENCES podcasts(id) ON DELETE CASCADE,
+                tag_id     INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (podcast_id, tag_id)
+            );
+
+            -- source: 'manual' or 'topic' (added by auto-tagging)
+            CREATE TABLE IF NOT EXISTS episode_tags (
+                episode_id INTEGER NOT NULL REFERENCES episodes(id) ON DELETE CASCADE,
+                tag_id     INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                source     TEXT NOT NULL DEFAULT 'manual',
+                PRIMARY KEY (episode_id, tag_id)
+            );
+
             CREATE INDEX IF NOT EXISTS idx_episodes_podcast_id ON episodes(podcast_id);
             CREATE INDEX IF NOT EXISTS idx_episodes_status ON episodes(status);
-            CREATE INDEX IF NOT EXISTS idx_summaries_episode_id ON summaries(episode_id);",
+            CREATE INDEX IF NOT EXISTS idx_summaries_episode_id ON summaries(episode_id);
+            CREATE INDEX IF NOT EXISTS idx_summary_topics_topic ON summary_topics(topic COLLATE NOCASE);
+            CREATE INDEX IF NOT EXISTS idx_summary_topics_summary_id ON summary_topics(summary_id);
+            CREATE INDEX IF NOT EXISTS idx_summary_takeaways_summary_id ON summary_takeaways(summary_id);
+            CREATE INDEX IF NOT EXISTS idx_summary_quotes_summary_id ON summary_quotes(summary_id);
+            CREATE INDEX IF NOT EXISTS idx_transcript_chunks_episode_id ON transcript_chunks(episode_id);
+            CREATE INDEX IF NOT EXISTS idx_podcast_tags_tag_id ON podcast_tags(tag_id);
+            CREATE INDEX IF NOT EXISTS idx_episode_tags_tag_id ON episode_tags(tag_id);",
+        )?;
+
+        // Columns added after the initial schema
+        self.add_column_if_missing("summaries", "style", "TEXT")?;
+        self.add_column_if_missing("summaries", "prompt_hash", "TEXT")?;
+        self.add_column_if_missing("summaries", "language", "TEXT")?;
+        self.add_column_if_missing("summaries", "narrative", "TEXT")?;
+        self.add_column_if_missing("jobs", "last_attempt_at", "TEXT")?;
+        self.add_column_if_missing("episodes", "completed_stage", "TEXT")?;
+        self.add_column_if_missing("jobs", "priority", "INTEGER NOT NULL DEFAULT 0")?;
+
+        self.migrate_search_index()?;
+        Ok(())
+    }
+
+    /// Full-text index keyed by episode id. Titles, descriptions and the latest
+    /// summary are kept in sync by triggers; transcripts live in files, so sync
+    /// indexes them explicitly with [`Self::index_transcript`].
+    fn migrate_search_index(&self) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE name = 'search_index'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        self.conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+                title, description, summary, transcript,
+                tokenize = 'trigram'
+            );
+
+            CREATE TRIGGER IF NOT EXISTS episodes_search_insert AFTER INSERT ON episodes BEGIN
+                INSERT INTO search_index (rowid, title, description, summary, transcript)
+                VALUES (new.id, new.title, COALESCE(new.description, ''), '', '');
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS episodes_search_update AFTER UPDATE OF title, description ON episodes BEGIN
+                UPDATE search_index SET title = new.title, description = COALESCE(new.description, '')
+                WHERE rowid = new.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS episodes_search_delete AFTER DELETE ON episodes BEGIN
+                DELETE FROM search_index WHERE rowid = old.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS summaries_search_insert AFTER INSERT ON summaries BEGIN
+                UPDATE search_index SET summary = new.content WHERE rowid = new.episode_id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS summaries_search_delete AFTER DELETE ON summaries BEGIN
+                UPDATE search_index SET summary = COALESCE(
+                    (SELECT content FROM summaries WHERE episode_id = old.episode_id ORDER BY id DESC LIMIT 1), ''
+                ) WHERE rowid = old.episode_id;
+            END;",
         )?;
+
+        if !exists {
+            self.rebuild_search_index()?;
+        }
+        Ok(())
+    }
+
+    fn add_column_if_missing(&self, table: &str, column: &str, decl: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .iter()
+            .any(|name| name == column);
+        if !exists {
+            self.conn
+                .execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"))?;
+        }
         Ok(())
     }
 
+    /// Run `f` in a transaction, rolling back everything it wrote if it fails.
+    pub fn transaction<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        let tx = self.conn.unchecked_transaction()?;
+        let value = f(self)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
     // --- Podcasts ---
 
     pub fn insert_podcast(
@@ -203,7 +473,7 @@ impl Database {
         published_at: Option<DateTime<Utc>>,
         duration_secs: Option<i64>,
     ) -> Result<i64> {
-        self.conn.execute(
+        let changed = self.conn.execute(
             "INSERT OR IGNORE INTO episodes (podcast_id, guid, title, description, audio_url, published_at, duration_secs)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
@@ -216,12 +486,16 @@ impl Database {
                 duration_secs,
             ],
         )?;
+        // An ignored duplicate leaves last_insert_rowid at the previous insert
+        if changed == 0 {
+            return Ok(0);
+        }
         Ok(self.conn.last_insert_rowid())
     }
 
     pub fn get_episode(&self, id: i64) -> Result<Episode> {
         self.conn.query_row(
-            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at
+            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at, completed_stage
              FROM episodes WHERE id = ?1",
             params![id],
             Self::map_episode,
@@ -230,7 +504,7 @@ impl Database {
 
     pub fn list_episodes(&self, podcast_id: i64) -> Result<Vec<Episode>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at
+            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at, completed_stage
              FROM episodes WHERE podcast_id = ?1 ORDER BY published_at DESC",
         )?;
         let episodes = stmt
@@ -239,10 +513,42 @@ impl Database {
         Ok(episodes)
     }
 
-    #[allow(dead_code)]
+    /// One page of the episodes matching `query`, in its sort order.
+    pub fn query_episodes(&self, query: &EpisodeQuery) -> Result<Vec<Episode>> {
+        let (conditions, mut values) = query.conditions();
+        values.push(Value::Integer(query.limit.map_or(-1, |n| n as i64)));
+        values.push(Value::Integer(query.offset as i64));
+        let n = values.len();
+        let sql = format!(
+            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at, completed_stage
+             FROM episodes
+             WHERE {conditions}
+             ORDER BY {}
+             LIMIT ?{} OFFSET ?{n}",
+            query.sort.order_by(),
+            n - 1
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let episodes = stmt
+            .query_map(params_from_iter(values), Self::map_episode)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(episodes)
+    }
+
+    /// How many episodes match `query`, ignoring its limit and offset.
+    pub fn count_episodes(&self, query: &EpisodeQuery) -> Result<usize> {
+        let (conditions, values) = query.conditions();
+        let count: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM episodes WHERE {conditions}"),
+            params_from_iter(values),
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
     pub fn list_episodes_by_status(&self, status: &str) -> Result<Vec<Episode>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at
+            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at, completed_stage
              FROM episodes WHERE status = ?1 ORDER BY published_at DESC",
         )?;
         let episodes = stmt
@@ -253,8 +559,13 @@ impl Database {
 
     pub fn update_episode_status(&self, id: i64, status: &EpisodeStatus) -> Result<()> {
         self.conn.execute(
-            "UPDATE episodes SET status = ?1, fail_reason = ?2 WHERE id = ?3",
-            params![status.as_str(), status.fail_reason(), id],
+            "UPDATE episodes SET status = ?1, fail_reason = ?2, completed_stage = ?3 WHERE id = ?4",
+            params![
+                status.as_str(),
+                status.reason(),
+                status.completed_stage().map(|s| s.as_str()),
+                id
+            ],
         )?;
         Ok(())
     }
@@ -295,438 +606,2050 @@ impl Database {
             .map_err(Into::into)
     }
 
-    // --- Summaries ---
+    // --- Jobs ---
 
-    pub fn insert_summary(
+    /// Queue an episode for the pipeline starting at `stage`, replacing any earlier
+    /// job for it (including a parked failure).
+    pub fn enqueue_job(
         &self,
         episode_id: i64,
-        content: &str,
-        model: &str,
-        prompt_tokens: Option<i64>,
-        output_tokens: Option<i64>,
-    ) -> Result<i64> {
+        stage: JobStage,
+        final_stage: JobStage,
+        force_summary: bool,
+        style: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO summaries (episode_id, content, model, prompt_tokens, output_tokens)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![episode_id, content, model, prompt_tokens, output_tokens],
+            "INSERT INTO jobs (episode_id, stage, final_stage, force_summary, style, language, next_run_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+             ON CONFLICT(episode_id) DO UPDATE SET
+                stage = excluded.stage, final_stage = excluded.final_stage,
+                force_summary = excluded.force_summary, style = excluded.style,
+                language = excluded.language, attempts = 0, last_error = NULL,
+                next_run_at = excluded.next_run_at",
+            params![
+                episode_id,
+                stage.as_str(),
+                final_stage.as_str(),
+                force_summary,
+                style,
+                language,
+            ],
         )?;
+        Ok(())
+    }
+
+    pub fn get_job(&self, episode_id: i64) -> Result<Option<Job>> {
+        let job = self
+            .conn
+            .query_row(
+                &format!("{JOB_SELECT} WHERE j.episode_id = ?1"),
+                params![episode_id],
+                Self::map_job,
+            )
+            .optional()?;
+        Ok(job)
+    }
+
+    /// Jobs at `stage` that are due to run, highest priority then oldest first.
+    pub fn list_runnable_jobs(&self, stage: JobStage) -> Result<Vec<Job>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{JOB_SELECT}
+             WHERE j.stage = ?1 AND j.next_run_at IS NOT NULL
+               AND j.next_run_at <= strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+             ORDER BY j.priority DESC, j.id"
+        ))?;
+        let jobs = stmt
+            .query_map(params![stage.as_str()], Self::map_job)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(jobs)
+    }
+
+    /// Every job, in the order they are processed within a stage.
+    pub fn list_jobs(&self) -> Result<Vec<Job>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{JOB_SELECT} ORDER BY j.priority DESC, j.id"))?;
+        let jobs = stmt
+            .query_map([], Self::map_job)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(jobs)
+    }
+
+    pub fn set_job_priority(&self, episode_id: i64, priority: i64) -> Result<()> {
         self.conn.execute(
-            "UPDATE episodes SET status = 'summarized' WHERE id = ?1",
+            "UPDATE jobs SET priority = ?1 WHERE episode_id = ?2",
+            params![priority, episode_id],
+        )?;
+        Ok(())
+    }
+
+    /// Drop an episode's job. Returns false if it had none.
+    pub fn delete_job(&self, episode_id: i64) -> Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM jobs WHERE episode_id = ?1",
             params![episode_id],
         )?;
-        Ok(self.conn.last_insert_rowid())
+        Ok(deleted > 0)
     }
 
-    pub fn get_summary_by_episode(&self, episode_id: i64) -> Result<Option<Summary>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, episode_id, content, model, prompt_tokens, output_tokens, created_at
-             FROM summaries WHERE episode_id = ?1 ORDER BY created_at DESC LIMIT 1",
+    /// Drop all jobs, or one podcast's. Returns how many were dropped.
+    pub fn clear_jobs(&self, podcast_id: Option<i64>) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM jobs WHERE ?1 IS NULL
+                OR episode_id IN (SELECT id FROM episodes WHERE podcast_id = ?1)",
+            params![podcast_id],
         )?;
-        let mut rows = stmt.query(params![episode_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Summary {
-                id: row.get(0)?,
-                episode_id: row.get(1)?,
-                content: row.get(2)?,
-                model: row.get(3)?,
-                prompt_tokens: row.get(4)?,
-                output_tokens: row.get(5)?,
-                created_at: row
-                    .get::<_, String>(6)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(deleted)
     }
 
-    pub fn delete_summary_by_episode(&self, episode_id: i64) -> Result<()> {
+    /// Move a job on to `stage`, ready to run now.
+    pub fn advance_job(&self, id: i64, stage: JobStage) -> Result<()> {
         self.conn.execute(
-            "DELETE FROM summaries WHERE episode_id = ?1",
-            params![episode_id],
+            "UPDATE jobs SET stage = ?1, attempts = 0, last_error = NULL,
+                next_run_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+             WHERE id = ?2",
+            params![stage.as_str(), id],
         )?;
         Ok(())
     }
 
-    pub fn clear_episode_transcript(&self, id: i64) -> Result<()> {
+    /// Record a failed attempt. The job stays at its stage and runs again at
+    /// `retry_at`, or is parked when that is `None`.
+    pub fn fail_job(&self, id: i64, error: &str, retry_at: Option<DateTime<Utc>>) -> Result<()> {
         self.conn.execute(
-            "UPDATE episodes SET transcript_path = NULL, status = 'downloaded' WHERE id = ?1",
-            params![id],
+            "UPDATE jobs SET attempts = attempts + 1, last_error = ?1, next_run_at = ?2,
+                last_attempt_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+             WHERE id = ?3",
+            params![error, retry_at.map(format_job_time), id],
         )?;
         Ok(())
     }
 
-    fn map_episode(row: &rusqlite::Row<'_>) -> rusqlite::Result<Episode> {
-        let status_str: String = row.get(8)?;
-        let fail_reason: Option<String> = row.get(9)?;
-        Ok(Episode {
-            id: row.get(0)?,
-            podcast_id: row.get(1)?,
-            guid: row.get(2)?,
-            title: row.get(3)?,
-            description: row.get(4)?,
-            audio_url: row.get(5)?,
-            published_at: row
-                .get::<_, Option<String>>(6)?
-                .and_then(|s| s.parse().ok()),
-            duration_secs: row.get(7)?,
-            status: EpisodeStatus::from_db(&status_str, fail_reason.as_deref()),
-            audio_path: row.get(10)?,
-            transcript_path: row.get(11)?,
-            discovered_at: row
-                .get::<_, String>(12)?
-                .parse()
-                .unwrap_or_else(|_| Utc::now()),
-        })
+    /// Make a job due at `at` without resetting its attempts.
+    pub fn schedule_job(&self, id: i64, at: DateTime<Utc>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE jobs SET next_run_at = ?1 WHERE id = ?2",
+            params![format_job_time(at), id],
+        )?;
+        Ok(())
     }
 
-    #[cfg(test)]
-    pub fn open_in_memory() -> Result<Self> {
-        let conn = Connection::open_in_memory()?;
-        conn.execute_batch("PRAGMA foreign_keys=ON;")?;
-        let db = Self { conn };
-        db.migrate()?;
-        Ok(db)
+    pub fn complete_job(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM jobs WHERE id = ?1", params![id])?;
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // --- Episode leases ---
+
+    /// Claim an episode for `owner` for `ttl_secs`, unless another owner holds an
+    /// unexpired lease. Claiming an episode already held by `owner` renews it.
+    pub fn claim_episode(&self, episode_id: i64, owner: &str, ttl_secs: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT INTO episode_leases (episode_id, owner, expires_at)
+             VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%SZ', 'now', ?3 || ' seconds'))
+             ON CONFLICT(episode_id) DO UPDATE SET owner = excluded.owner, expires_at = excluded.expires_at
+             WHERE episode_leases.owner = excluded.owner
+                OR episode_leases.expires_at <= strftime('%Y-%m-%dT%H:%M:%SZ', 'now')",
+            params![episode_id, owner, ttl_secs],
+        )?;
+        Ok(changed > 0)
+    }
 
-    fn test_db() -> Database {
-        Database::open_in_memory().unwrap()
+    pub fn release_episode(&self, episode_id: i64, owner: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM episode_leases WHERE episode_id = ?1 AND owner = ?2",
+            params![episode_id, owner],
+        )?;
+        Ok(())
     }
 
-    // --- Podcast CRUD ---
+    /// Owner of an unexpired lease on the episode.
+    pub fn episode_lease_owner(&self, episode_id: i64) -> Result<Option<String>> {
+        let owner = self
+            .conn
+            .query_row(
+                "SELECT owner FROM episode_leases
+                 WHERE episode_id = ?1 AND expires_at > strftime('%Y-%m-%dT%H:%M:%SZ', 'now')",
+                params![episode_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(owner)
+    }
 
-    #[test]
-    fn insert_and_get_podcast() {
-        let db = test_db();
-        let p = db
-            .insert_podcast("https://example.com/feed.xml", "Test Pod", None, None)
-            .unwrap();
-        assert_eq!(p.title, "Test Pod");
-        assert_eq!(p.feed_url, "https://example.com/feed.xml");
+    // --- Summaries ---
 
-        let fetched = db.get_podcast(p.id).unwrap();
-        assert_eq!(fetched.title, "Test Pod");
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_summary(
+        &self,
+        episode_id: i64,
+        content: &str,
+        model: &str,
+        prompt_tokens: Option<i64>,
+        output_tokens: Option<i64>,
+        style: Option<&str>,
+        prompt_hash: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO summaries (episode_id, content, model, prompt_tokens, output_tokens, style, prompt_hash, language)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                episode_id,
+                content,
+                model,
+                prompt_tokens,
+                output_tokens,
+                style,
+                prompt_hash,
+                language
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "UPDATE episodes SET status = 'summarized' WHERE id = ?1",
+            params![episode_id],
+        )?;
+        Ok(id)
     }
 
-    #[test]
-    fn insert_podcast_with_optional_fields() {
-        let db = test_db();
-        let p = db
-            .insert_podcast(
-                "https://ex.com/feed",
-                "Pod",
-                Some("https://ex.com"),
-                Some("A podcast"),
-            )
-            .unwrap();
-        assert_eq!(p.website_url.as_deref(), Some("https://ex.com"));
-        assert_eq!(p.description.as_deref(), Some("A podcast"));
+    /// Latest summary for an episode.
+    pub fn get_summary_by_episode(&self, episode_id: i64) -> Result<Option<Summary>> {
+        self.query_summary(
+            &format!("SELECT * FROM ({SUMMARY_VERSIONS}) ORDER BY version DESC LIMIT 1"),
+            params![episode_id],
+        )
     }
 
-    #[test]
-    fn duplicate_feed_url_fails() {
-        let db = test_db();
-        db.insert_podcast("https://example.com/feed.xml", "First", None, None)
-            .unwrap();
-        let result = db.insert_podcast("https://example.com/feed.xml", "Second", None, None);
-        assert!(result.is_err());
+    /// A specific version (1-based, oldest first) of an episode's summary.
+    pub fn get_summary_version(&self, episode_id: i64, version: i64) -> Result<Option<Summary>> {
+        self.query_summary(
+            &format!("SELECT * FROM ({SUMMARY_VERSIONS}) WHERE version = ?2"),
+            params![episode_id, version],
+        )
     }
 
-    #[test]
-    fn find_podcast_by_name_partial_match() {
-        let db = test_db();
-        db.insert_podcast("https://ex.com/f1", "Rust Weekly", None, None)
-            .unwrap();
-        db.insert_podcast("https://ex.com/f2", "Go Monthly", None, None)
-            .unwrap();
-
-        let found = db.find_podcast_by_name("rust").unwrap();
-        assert!(found.is_some());
-        assert_eq!(found.unwrap().title, "Rust Weekly");
+    /// Latest summary for an episode generated by the given model.
+    pub fn get_latest_summary_by_model(
+        &self,
+        episode_id: i64,
+        model: &str,
+    ) -> Result<Option<Summary>> {
+        self.query_summary(
+            &format!(
+                "SELECT * FROM ({SUMMARY_VERSIONS}) WHERE model = ?2 ORDER BY version DESC LIMIT 1"
+            ),
+            params![episode_id, model],
+        )
     }
 
-    #[test]
-    fn find_podcast_by_name_no_match() {
-        let db = test_db();
-        db.insert_podcast("https://ex.com/f1", "Rust Weekly", None, None)
-            .unwrap();
-        let found = db.find_podcast_by_name("python").unwrap();
-        assert!(found.is_none());
+    /// Latest summary for an episode written in the given language.
+    pub fn get_latest_summary_by_language(
+        &self,
+        episode_id: i64,
+        language: &str,
+    ) -> Result<Option<Summary>> {
+        self.query_summary(
+            &format!(
+                "SELECT * FROM ({SUMMARY_VERSIONS}) WHERE language = ?2 COLLATE NOCASE ORDER BY version DESC LIMIT 1"
+            ),
+            params![episode_id, language],
+        )
     }
 
-    #[test]
-    fn find_podcast_by_url() {
-        let db = test_db();
-        db.insert_podcast("https://ex.com/feed.xml", "Test", None, None)
-            .unwrap();
-        let found = db.find_podcast_by_url("https://ex.com/feed.xml").unwrap();
-        assert!(found.is_some());
-        assert_eq!(found.unwrap().title, "Test");
+    /// All summary versions for an episode, oldest first.
+    pub fn list_summaries_by_episode(&self, episode_id: i64) -> Result<Vec<Summary>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM ({SUMMARY_VERSIONS}) ORDER BY version"
+        ))?;
+        let summaries = stmt
+            .query_map(params![episode_id], Self::map_summary)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(summaries)
     }
 
-    #[test]
-    fn find_podcast_by_url_not_found() {
-        let db = test_db();
-        let found = db.find_podcast_by_url("https://nope.com/feed.xml").unwrap();
-        assert!(found.is_none());
+    /// The latest summary of each episode, for episodes whose latest summary was
+    /// created at or after `since`, oldest first.
+    pub fn list_summaries_since(&self, since: DateTime<Utc>) -> Result<Vec<Summary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, episode_id, content, model, prompt_tokens, output_tokens, created_at, style, prompt_hash, language, version
+             FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY episode_id ORDER BY id) AS version,
+                       MAX(id) OVER (PARTITION BY episode_id) AS latest_id
+                FROM summaries
+             )
+             WHERE id = latest_id AND created_at >= ?1
+             ORDER BY id",
+        )?;
+        let summaries = stmt
+            .query_map(params![dates::to_db_time(since)], Self::map_summary)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(summaries)
     }
 
-    #[test]
-    fn list_podcasts_ordered_by_title() {
+    /// The latest summary of each episode (optionally of one podcast), most
+    /// recently created first.
+    pub fn list_latest_summaries(
+        &self,
+        podcast_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Summary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, episode_id, content, model, prompt_tokens, output_tokens, created_at, style, prompt_hash, language, version
+             FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY episode_id ORDER BY id) AS version,
+                       MAX(id) OVER (PARTITION BY episode_id) AS latest_id
+                FROM summaries
+             )
+             WHERE id = latest_id
+               AND (?1 IS NULL OR episode_id IN (SELECT id FROM episodes WHERE podcast_id = ?1))
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let summaries = stmt
+            .query_map(params![podcast_id, limit as i64], Self::map_summary)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(summaries)
+    }
+
+    /// Store the typed sections of a summary.
+    pub fn insert_summary_sections(
+        &self,
+        summary_id: i64,
+        sections: &StructuredSummary,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE summaries SET narrative = ?1 WHERE id = ?2",
+            params![sections.summary, summary_id],
+        )?;
+        for topic in &sections.topics {
+            self.conn.execute(
+                "INSERT INTO summary_topics (summary_id, topic) VALUES (?1, ?2)",
+                params![summary_id, topic],
+            )?;
+        }
+        for (i, text) in sections.key_takeaways.iter().enumerate() {
+            self.conn.execute(
+                "INSERT INTO summary_takeaways (summary_id, position, text) VALUES (?1, ?2, ?3)",
+                params![summary_id, i as i64, text],
+            )?;
+        }
+        for (i, q) in sections.notable_quotes.iter().enumerate() {
+            self.conn.execute(
+                "INSERT INTO summary_quotes (summary_id, position, text, speaker, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![summary_id, i as i64, q.text, q.speaker, q.timestamp],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Typed sections of a summary, if any were stored.
+    pub fn get_summary_sections(&self, summary_id: i64) -> Result<Option<StructuredSummary>> {
+        let narrative: Option<String> = self.conn.query_row(
+            "SELECT narrative FROM summaries WHERE id = ?1",
+            params![summary_id],
+            |row| row.get(0),
+        )?;
+        let Some(narrative) = narrative else {
+            return Ok(None);
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT topic FROM summary_topics WHERE summary_id = ?1 ORDER BY rowid")?;
+        let topics = stmt
+            .query_map(params![summary_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT text FROM summary_takeaways WHERE summary_id = ?1 ORDER BY position",
+        )?;
+        let key_takeaways = stmt
+            .query_map(params![summary_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT text, speaker, timestamp FROM summary_quotes WHERE summary_id = ?1 ORDER BY position",
+        )?;
+        let notable_quotes = stmt
+            .query_map(params![summary_id], |row| {
+                Ok(Quote {
+                    text: row.get(0)?,
+                    speaker: row.get(1)?,
+                    timestamp: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(Some(StructuredSummary {
+            topics,
+            summary: narrative,
+            key_takeaways,
+            notable_quotes,
+        }))
+    }
+
+    /// Topics across the latest summary of every episode, with episode counts,
+    /// most common first.
+    pub fn list_topics(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT MIN(t.topic), COUNT(DISTINCT s.episode_id) AS n
+             FROM summary_topics t
+             JOIN summaries s ON s.id = t.summary_id
+             WHERE s.id IN (SELECT MAX(id) FROM summaries GROUP BY episode_id)
+             GROUP BY LOWER(t.topic)
+             ORDER BY n DESC, LOWER(t.topic)",
+        )?;
+        let topics = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(topics)
+    }
+
+    /// Episodes whose latest summary mentions a topic (case-insensitive substring).
+    pub fn list_episodes_by_topic(&self, topic: &str) -> Result<Vec<Episode>> {
+        let pattern = search::like_pattern(topic);
+        let mut stmt = self.conn.prepare(
+            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at, completed_stage
+             FROM episodes WHERE id IN (
+                SELECT s.episode_id FROM summary_topics t
+                JOIN summaries s ON s.id = t.summary_id
+                WHERE t.topic LIKE ?1 ESCAPE '\\'
+                  AND s.id IN (SELECT MAX(id) FROM summaries GROUP BY episode_id)
+             )
+             ORDER BY published_at DESC",
+        )?;
+        let episodes = stmt
+            .query_map(params![pattern], Self::map_episode)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(episodes)
+    }
+
+    fn query_summary(&self, sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Option<Summary>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let mut rows = stmt.query(params)?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::map_summary(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn clear_episode_transcript(&self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE episodes SET transcript_path = NULL, status = 'downloaded' WHERE id = ?1",
+            params![id],
+        )?;
+        self.index_transcript(id, "")?;
+        self.conn.execute(
+            "DELETE FROM transcript_chunks WHERE episode_id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    // --- Tags ---
+
+    /// The ID of a tag, creating it if it doesn't exist yet.
+    pub fn ensure_tag(&self, name: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            params![name],
+        )?;
+        let id = self.conn.query_row(
+            "SELECT id FROM tags WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// The ID of a tag by name (case-insensitive).
+    pub fn find_tag(&self, name: &str) -> Result<Option<i64>> {
+        let id = self
+            .conn
+            .query_row(
+                "SELECT id FROM tags WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    /// All tags with their direct podcast and episode counts, by name.
+    pub fn list_tags(&self) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.name,
+                    (SELECT COUNT(*) FROM podcast_tags WHERE tag_id = t.id),
+                    (SELECT COUNT(*) FROM episode_tags WHERE tag_id = t.id)
+             FROM tags t ORDER BY t.name",
+        )?;
+        let tags = stmt
+            .query_map([], |row| {
+                Ok(Tag {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    podcasts: row.get(2)?,
+                    episodes: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    /// Returns false if the podcast already had the tag.
+    pub fn tag_podcast(&self, podcast_id: i64, tag_id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT OR IGNORE INTO podcast_tags (podcast_id, tag_id) VALUES (?1, ?2)",
+            params![podcast_id, tag_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Returns false if the podcast didn't have the tag.
+    pub fn untag_podcast(&self, podcast_id: i64, tag_id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM podcast_tags WHERE podcast_id = ?1 AND tag_id = ?2",
+            params![podcast_id, tag_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Tag one episode; `source` says who added it (`manual` or `topic`).
+    /// Returns false if the episode already had the tag.
+    pub fn tag_episode(&self, episode_id: i64, tag_id: i64, source: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT OR IGNORE INTO episode_tags (episode_id, tag_id, source) VALUES (?1, ?2, ?3)",
+            params![episode_id, tag_id, source],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Returns false if the episode didn't carry the tag directly.
+    pub fn untag_episode(&self, episode_id: i64, tag_id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM episode_tags WHERE episode_id = ?1 AND tag_id = ?2",
+            params![episode_id, tag_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Drop tags no podcast or episode carries any more.
+    pub fn delete_unused_tags(&self) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM tags
+             WHERE id NOT IN (SELECT tag_id FROM podcast_tags)
+               AND id NOT IN (SELECT tag_id FROM episode_tags)",
+            [],
+        )?;
+        Ok(deleted)
+    }
+
+    /// IDs of the podcasts carrying a tag.
+    pub fn tagged_podcast_ids(&self, tag_id: i64) -> Result<HashSet<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT podcast_id FROM podcast_tags WHERE tag_id = ?1")?;
+        let ids = stmt
+            .query_map(params![tag_id], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// IDs of the episodes carrying a tag, directly or through their podcast.
+    pub fn tagged_episode_ids(&self, tag_id: i64) -> Result<HashSet<i64>> {
+        let mut stmt = self.conn.prepare(&tagged_episodes(1))?;
+        let ids = stmt
+            .query_map(params![tag_id], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Tag names of one episode, its own and its podcast's, by name.
+    pub fn episode_tag_names(&self, episode_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name FROM tags t WHERE t.id IN (
+                SELECT tag_id FROM episode_tags WHERE episode_id = ?1
+                UNION SELECT pt.tag_id FROM podcast_tags pt
+                JOIN episodes e ON e.podcast_id = pt.podcast_id WHERE e.id = ?1
+             )
+             ORDER BY t.name",
+        )?;
+        let names = stmt
+            .query_map(params![episode_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(names)
+    }
+
+    // --- Embeddings ---
+
+    /// Replace an episode's embedded chunks for `model`.
+    pub fn replace_transcript_chunks(
+        &self,
+        episode_id: i64,
+        model: &str,
+        chunks: &[(embed::Chunk, Vec<f32>)],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM transcript_chunks WHERE episode_id = ?1 AND model = ?2",
+            params![episode_id, model],
+        )?;
+        for (i, (chunk, vector)) in chunks.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO transcript_chunks (episode_id, position, text, start_secs, end_secs, model, embedding)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    episode_id,
+                    i as i64,
+                    chunk.text,
+                    chunk.start_secs,
+                    chunk.end_secs,
+                    model,
+                    embed::encode_vector(vector),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// All chunks embedded with `model`, optionally for one podcast.
+    pub fn list_transcript_chunks(
+        &self,
+        model: &str,
+        podcast_id: Option<i64>,
+    ) -> Result<Vec<TranscriptChunk>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.episode_id, c.position, c.text, c.start_secs, c.end_secs, c.embedding
+             FROM transcript_chunks c
+             JOIN episodes e ON e.id = c.episode_id
+             WHERE c.model = ?1 AND (?2 IS NULL OR e.podcast_id = ?2)
+             ORDER BY c.episode_id, c.position",
+        )?;
+        let chunks = stmt
+            .query_map(params![model, podcast_id], |row| {
+                Ok(TranscriptChunk {
+                    id: row.get(0)?,
+                    episode_id: row.get(1)?,
+                    position: row.get(2)?,
+                    text: row.get(3)?,
+                    start_secs: row.get(4)?,
+                    end_secs: row.get(5)?,
+                    embedding: embed::decode_vector(&row.get::<_, Vec<u8>>(6)?),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(chunks)
+    }
+
+    /// Transcribed episodes with no chunks embedded by `model`.
+    pub fn list_episodes_missing_embeddings(&self, model: &str) -> Result<Vec<Episode>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, podcast_id, guid, title, description, audio_url, published_at, duration_secs, status, fail_reason, audio_path, transcript_path, discovered_at, completed_stage
+             FROM episodes e
+             WHERE transcript_path IS NOT NULL
+               AND NOT EXISTS (SELECT 1 FROM transcript_chunks c WHERE c.episode_id = e.id AND c.model = ?1)
+             ORDER BY published_at DESC",
+        )?;
+        let episodes = stmt
+            .query_map(params![model], Self::map_episode)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(episodes)
+    }
+
+    // --- Search ---
+
+    pub fn index_transcript(&self, episode_id: i64, transcript: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE search_index SET transcript = ?1 WHERE rowid = ?2",
+            params![transcript, episode_id],
+        )?;
+        Ok(())
+    }
+
+    /// Rebuild the search index from the database and transcript files.
+    /// Returns the number of episodes indexed.
+    pub fn rebuild_search_index(&self) -> Result<usize> {
+        self.conn.execute_batch(
+            "DELETE FROM search_index;
+             INSERT INTO search_index (rowid, title, description, summary, transcript)
+             SELECT e.id, e.title, COALESCE(e.description, ''),
+                    COALESCE((SELECT content FROM summaries s WHERE s.episode_id = e.id ORDER BY s.id DESC LIMIT 1), ''),
+                    ''
+             FROM episodes e;",
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, transcript_path FROM episodes WHERE transcript_path IS NOT NULL",
+        )?;
+        let transcripts = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (id, path) in transcripts {
+            // A missing transcript file just leaves the episode unindexed
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                self.index_transcript(id, &text)?;
+            }
+        }
+
+        let count = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM search_index", [], |row| {
+                row.get::<_, i64>(0)
+            })?;
+        Ok(count as usize)
+    }
+
+    /// Episodes matching every term of `query`, best match first. `until` is exclusive.
+    pub fn search(
+        &self,
+        query: &str,
+        podcast_id: Option<i64>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        tag_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let terms = search::terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut values = vec![
+            podcast_id.map_or(Value::Null, Value::Integer),
+            since.map_or(Value::Null, |d| Value::Text(d.to_rfc3339())),
+            until.map_or(Value::Null, |d| Value::Text(d.to_rfc3339())),
+            Value::Integer(limit as i64),
+            tag_id.map_or(Value::Null, Value::Integer),
+        ];
+        let filters = format!(
+            "(?1 IS NULL OR e.podcast_id = ?1)
+             AND (?2 IS NULL OR e.published_at >= ?2)
+             AND (?3 IS NULL OR e.published_at < ?3)
+             AND (?5 IS NULL OR e.id IN ({}))",
+            tagged_episodes(5)
+        );
+
+        if search::is_indexable(&terms) {
+            values.push(Value::Text(search::match_expression(&terms)));
+            values.push(Value::Text(search::HIGHLIGHT_START.to_string()));
+            values.push(Value::Text(search::HIGHLIGHT_END.to_string()));
+            let sql = format!(
+                "SELECT e.id, e.title, p.title, e.published_at,
+                        snippet(search_index, -1, ?7, ?8, '…', 32)
+                 FROM search_index
+                 JOIN episodes e ON e.id = search_index.rowid
+                 JOIN podcasts p ON p.id = e.podcast_id
+                 WHERE search_index MATCH ?6 AND {filters}
+                 ORDER BY bm25(search_index, 10.0, 2.0, 5.0, 1.0)
+                 LIMIT ?4"
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let hits = stmt
+                .query_map(params_from_iter(values), |row| {
+                    Ok(SearchHit {
+                        episode_id: row.get(0)?,
+                        episode_title: row.get(1)?,
+                        podcast_title: row.get(2)?,
+                        published_at: row
+                            .get::<_, Option<String>>(3)?
+                            .and_then(|s| s.parse().ok()),
+                        snippet: row.get(4)?,
+                    })
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            return Ok(hits);
+        }
+
+        // Terms too short for trigrams: scan with LIKE and cut snippets ourselves
+        let mut conditions = Vec::new();
+        for term in &terms {
+            values.push(Value::Text(search::like_pattern(term)));
+            let n = values.len();
+            conditions.push(format!(
+                "(s.title LIKE ?{n} ESCAPE '\\' OR s.description LIKE ?{n} ESCAPE '\\'
+                  OR s.summary LIKE ?{n} ESCAPE '\\' OR s.transcript LIKE ?{n} ESCAPE '\\')"
+            ));
+        }
+        let sql = format!(
+            "SELECT e.id, e.title, p.title, e.published_at, s.summary, s.transcript, s.description
+             FROM search_index s
+             JOIN episodes e ON e.id = s.rowid
+             JOIN podcasts p ON p.id = e.podcast_id
+             WHERE {} AND {filters}
+             ORDER BY e.published_at DESC
+             LIMIT ?4",
+            conditions.join(" AND ")
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let hits = stmt
+            .query_map(params_from_iter(values), |row| {
+                let title: String = row.get(1)?;
+                let fields: [String; 3] = [row.get(4)?, row.get(5)?, row.get(6)?];
+                let snippet = fields
+                    .iter()
+                    .chain(std::iter::once(&title))
+                    .find_map(|text| search::snippet(text, &terms))
+                    .unwrap_or_default();
+                Ok(SearchHit {
+                    episode_id: row.get(0)?,
+                    episode_title: title,
+                    podcast_title: row.get(2)?,
+                    published_at: row
+                        .get::<_, Option<String>>(3)?
+                        .and_then(|s| s.parse().ok()),
+                    snippet,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(hits)
+    }
+
+    fn map_summary(row: &rusqlite::Row<'_>) -> rusqlite::Result<Summary> {
+        Ok(Summary {
+            id: row.get(0)?,
+            episode_id: row.get(1)?,
+            content: row.get(2)?,
+            model: row.get(3)?,
+            prompt_tokens: row.get(4)?,
+            output_tokens: row.get(5)?,
+            created_at: dates::parse_db_time(&row.get::<_, String>(6)?).unwrap_or_else(Utc::now),
+            style: row.get(7)?,
+            prompt_hash: row.get(8)?,
+            language: row.get(9)?,
+            version: row.get(10)?,
+        })
+    }
+
+    fn map_episode(row: &rusqlite::Row<'_>) -> rusqlite::Result<Episode> {
+        let status_str: String = row.get(8)?;
+        let fail_reason: Option<String> = row.get(9)?;
+        let completed_stage: Option<String> = row.get(13)?;
+        Ok(Episode {
+            id: row.get(0)?,
+            podcast_id: row.get(1)?,
+            guid: row.get(2)?,
+            title: row.get(3)?,
+            description: row.get(4)?,
+            audio_url: row.get(5)?,
+            published_at: row
+                .get::<_, Option<String>>(6)?
+                .and_then(|s| s.parse().ok()),
+            duration_secs: row.get(7)?,
+            status: EpisodeStatus::from_db(
+                &status_str,
+                fail_reason.as_deref(),
+                completed_stage.as_deref(),
+            ),
+            audio_path: row.get(10)?,
+            transcript_path: row.get(11)?,
+            discovered_at: dates::parse_db_time(&row.get::<_, String>(12)?)
+                .unwrap_or_else(Utc::now),
+        })
+    }
+
+    fn map_job(row: &rusqlite::Row<'_>) -> rusqlite::Result<Job> {
+        Ok(Job {
+            id: row.get(0)?,
+            episode_id: row.get(1)?,
+            podcast_id: row.get(2)?,
+            stage: JobStage::from_db(&row.get::<_, String>(3)?),
+            final_stage: JobStage::from_db(&row.get::<_, String>(4)?),
+            force_summary: row.get(5)?,
+            style: row.get(6)?,
+            language: row.get(7)?,
+            attempts: row.get(8)?,
+            last_error: row.get(9)?,
+            next_run_at: row
+                .get::<_, Option<String>>(10)?
+                .and_then(|s| s.parse().ok()),
+            last_attempt_at: row
+                .get::<_, Option<String>>(11)?
+                .and_then(|s| s.parse().ok()),
+            priority: row.get(12)?,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+        let db = Self { conn };
+        db.migrate()?;
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Failure;
+
+    fn test_db() -> Database {
+        Database::open_in_memory().unwrap()
+    }
+
+    // --- Podcast CRUD ---
+
+    #[test]
+    fn insert_and_get_podcast() {
+        let db = test_db();
+        let p = db
+            .insert_podcast("https://example.com/feed.xml", "Test Pod", None, None)
+            .unwrap();
+        assert_eq!(p.title, "Test Pod");
+        assert_eq!(p.feed_url, "https://example.com/feed.xml");
+
+        let fetched = db.get_podcast(p.id).unwrap();
+        assert_eq!(fetched.title, "Test Pod");
+    }
+
+    #[test]
+    fn insert_podcast_with_optional_fields() {
+        let db = test_db();
+        let p = db
+            .insert_podcast(
+                "https://ex.com/feed",
+                "Pod",
+                Some("https://ex.com"),
+                Some("A podcast"),
+            )
+            .unwrap();
+        assert_eq!(p.website_url.as_deref(), Some("https://ex.com"));
+        assert_eq!(p.description.as_deref(), Some("A podcast"));
+    }
+
+    #[test]
+    fn duplicate_feed_url_fails() {
+        let db = test_db();
+        db.insert_podcast("https://example.com/feed.xml", "First", None, None)
+            .unwrap();
+        let result = db.insert_podcast("https://example.com/feed.xml", "Second", None, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn find_podcast_by_name_partial_match() {
+        let db = test_db();
+        db.insert_podcast("https://ex.com/f1", "Rust Weekly", None, None)
+            .unwrap();
+        db.insert_podcast("https://ex.com/f2", "Go Monthly", None, None)
+            .unwrap();
+
+        let found = db.find_podcast_by_name("rust").unwrap();
+        assert!(found.is_some());
+        assert_eq!(found.unwrap().title, "Rust Weekly");
+    }
+
+    #[test]
+    fn find_podcast_by_name_no_match() {
+        let db = test_db();
+        db.insert_podcast("https://ex.com/f1", "Rust Weekly", None, None)
+            .unwrap();
+        let found = db.find_podcast_by_name("python").unwrap();
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn find_podcast_by_url() {
+        let db = test_db();
+        db.insert_podcast("https://ex.com/feed.xml", "Test", None, None)
+            .unwrap();
+        let found = db.find_podcast_by_url("https://ex.com/feed.xml").unwrap();
+        assert!(found.is_some());
+        assert_eq!(found.unwrap().title, "Test");
+    }
+
+    #[test]
+    fn find_podcast_by_url_not_found() {
+        let db = test_db();
+        let found = db.find_podcast_by_url("https://nope.com/feed.xml").unwrap();
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn list_podcasts_ordered_by_title() {
+        let db = test_db();
+        db.insert_podcast("https://ex.com/b", "Bravo", None, None)
+            .unwrap();
+        db.insert_podcast("https://ex.com/a", "Alpha", None, None)
+            .unwrap();
+        db.insert_podcast("https://ex.com/c", "Charlie", None, None)
+            .unwrap();
+
+        let list = db.list_podcasts().unwrap();
+        let titles: Vec<&str> = list.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["Alpha", "Bravo", "Charlie"]);
+    }
+
+    #[test]
+    fn list_podcasts_empty() {
+        let db = test_db();
+        let list = db.list_podcasts().unwrap();
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn delete_podcast() {
+        let db = test_db();
+        let p = db
+            .insert_podcast("https://ex.com/feed", "ToDelete", None, None)
+            .unwrap();
+        db.delete_podcast(p.id).unwrap();
+        assert!(db.get_podcast(p.id).is_err());
+    }
+
+    #[test]
+    fn update_last_checked() {
+        let db = test_db();
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        assert!(p.last_checked.is_none());
+        db.update_last_checked(p.id).unwrap();
+        let updated = db.get_podcast(p.id).unwrap();
+        assert!(updated.last_checked.is_some());
+    }
+
+    // --- Episode CRUD ---
+
+    fn insert_test_podcast(db: &Database) -> Podcast {
+        db.insert_podcast("https://ex.com/feed", "Test Pod", None, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn insert_and_get_episode() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(
+                p.id,
+                "guid-1",
+                "Episode 1",
+                None,
+                "https://ex.com/ep1.mp3",
+                None,
+                Some(3600),
+            )
+            .unwrap();
+        assert!(ep_id > 0);
+
+        let ep = db.get_episode(ep_id).unwrap();
+        assert_eq!(ep.title, "Episode 1");
+        assert_eq!(ep.guid, "guid-1");
+        assert_eq!(ep.duration_secs, Some(3600));
+        assert_eq!(ep.status, EpisodeStatus::New);
+    }
+
+    #[test]
+    fn duplicate_guid_ignored() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        db.insert_episode(
+            p.id,
+            "guid-1",
+            "First",
+            None,
+            "https://ex.com/1.mp3",
+            None,
+            None,
+        )
+        .unwrap();
+        // INSERT OR IGNORE - duplicate guid same podcast is ignored
+        let id = db
+            .insert_episode(
+                p.id,
+                "guid-1",
+                "Duplicate",
+                None,
+                "https://ex.com/2.mp3",
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(id, 0);
+        let episodes = db.list_episodes(p.id).unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].title, "First");
+    }
+
+    #[test]
+    fn list_episodes_for_podcast() {
         let db = test_db();
-        db.insert_podcast("https://ex.com/b", "Bravo", None, None)
+        let p = insert_test_podcast(&db);
+        db.insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
             .unwrap();
-        db.insert_podcast("https://ex.com/a", "Alpha", None, None)
+        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+
+        let episodes = db.list_episodes(p.id).unwrap();
+        assert_eq!(episodes.len(), 2);
+    }
+
+    #[test]
+    fn list_episodes_by_status() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep1 = db
+            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+        db.update_episode_audio_path(ep1, "/tmp/audio.mp3").unwrap();
+
+        let downloaded = db.list_episodes_by_status("downloaded").unwrap();
+        assert_eq!(downloaded.len(), 1);
+        assert_eq!(downloaded[0].title, "Ep 1");
+
+        let new_eps = db.list_episodes_by_status("new").unwrap();
+        assert_eq!(new_eps.len(), 1);
+    }
+
+    // --- Status lifecycle ---
+
+    #[test]
+    fn episode_status_lifecycle() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+            .unwrap();
+
+        // new -> downloaded
+        db.update_episode_audio_path(ep_id, "/tmp/audio.mp3")
+            .unwrap();
+        let ep = db.get_episode(ep_id).unwrap();
+        assert_eq!(ep.status, EpisodeStatus::Downloaded);
+
+        // downloaded -> transcribed
+        db.update_episode_transcript_path(ep_id, "/tmp/transcript.txt")
+            .unwrap();
+        let ep = db.get_episode(ep_id).unwrap();
+        assert_eq!(ep.status, EpisodeStatus::Transcribed);
+    }
+
+    #[test]
+    fn episode_status_to_summarized() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+            .unwrap();
+        db.update_episode_audio_path(ep_id, "/tmp/a.mp3").unwrap();
+        db.update_episode_transcript_path(ep_id, "/tmp/t.txt")
+            .unwrap();
+        db.insert_summary(
+            ep_id,
+            "Summary text",
+            "gpt-4",
+            Some(100),
+            Some(50),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let ep = db.get_episode(ep_id).unwrap();
+        assert_eq!(ep.status, EpisodeStatus::Summarized);
+    }
+
+    #[test]
+    fn episode_status_failed() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+            .unwrap();
+        let failed = EpisodeStatus::Failed {
+            failure: Failure::classify(JobStage::Summarize, "API error (500)"),
+            completed: Some(JobStage::Transcribe),
+        };
+        db.update_episode_status(ep_id, &failed).unwrap();
+
+        let ep = db.get_episode(ep_id).unwrap();
+        assert_eq!(ep.status, failed);
+
+        // Leaving the failed state forgets the stage
+        db.update_episode_status(ep_id, &EpisodeStatus::Summarized)
+            .unwrap();
+        let ep = db.get_episode(ep_id).unwrap();
+        assert_eq!(ep.status, EpisodeStatus::Summarized);
+        let completed: Option<String> = db
+            .conn
+            .query_row(
+                "SELECT completed_stage FROM episodes WHERE id = ?1",
+                params![ep_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(completed.is_none());
+    }
+
+    // --- Jobs ---
+
+    #[test]
+    fn job_moves_through_stages() {
+        let db = test_db();
+        let ep = lease_episode(&db);
+        db.enqueue_job(
+            ep,
+            JobStage::Download,
+            JobStage::Summarize,
+            false,
+            Some("brief"),
+            None,
+        )
+        .unwrap();
+
+        let jobs = db.list_runnable_jobs(JobStage::Download).unwrap();
+        assert_eq!(jobs.len(), 1);
+        let job = &jobs[0];
+        assert_eq!(job.episode_id, ep);
+        assert_eq!(job.final_stage, JobStage::Summarize);
+        assert_eq!(job.style.as_deref(), Some("brief"));
+        assert!(
+            db.list_runnable_jobs(JobStage::Transcribe)
+                .unwrap()
+                .is_empty()
+        );
+
+        db.advance_job(job.id, JobStage::Transcribe).unwrap();
+        assert!(
+            db.list_runnable_jobs(JobStage::Download)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            db.list_runnable_jobs(JobStage::Transcribe).unwrap().len(),
+            1
+        );
+
+        db.complete_job(job.id).unwrap();
+        assert!(db.get_job(ep).unwrap().is_none());
+    }
+
+    #[test]
+    fn failed_job_is_parked_until_requeued() {
+        let db = test_db();
+        let ep = lease_episode(&db);
+        db.enqueue_job(
+            ep,
+            JobStage::Download,
+            JobStage::Summarize,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        let job = db.get_job(ep).unwrap().unwrap();
+        db.advance_job(job.id, JobStage::Summarize).unwrap();
+        db.fail_job(job.id, "auth: API error (401)", None).unwrap();
+
+        let parked = db.get_job(ep).unwrap().unwrap();
+        assert_eq!(parked.stage, JobStage::Summarize);
+        assert_eq!(parked.attempts, 1);
+        assert_eq!(parked.last_error.as_deref(), Some("auth: API error (401)"));
+        assert!(parked.next_run_at.is_none());
+        assert!(parked.last_attempt_at.is_some());
+        assert!(
+            db.list_runnable_jobs(JobStage::Summarize)
+                .unwrap()
+                .is_empty()
+        );
+
+        // A retry scheduled in the future isn't runnable yet
+        let later = Utc::now() + chrono::Duration::minutes(30);
+        db.fail_job(job.id, "quota: API error (429)", Some(later))
+            .unwrap();
+        let waiting = db.get_job(ep).unwrap().unwrap();
+        assert_eq!(waiting.attempts, 2);
+        assert_eq!(waiting.next_run_at.unwrap().timestamp(), later.timestamp());
+        assert!(
+            db.list_runnable_jobs(JobStage::Summarize)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Rescheduling keeps the attempt count
+        db.schedule_job(job.id, Utc::now()).unwrap();
+        let due = db.list_runnable_jobs(JobStage::Summarize).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].attempts, 2);
+
+        // Queuing again resets the job
+        db.enqueue_job(ep, JobStage::Download, JobStage::Download, true, None, None)
+            .unwrap();
+        let job = db.get_job(ep).unwrap().unwrap();
+        assert_eq!(job.stage, JobStage::Download);
+        assert_eq!(job.final_stage, JobStage::Download);
+        assert!(job.force_summary);
+        assert_eq!(job.attempts, 0);
+        assert!(job.last_error.is_none());
+    }
+
+    #[test]
+    fn jobs_run_by_priority_and_can_be_removed() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let other = db
+            .insert_podcast("https://ex.com/other", "Other", None, None)
+            .unwrap();
+        let mut eps = Vec::new();
+        for (podcast_id, guid) in [(p.id, "a"), (p.id, "b"), (other.id, "c")] {
+            let ep = db
+                .insert_episode(
+                    podcast_id,
+                    guid,
+                    guid,
+                    None,
+                    "https://ex.com/x.mp3",
+                    None,
+                    None,
+                )
+                .unwrap();
+            db.enqueue_job(
+                ep,
+                JobStage::Download,
+                JobStage::Summarize,
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+            eps.push(ep);
+        }
+        db.set_job_priority(eps[1], 5).unwrap();
+        // Re-queuing keeps the priority
+        db.enqueue_job(
+            eps[1],
+            JobStage::Download,
+            JobStage::Summarize,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let order: Vec<i64> = db
+            .list_runnable_jobs(JobStage::Download)
+            .unwrap()
+            .iter()
+            .map(|j| j.episode_id)
+            .collect();
+        assert_eq!(order, vec![eps[1], eps[0], eps[2]]);
+        assert_eq!(db.list_jobs().unwrap()[0].priority, 5);
+
+        assert!(db.delete_job(eps[0]).unwrap());
+        assert!(!db.delete_job(eps[0]).unwrap());
+        assert_eq!(db.clear_jobs(Some(other.id)).unwrap(), 1);
+        assert_eq!(db.list_jobs().unwrap().len(), 1);
+        assert_eq!(db.clear_jobs(None).unwrap(), 1);
+        assert!(db.list_jobs().unwrap().is_empty());
+    }
+
+    // --- Episode leases ---
+
+    fn lease_episode(db: &Database) -> i64 {
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        db.insert_episode(p.id, "g1", "Ep", None, "https://ex.com/1.mp3", None, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn claim_is_exclusive_until_released() {
+        let db = test_db();
+        let id = lease_episode(&db);
+
+        assert!(db.claim_episode(id, "a", 600).unwrap());
+        assert!(!db.claim_episode(id, "b", 600).unwrap());
+        assert_eq!(db.episode_lease_owner(id).unwrap().as_deref(), Some("a"));
+        // Renewing our own lease succeeds
+        assert!(db.claim_episode(id, "a", 600).unwrap());
+
+        // Only the owner can release
+        db.release_episode(id, "b").unwrap();
+        assert!(!db.claim_episode(id, "b", 600).unwrap());
+        db.release_episode(id, "a").unwrap();
+        assert!(db.episode_lease_owner(id).unwrap().is_none());
+        assert!(db.claim_episode(id, "b", 600).unwrap());
+    }
+
+    #[test]
+    fn stale_lease_can_be_taken_over() {
+        let db = test_db();
+        let id = lease_episode(&db);
+
+        // A crashed process's lease that has already expired
+        assert!(db.claim_episode(id, "a", -1).unwrap());
+        assert!(db.episode_lease_owner(id).unwrap().is_none());
+        assert!(db.claim_episode(id, "b", 600).unwrap());
+        assert_eq!(db.episode_lease_owner(id).unwrap().as_deref(), Some("b"));
+    }
+
+    // --- Episode counts ---
+
+    #[test]
+    fn episode_count() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        assert_eq!(db.episode_count(p.id).unwrap(), 0);
+
+        db.insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+        assert_eq!(db.episode_count(p.id).unwrap(), 2);
+    }
+
+    #[test]
+    fn episode_count_by_status() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep1 = db
+            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+
+        assert_eq!(db.episode_count_by_status(p.id, "new").unwrap(), 2);
+        db.update_episode_audio_path(ep1, "/tmp/a.mp3").unwrap();
+        assert_eq!(db.episode_count_by_status(p.id, "new").unwrap(), 1);
+        assert_eq!(db.episode_count_by_status(p.id, "downloaded").unwrap(), 1);
+    }
+
+    // --- Summaries ---
+
+    #[test]
+    fn insert_and_get_summary() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+            .unwrap();
+        let sum_id = db
+            .insert_summary(
+                ep_id,
+                "Great episode summary",
+                "gemini-2.0-flash",
+                Some(500),
+                Some(200),
+                Some("brief"),
+                Some("abc123"),
+                Some("zh-TW"),
+            )
+            .unwrap();
+        assert!(sum_id > 0);
+
+        let summary = db.get_summary_by_episode(ep_id).unwrap().unwrap();
+        assert_eq!(summary.content, "Great episode summary");
+        assert_eq!(summary.model, "gemini-2.0-flash");
+        assert_eq!(summary.prompt_tokens, Some(500));
+        assert_eq!(summary.output_tokens, Some(200));
+        assert_eq!(summary.style.as_deref(), Some("brief"));
+        assert_eq!(summary.prompt_hash.as_deref(), Some("abc123"));
+        assert_eq!(summary.language.as_deref(), Some("zh-TW"));
+        let by_lang = db
+            .get_latest_summary_by_language(ep_id, "zh-tw")
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_lang.id, sum_id);
+        assert_eq!(summary.version, 1);
+    }
+
+    #[test]
+    fn summaries_are_versioned() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+            .unwrap();
+        db.insert_summary(ep_id, "first", "gpt-4o", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary(ep_id, "second", "gemini", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary(ep_id, "third", "gpt-4o", None, None, None, None, None)
+            .unwrap();
+
+        let all = db.list_summaries_by_episode(ep_id).unwrap();
+        let versions: Vec<(i64, &str)> = all
+            .iter()
+            .map(|s| (s.version, s.content.as_str()))
+            .collect();
+        assert_eq!(versions, vec![(1, "first"), (2, "second"), (3, "third")]);
+
+        let latest = db.get_summary_by_episode(ep_id).unwrap().unwrap();
+        assert_eq!(latest.content, "third");
+        assert_eq!(latest.version, 3);
+
+        let v2 = db.get_summary_version(ep_id, 2).unwrap().unwrap();
+        assert_eq!(v2.content, "second");
+        assert!(db.get_summary_version(ep_id, 4).unwrap().is_none());
+
+        let by_model = db
+            .get_latest_summary_by_model(ep_id, "gemini")
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_model.version, 2);
+        assert!(
+            db.get_latest_summary_by_model(ep_id, "claude")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn summary_versions_are_per_episode() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep1 = db
+            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        let ep2 = db
+            .insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+        db.insert_summary(ep1, "a", "m", None, None, None, None, None)
             .unwrap();
-        db.insert_podcast("https://ex.com/c", "Charlie", None, None)
+        db.insert_summary(ep2, "b", "m", None, None, None, None, None)
             .unwrap();
 
-        let list = db.list_podcasts().unwrap();
-        let titles: Vec<&str> = list.iter().map(|p| p.title.as_str()).collect();
-        assert_eq!(titles, vec!["Alpha", "Bravo", "Charlie"]);
+        assert_eq!(db.get_summary_by_episode(ep2).unwrap().unwrap().version, 1);
     }
 
     #[test]
-    fn list_podcasts_empty() {
+    fn summary_sections_roundtrip() {
         let db = test_db();
-        let list = db.list_podcasts().unwrap();
-        assert!(list.is_empty());
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+            .unwrap();
+        let sum_id = db
+            .insert_summary(ep_id, "text", "m", None, None, None, None, None)
+            .unwrap();
+        assert!(db.get_summary_sections(sum_id).unwrap().is_none());
+
+        let sections = StructuredSummary {
+            topics: vec!["Postgres".to_string(), "Vacuum".to_string()],
+            summary: "Narrative".to_string(),
+            key_takeaways: vec!["a".to_string(), "b".to_string()],
+            notable_quotes: vec![Quote {
+                text: "q".to_string(),
+                speaker: None,
+                timestamp: Some("1:00".to_string()),
+            }],
+        };
+        db.insert_summary_sections(sum_id, &sections).unwrap();
+        assert_eq!(db.get_summary_sections(sum_id).unwrap(), Some(sections));
     }
 
     #[test]
-    fn delete_podcast() {
+    fn failed_transaction_leaves_no_summary() {
         let db = test_db();
-        let p = db
-            .insert_podcast("https://ex.com/feed", "ToDelete", None, None)
+        let p = insert_test_podcast(&db);
+        let ep_id = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
             .unwrap();
-        db.delete_podcast(p.id).unwrap();
-        assert!(db.get_podcast(p.id).is_err());
+        let result: Result<()> = db.transaction(|db| {
+            db.insert_summary(ep_id, "text", "m", None, None, None, None, None)?;
+            anyhow::bail!("sections failed")
+        });
+        assert!(result.is_err());
+        assert!(db.get_summary_by_episode(ep_id).unwrap().is_none());
+        assert_eq!(db.get_episode(ep_id).unwrap().status, EpisodeStatus::New);
     }
 
     #[test]
-    fn update_last_checked() {
+    fn episodes_by_topic_uses_latest_summary() {
         let db = test_db();
-        let p = db
-            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+        let p = insert_test_podcast(&db);
+        let ep1 = db
+            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
             .unwrap();
-        assert!(p.last_checked.is_none());
-        db.update_last_checked(p.id).unwrap();
-        let updated = db.get_podcast(p.id).unwrap();
-        assert!(updated.last_checked.is_some());
+        let ep2 = db
+            .insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+        let sections = |topics: &[&str]| StructuredSummary {
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+            summary: "s".to_string(),
+            ..Default::default()
+        };
+
+        let old = db
+            .insert_summary(ep1, "old", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary_sections(old, &sections(&["Kubernetes"]))
+            .unwrap();
+        let new = db
+            .insert_summary(ep1, "new", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary_sections(new, &sections(&["Postgres vacuum", "Rust"]))
+            .unwrap();
+        let s2 = db
+            .insert_summary(ep2, "s", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary_sections(s2, &sections(&["rust"]))
+            .unwrap();
+
+        let eps = db.list_episodes_by_topic("postgres").unwrap();
+        assert_eq!(eps.len(), 1);
+        assert_eq!(eps[0].id, ep1);
+        assert!(db.list_episodes_by_topic("kubernetes").unwrap().is_empty());
+        // Wildcards in the topic are matched literally
+        assert!(db.list_episodes_by_topic("%").unwrap().is_empty());
+        assert!(db.list_episodes_by_topic("r_st").unwrap().is_empty());
+
+        let topics = db.list_topics().unwrap();
+        assert_eq!(topics[0].1, 2);
+        assert!(topics[0].0.eq_ignore_ascii_case("rust"));
+        assert_eq!(topics.len(), 2);
     }
 
-    // --- Episode CRUD ---
+    #[test]
+    fn summaries_since_returns_latest_in_window() {
+        let db = test_db();
+        let p = insert_test_podcast(&db);
+        let ep1 = db
+            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        let ep2 = db
+            .insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+        let old = db
+            .insert_summary(ep1, "old", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary(ep1, "new", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary(ep2, "other", "m", None, None, None, None, None)
+            .unwrap();
+        db.conn
+            .execute(
+                "UPDATE summaries SET created_at = '2020-01-01 00:00:00' WHERE id = ?1",
+                params![old],
+            )
+            .unwrap();
 
-    fn insert_test_podcast(db: &Database) -> Podcast {
-        db.insert_podcast("https://ex.com/feed", "Test Pod", None, None)
-            .unwrap()
+        let since = Utc::now() - chrono::Duration::days(7);
+        let recent = db.list_summaries_since(since).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].content, "new");
+        assert_eq!(recent[0].version, 2);
+        assert_eq!(recent[1].content, "other");
+
+        let future = Utc::now() + chrono::Duration::days(1);
+        assert!(db.list_summaries_since(future).unwrap().is_empty());
     }
 
     #[test]
-    fn insert_and_get_episode() {
+    fn list_latest_summaries_per_podcast() {
+        let db = test_db();
+        let p1 = insert_test_podcast(&db);
+        let p2 = db
+            .insert_podcast("https://ex.com/other", "Other Pod", None, None)
+            .unwrap();
+        let a = db
+            .insert_episode(p1.id, "g1", "A", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        let b = db
+            .insert_episode(p2.id, "g2", "B", None, "https://ex.com/2.mp3", None, None)
+            .unwrap();
+        db.insert_summary(a, "a v1", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary(b, "b v1", "m", None, None, None, None, None)
+            .unwrap();
+        db.insert_summary(a, "a v2", "m", None, None, None, None, None)
+            .unwrap();
+
+        let all = db.list_latest_summaries(None, 10).unwrap();
+        let contents: Vec<_> = all.iter().map(|s| s.content.as_str()).collect();
+        assert_eq!(contents, vec!["a v2", "b v1"]);
+        assert_eq!(all[0].version, 2);
+
+        let one = db.list_latest_summaries(Some(p2.id), 10).unwrap();
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].content, "b v1");
+        assert_eq!(db.list_latest_summaries(None, 1).unwrap().len(), 1);
+    }
+
+    // --- Search ---
+
+    #[test]
+    fn search_finds_titles_summaries_and_transcripts() {
         let db = test_db();
         let p = insert_test_podcast(&db);
-        let ep_id = db
+        let ep1 = db
             .insert_episode(
                 p.id,
-                "guid-1",
-                "Episode 1",
+                "g1",
+                "Database night",
+                None,
+                "https://ex.com/1.mp3",
                 None,
-                "https://ex.com/ep1.mp3",
                 None,
-                Some(3600),
             )
             .unwrap();
-        assert!(ep_id > 0);
+        let ep2 = db
+            .insert_episode(
+                p.id,
+                "g2",
+                "Other",
+                Some("About Go"),
+                "https://ex.com/2.mp3",
+                None,
+                None,
+            )
+            .unwrap();
+        db.insert_summary(
+            ep1,
+            "They tune Postgres autovacuum.",
+            "m",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        db.index_transcript(ep2, "今天我們聊資料庫索引").unwrap();
 
-        let ep = db.get_episode(ep_id).unwrap();
-        assert_eq!(ep.title, "Episode 1");
-        assert_eq!(ep.guid, "guid-1");
-        assert_eq!(ep.duration_secs, Some(3600));
-        assert_eq!(ep.status, EpisodeStatus::New);
+        let hits = db
+            .search("postgres VACUUM", None, None, None, None, 10)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].episode_id, ep1);
+        assert!(hits[0].snippet.contains(search::HIGHLIGHT_START));
+
+        let hits = db.search("資料庫", None, None, None, None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].episode_id, ep2);
+
+        // Short terms use the LIKE fallback
+        let hits = db.search("go", None, None, None, None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].episode_id, ep2);
+
+        assert!(
+            db.search("kubernetes", None, None, None, None, 10)
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[test]
-    fn duplicate_guid_ignored() {
+    fn search_filters_by_podcast_and_date() {
         let db = test_db();
-        let p = insert_test_podcast(&db);
+        let p1 = insert_test_podcast(&db);
+        let p2 = db
+            .insert_podcast("https://ex.com/f2", "Other Pod", None, None)
+            .unwrap();
+        let jan = "2024-01-15T00:00:00Z".parse().ok();
+        let mar = "2024-03-15T00:00:00Z".parse().ok();
         db.insert_episode(
-            p.id,
-            "guid-1",
-            "First",
+            p1.id,
+            "g1",
+            "Rust in January",
             None,
             "https://ex.com/1.mp3",
-            None,
+            jan,
             None,
         )
         .unwrap();
-        // INSERT OR IGNORE - duplicate guid same podcast is ignored
         db.insert_episode(
-            p.id,
-            "guid-1",
-            "Duplicate",
+            p2.id,
+            "g2",
+            "Rust in March",
             None,
             "https://ex.com/2.mp3",
-            None,
+            mar,
             None,
         )
         .unwrap();
-        let episodes = db.list_episodes(p.id).unwrap();
-        assert_eq!(episodes.len(), 1);
-        assert_eq!(episodes[0].title, "First");
-    }
 
-    #[test]
-    fn list_episodes_for_podcast() {
-        let db = test_db();
-        let p = insert_test_podcast(&db);
-        db.insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
-            .unwrap();
-        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+        assert_eq!(
+            db.search("rust", None, None, None, None, 10).unwrap().len(),
+            2
+        );
+        let hits = db
+            .search("rust", Some(p2.id), None, None, None, 10)
             .unwrap();
-
-        let episodes = db.list_episodes(p.id).unwrap();
-        assert_eq!(episodes.len(), 2);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].podcast_title, "Other Pod");
+
+        let feb = "2024-02-01T00:00:00Z".parse().ok();
+        let hits = db.search("rust", None, feb, None, None, 10).unwrap();
+        assert_eq!(hits[0].episode_title, "Rust in March");
+        let hits = db.search("rust", None, None, feb, None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].episode_title, "Rust in January");
     }
 
     #[test]
-    fn list_episodes_by_status() {
+    fn search_index_follows_deletes_and_rebuild() {
         let db = test_db();
         let p = insert_test_podcast(&db);
-        let ep1 = db
-            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+        let ep = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/1.mp3", None, None)
             .unwrap();
-        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+        db.index_transcript(ep, "a long talk about compilers")
             .unwrap();
-        db.update_episode_audio_path(ep1, "/tmp/audio.mp3").unwrap();
+        assert_eq!(
+            db.search("compilers", None, None, None, None, 10)
+                .unwrap()
+                .len(),
+            1
+        );
 
-        let downloaded = db.list_episodes_by_status("downloaded").unwrap();
-        assert_eq!(downloaded.len(), 1);
-        assert_eq!(downloaded[0].title, "Ep 1");
+        db.clear_episode_transcript(ep).unwrap();
+        assert!(
+            db.search("compilers", None, None, None, None, 10)
+                .unwrap()
+                .is_empty()
+        );
 
-        let new_eps = db.list_episodes_by_status("new").unwrap();
-        assert_eq!(new_eps.len(), 1);
-    }
+        db.insert_summary(ep, "linkers", "m", None, None, None, None, None)
+            .unwrap();
+        assert_eq!(db.rebuild_search_index().unwrap(), 1);
+        assert_eq!(
+            db.search("linkers", None, None, None, None, 10)
+                .unwrap()
+                .len(),
+            1
+        );
 
-    // --- Status lifecycle ---
+        db.delete_podcast(p.id).unwrap();
+        assert!(
+            db.search("linkers", None, None, None, None, 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
 
     #[test]
-    fn episode_status_lifecycle() {
+    fn query_episodes_filters_sorts_and_pages() {
         let db = test_db();
-        let p = insert_test_podcast(&db);
-        let ep_id = db
-            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+        let p1 = insert_test_podcast(&db);
+        let p2 = db
+            .insert_podcast("https://ex.com/other", "Other Pod", None, None)
             .unwrap();
-
-        // new -> downloaded
-        db.update_episode_audio_path(ep_id, "/tmp/audio.mp3")
+        let mut ids = Vec::new();
+        for (i, (podcast, secs)) in [(p1.id, 600), (p1.id, 3600), (p2.id, 1800), (p2.id, 60)]
+            .into_iter()
+            .enumerate()
+        {
+            let published = format!("2024-0{}-01T00:00:00Z", i + 1).parse().ok();
+            ids.push(
+                db.insert_episode(
+                    podcast,
+                    &format!("g{i}"),
+                    &format!("Ep {i}"),
+                    None,
+                    "https://ex.com/e.mp3",
+                    published,
+                    Some(secs),
+                )
+                .unwrap(),
+            );
+        }
+        db.update_episode_status(ids[0], &EpisodeStatus::Summarized)
             .unwrap();
-        let ep = db.get_episode(ep_id).unwrap();
-        assert_eq!(ep.status, EpisodeStatus::Downloaded);
-
-        // downloaded -> transcribed
-        db.update_episode_transcript_path(ep_id, "/tmp/transcript.txt")
+        db.update_episode_status(ids[2], &EpisodeStatus::Summarized)
             .unwrap();
-        let ep = db.get_episode(ep_id).unwrap();
-        assert_eq!(ep.status, EpisodeStatus::Transcribed);
+        let ids_of = |q: &EpisodeQuery| -> Vec<i64> {
+            db.query_episodes(q).unwrap().iter().map(|e| e.id).collect()
+        };
+
+        // Newest first across podcasts
+        let all = EpisodeQuery::default();
+        assert_eq!(ids_of(&all), vec![ids[3], ids[2], ids[1], ids[0]]);
+        assert_eq!(db.count_episodes(&all).unwrap(), 4);
+
+        let done = EpisodeQuery {
+            statuses: vec!["summarized".into()],
+            ..Default::default()
+        };
+        assert_eq!(ids_of(&done), vec![ids[2], ids[0]]);
+
+        let by_duration = EpisodeQuery {
+            sort: EpisodeSort::Duration,
+            ..Default::default()
+        };
+        assert_eq!(ids_of(&by_duration), vec![ids[1], ids[2], ids[0], ids[3]]);
+
+        let by_status = EpisodeQuery {
+            sort: EpisodeSort::Status,
+            ..Default::default()
+        };
+        assert_eq!(ids_of(&by_status), vec![ids[3], ids[1], ids[2], ids[0]]);
+
+        let window = EpisodeQuery {
+            podcast_id: Some(p1.id),
+            since: "2024-02-01T00:00:00Z".parse().ok(),
+            until: "2024-04-01T00:00:00Z".parse().ok(),
+            ..Default::default()
+        };
+        assert_eq!(ids_of(&window), vec![ids[1]]);
+
+        let page = EpisodeQuery {
+            limit: Some(2),
+            offset: 1,
+            ..Default::default()
+        };
+        assert_eq!(ids_of(&page), vec![ids[2], ids[1]]);
+        assert_eq!(db.count_episodes(&page).unwrap(), 4);
     }
 
+    // --- Tags ---
+
     #[test]
-    fn episode_status_to_summarized() {
+    fn podcast_tags_apply_to_all_its_episodes() {
         let db = test_db();
-        let p = insert_test_podcast(&db);
-        let ep_id = db
-            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+        let p1 = insert_test_podcast(&db);
+        let p2 = db
+            .insert_podcast("https://ex.com/other", "Other Pod", None, None)
             .unwrap();
-        db.update_episode_audio_path(ep_id, "/tmp/a.mp3").unwrap();
-        db.update_episode_transcript_path(ep_id, "/tmp/t.txt")
+        let a = db
+            .insert_episode(p1.id, "g1", "A", None, "https://ex.com/1.mp3", None, None)
             .unwrap();
-        db.insert_summary(ep_id, "Summary text", "gpt-4", Some(100), Some(50))
+        let b = db
+            .insert_episode(p2.id, "g2", "B", None, "https://ex.com/2.mp3", None, None)
             .unwrap();
-
-        let ep = db.get_episode(ep_id).unwrap();
-        assert_eq!(ep.status, EpisodeStatus::Summarized);
-    }
-
-    #[test]
-    fn episode_status_failed() {
-        let db = test_db();
-        let p = insert_test_podcast(&db);
-        let ep_id = db
-            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+        let c = db
+            .insert_episode(p2.id, "g3", "C", None, "https://ex.com/3.mp3", None, None)
             .unwrap();
-        db.update_episode_status(ep_id, &EpisodeStatus::Failed("download error".to_string()))
+
+        let rust = db.ensure_tag("Rust").unwrap();
+        assert_eq!(db.ensure_tag("rust").unwrap(), rust);
+        assert!(db.tag_podcast(p1.id, rust).unwrap());
+        assert!(db.tag_episode(b, rust, "manual").unwrap());
+        assert!(!db.tag_episode(b, rust, "manual").unwrap());
+
+        let ids = db.tagged_episode_ids(rust).unwrap();
+        assert!(ids.contains(&a) && ids.contains(&b) && !ids.contains(&c));
+        // Added later, still covered by the podcast's tag
+        let d = db
+            .insert_episode(p1.id, "g4", "D", None, "https://ex.com/4.mp3", None, None)
             .unwrap();
+        assert!(db.tagged_episode_ids(rust).unwrap().contains(&d));
+        assert_eq!(db.episode_tag_names(d).unwrap(), vec!["Rust"]);
 
-        let ep = db.get_episode(ep_id).unwrap();
-        assert_eq!(
-            ep.status,
-            EpisodeStatus::Failed("download error".to_string())
-        );
+        let tags = db.list_tags().unwrap();
+        assert_eq!((tags[0].podcasts, tags[0].episodes), (1, 1));
     }
 
-    // --- Episode counts ---
-
     #[test]
-    fn episode_count() {
+    fn unused_tags_are_deleted() {
         let db = test_db();
         let p = insert_test_podcast(&db);
-        assert_eq!(db.episode_count(p.id).unwrap(), 0);
-
-        db.insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
+        let ep = db
+            .insert_episode(p.id, "g1", "A", None, "https://ex.com/1.mp3", None, None)
             .unwrap();
-        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
-            .unwrap();
-        assert_eq!(db.episode_count(p.id).unwrap(), 2);
+        let tag = db.ensure_tag("later").unwrap();
+        db.tag_episode(ep, tag, "manual").unwrap();
+
+        assert_eq!(db.delete_unused_tags().unwrap(), 0);
+        assert!(db.untag_episode(ep, tag).unwrap());
+        assert!(!db.untag_episode(ep, tag).unwrap());
+        assert_eq!(db.delete_unused_tags().unwrap(), 1);
+        assert!(db.find_tag("later").unwrap().is_none());
     }
 
     #[test]
-    fn episode_count_by_status() {
+    fn search_filters_by_tag() {
         let db = test_db();
         let p = insert_test_podcast(&db);
-        let ep1 = db
-            .insert_episode(p.id, "g1", "Ep 1", None, "https://ex.com/1.mp3", None, None)
-            .unwrap();
-        db.insert_episode(p.id, "g2", "Ep 2", None, "https://ex.com/2.mp3", None, None)
+        let a = db
+            .insert_episode(
+                p.id,
+                "g1",
+                "Rust one",
+                None,
+                "https://ex.com/1.mp3",
+                None,
+                None,
+            )
             .unwrap();
+        db.insert_episode(
+            p.id,
+            "g2",
+            "Rust two",
+            None,
+            "https://ex.com/2.mp3",
+            None,
+            None,
+        )
+        .unwrap();
+        let tag = db.ensure_tag("keep").unwrap();
+        db.tag_episode(a, tag, "manual").unwrap();
 
-        assert_eq!(db.episode_count_by_status(p.id, "new").unwrap(), 2);
-        db.update_episode_audio_path(ep1, "/tmp/a.mp3").unwrap();
-        assert_eq!(db.episode_count_by_status(p.id, "new").unwrap(), 1);
-        assert_eq!(db.episode_count_by_status(p.id, "downloaded").unwrap(), 1);
+        assert_eq!(
+            db.search("rust", None, None, None, None, 10).unwrap().len(),
+            2
+        );
+        let hits = db.search("rust", None, None, None, Some(tag), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].episode_id, a);
+        // Short terms take the LIKE path
+        let hits = db.search("on", None, None, None, Some(tag), 10).unwrap();
+        assert_eq!(hits.len(), 1);
     }
 
-    // --- Summaries ---
+    // --- Embeddings ---
 
     #[test]
-    fn insert_and_get_summary() {
+    fn transcript_chunks_replace_and_list() {
         let db = test_db();
         let p = insert_test_podcast(&db);
-        let ep_id = db
-            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
+        let ep = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/1.mp3", None, None)
             .unwrap();
-        let sum_id = db
-            .insert_summary(
-                ep_id,
-                "Great episode summary",
-                "gemini-2.0-flash",
-                Some(500),
-                Some(200),
-            )
+        db.update_episode_transcript_path(ep, "/tmp/t.txt").unwrap();
+        assert_eq!(db.list_episodes_missing_embeddings("m").unwrap().len(), 1);
+
+        let chunk = |text: &str, start: Option<f64>| embed::Chunk {
+            text: text.to_string(),
+            start_secs: start,
+            end_secs: start.map(|s| s + 30.0),
+        };
+        db.replace_transcript_chunks(
+            ep,
+            "m",
+            &[
+                (chunk("a", Some(0.0)), vec![1.0, 0.0]),
+                (chunk("b", None), vec![0.0, 1.0]),
+            ],
+        )
+        .unwrap();
+        db.replace_transcript_chunks(ep, "m", &[(chunk("c", Some(60.0)), vec![0.5, 0.5])])
             .unwrap();
-        assert!(sum_id > 0);
 
-        let summary = db.get_summary_by_episode(ep_id).unwrap().unwrap();
-        assert_eq!(summary.content, "Great episode summary");
-        assert_eq!(summary.model, "gemini-2.0-flash");
-        assert_eq!(summary.prompt_tokens, Some(500));
-        assert_eq!(summary.output_tokens, Some(200));
+        let chunks = db.list_transcript_chunks("m", None).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text, "c");
+        assert_eq!(chunks[0].start_secs, Some(60.0));
+        assert_eq!(chunks[0].embedding, vec![0.5, 0.5]);
+        assert!(db.list_transcript_chunks("other", None).unwrap().is_empty());
+        assert!(
+            db.list_transcript_chunks("m", Some(p.id + 1))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(db.list_episodes_missing_embeddings("m").unwrap().is_empty());
+        assert_eq!(
+            db.list_episodes_missing_embeddings("other").unwrap().len(),
+            1
+        );
+
+        db.clear_episode_transcript(ep).unwrap();
+        assert!(db.list_transcript_chunks("m", None).unwrap().is_empty());
     }
 
     #[test]
@@ -740,21 +2663,6 @@ mod tests {
         assert!(summary.is_none());
     }
 
-    #[test]
-    fn delete_summary_by_episode() {
-        let db = test_db();
-        let p = insert_test_podcast(&db);
-        let ep_id = db
-            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
-            .unwrap();
-        db.insert_summary(ep_id, "summary", "model", None, None)
-            .unwrap();
-        assert!(db.get_summary_by_episode(ep_id).unwrap().is_some());
-
-        db.delete_summary_by_episode(ep_id).unwrap();
-        assert!(db.get_summary_by_episode(ep_id).unwrap().is_none());
-    }
-
     #[test]
     fn clear_episode_transcript() {
         let db = test_db();
@@ -784,7 +2692,7 @@ mod tests {
         let ep_id = db
             .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/e.mp3", None, None)
             .unwrap();
-        db.insert_summary(ep_id, "summary", "model", None, None)
+        db.insert_summary(ep_id, "summary", "model", None, None, None, None, None)
             .unwrap();
 
         db.delete_podcast(p.id).unwrap();
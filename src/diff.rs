@@ -0,0 +1,90 @@
+/// One line of a line-based diff.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Line diff of `old` against `new` using a longest-common-subsequence table.
+/// Quadratic in the number of lines, which is fine for summaries.
+pub fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| DiffLine::Removed(l)));
+    out.extend(b[j..].iter().map(|l| DiffLine::Added(l)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_texts() {
+        let d = line_diff("a\nb", "a\nb");
+        assert_eq!(d, vec![DiffLine::Same("a"), DiffLine::Same("b")]);
+    }
+
+    #[test]
+    fn changed_line() {
+        let d = line_diff("a\nb\nc", "a\nx\nc");
+        assert_eq!(
+            d,
+            vec![
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("x"),
+                DiffLine::Same("c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn added_and_removed_at_ends() {
+        let d = line_diff("a\nb", "b\nc");
+        assert_eq!(
+            d,
+            vec![
+                DiffLine::Removed("a"),
+                DiffLine::Same("b"),
+                DiffLine::Added("c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert!(line_diff("", "").is_empty());
+        assert_eq!(line_diff("", "a"), vec![DiffLine::Added("a")]);
+        assert_eq!(line_diff("a", ""), vec![DiffLine::Removed("a")]);
+    }
+}
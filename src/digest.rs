@@ -0,0 +1,218 @@
+//! Cross-podcast digests: recent summaries grouped by podcast and condensed by one
+//! "meta-summary" chat completion.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::language::SummaryLanguage;
+use crate::text::escape_html;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum DigestFormat {
+    #[value(alias = "md")]
+    Markdown,
+    Html,
+    #[value(alias = "txt")]
+    Text,
+}
+
+/// One summarized episode in the digest window.
+#[derive(Debug, Clone)]
+pub struct DigestEntry {
+    pub episode_id: i64,
+    pub episode_title: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub summary: String,
+}
+
+/// Entries grouped by podcast title.
+pub type DigestGroups = BTreeMap<String, Vec<DigestEntry>>;
+
+/// System prompt for the meta-summary in the requested format.
+pub fn system_prompt(format: DigestFormat, language: Option<&SummaryLanguage>) -> String {
+    let output = match format {
+        DigestFormat::Markdown => "Format the digest as Markdown, with a `##` heading per podcast.",
+        DigestFormat::Html => {
+            "Format the digest as an HTML fragment (no <html>, <head> or <body>), with an <h2> per \
+             podcast and <ul>/<p> for content."
+        }
+        DigestFormat::Text => {
+            "Format the digest as plain text without Markdown, with the podcast name on its own \
+             line before its section."
+        }
+    };
+    let language = language
+        .map(|l| format!(" Write the digest in {}.", l.name))
+        .unwrap_or_default();
+    format!(
+        "You write a digest of podcast episodes for a busy team. You are given the summaries of \
+         every episode summarized in the period, grouped by podcast. Start with a short overview of \
+         the main themes across all podcasts, then cover each podcast: what its episodes were about \
+         and the most useful takeaways. Refer to episodes by title and id, like \"Title (#42)\". Be \
+         concise and do not invent anything that is not in the summaries. {output}{language}"
+    )
+}
+
+/// The summaries to condense, grouped by podcast.
+pub fn user_message(groups: &DigestGroups, since: DateTime<Utc>, until: DateTime<Utc>) -> String {
+    let mut out = format!(
+        "Episodes summarized from {} to {}:\n",
+        since.format("%Y-%m-%d"),
+        until.format("%Y-%m-%d")
+    );
+    for (podcast, entries) in groups {
+        out.push_str(&format!("\n=== Podcast: {podcast} ===\n"));
+        for entry in entries {
+            out.push_str(&format!(
+                "\n--- #{} {}",
+                entry.episode_id, entry.episode_title
+            ));
+            if let Some(date) = entry.published_at {
+                out.push_str(&format!(" ({})", date.format("%Y-%m-%d")));
+            }
+            out.push_str(" ---\n");
+            out.push_str(entry.summary.trim());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The complete digest document: a title, the model's digest and a list of the
+/// episodes it covers.
+pub fn render(format: DigestFormat, title: &str, body: &str, groups: &DigestGroups) -> String {
+    let body = strip_code_fence(body);
+    match format {
+        DigestFormat::Markdown => {
+            let mut out = format!("# {title}\n\n{body}\n\n## Episodes\n");
+            for (podcast, entries) in groups {
+                out.push_str(&format!("\n**{podcast}**\n\n"));
+                for e in entries {
+                    out.push_str(&format!("- #{} {}\n", e.episode_id, e.episode_title));
+                }
+            }
+            out
+        }
+        DigestFormat::Html => {
+            let mut index = String::new();
+            for (podcast, entries) in groups {
+                index.push_str(&format!("<h3>{}</h3>\n<ul>\n", escape_html(podcast)));
+                for e in entries {
+                    index.push_str(&format!(
+                        "<li>#{} {}</li>\n",
+                        e.episode_id,
+                        escape_html(&e.episode_title)
+                    ));
+                }
+                index.push_str("</ul>\n");
+            }
+            let title = escape_html(title);
+            format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}\n<h2>Episodes</h2>\n{index}</body>\n</html>\n"
+            )
+        }
+        DigestFormat::Text => {
+            let mut out = format!(
+                "{title}\n{}\n\n{body}\n\nEpisodes\n",
+                "=".repeat(title.chars().count())
+            );
+            for (podcast, entries) in groups {
+                out.push_str(&format!("\n{podcast}\n"));
+                for e in entries {
+                    out.push_str(&format!("  #{} {}\n", e.episode_id, e.episode_title));
+                }
+            }
+            out
+        }
+    }
+}
+
+/// Models sometimes wrap the whole answer in a code fence; drop it.
+fn strip_code_fence(s: &str) -> &str {
+    let trimmed = s.trim();
+    if let Some(rest) = trimmed.strip_prefix("```")
+        && let Some(inner) = rest.strip_suffix("```")
+    {
+        // Skip the language tag on the opening line
+        return inner
+            .split_once('\n')
+            .map_or(inner, |(_, body)| body)
+            .trim();
+    }
+    trimmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups() -> DigestGroups {
+        let mut groups = DigestGroups::new();
+        groups.insert(
+            "Rust <Weekly>".to_string(),
+            vec![DigestEntry {
+                episode_id: 42,
+                episode_title: "Async & you".to_string(),
+                published_at: "2024-03-04T00:00:00Z".parse().ok(),
+                summary: " Covers async Rust. ".to_string(),
+            }],
+        );
+        groups
+    }
+
+    #[test]
+    fn parse_formats() {
+        use clap::ValueEnum;
+        let parse = |s| DigestFormat::from_str(s, true);
+        assert_eq!(parse("md").unwrap(), DigestFormat::Markdown);
+        assert_eq!(parse("HTML").unwrap(), DigestFormat::Html);
+        assert_eq!(parse("txt").unwrap(), DigestFormat::Text);
+        assert!(parse("pdf").is_err());
+    }
+
+    #[test]
+    fn user_message_groups_by_podcast() {
+        let since = "2024-03-01T00:00:00Z".parse().unwrap();
+        let until = "2024-03-08T00:00:00Z".parse().unwrap();
+        let msg = user_message(&groups(), since, until);
+        assert!(msg.starts_with("Episodes summarized from 2024-03-01 to 2024-03-08:"));
+        assert!(msg.contains("=== Podcast: Rust <Weekly> ==="));
+        assert!(msg.contains("--- #42 Async & you (2024-03-04) ---\nCovers async Rust.\n"));
+    }
+
+    #[test]
+    fn system_prompt_mentions_format_and_language() {
+        let lang = SummaryLanguage::parse("zh-TW");
+        let prompt = system_prompt(DigestFormat::Html, Some(&lang));
+        assert!(prompt.contains("HTML fragment"));
+        assert!(prompt.contains("Traditional Chinese (Taiwan)"));
+    }
+
+    #[test]
+    fn render_markdown_lists_episodes() {
+        let out = render(
+            DigestFormat::Markdown,
+            "Digest",
+            "```markdown\n## Rust\nStuff\n```",
+            &groups(),
+        );
+        assert!(out.starts_with("# Digest\n\n## Rust\nStuff\n\n## Episodes\n"));
+        assert!(out.contains("- #42 Async & you"));
+    }
+
+    #[test]
+    fn render_html_escapes_titles() {
+        let out = render(DigestFormat::Html, "Digest", "<p>Body</p>", &groups());
+        assert!(out.contains("<h3>Rust &lt;Weekly&gt;</h3>"));
+        assert!(out.contains("<li>#42 Async &amp; you</li>"));
+        assert!(out.contains("<p>Body</p>"));
+    }
+
+    #[test]
+    fn render_text_underlines_title() {
+        let out = render(DigestFormat::Text, "Digest", "Body", &groups());
+        assert!(out.starts_with("Digest\n======\n\nBody\n"));
+        assert!(out.contains("  #42 Async & you"));
+    }
+}
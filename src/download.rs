@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::ProgressStyle;
 
 pub async fn download_episode(
     client: &reqwest::Client,
@@ -36,7 +36,7 @@ pub async fn download_episode(
 
     let total_size = response.content_length().unwrap_or(0);
 
-    let pb = ProgressBar::new(total_size);
+    let pb = crate::progress::bar(total_size);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("    {msg} [{bar:30.cyan/dim}] {bytes}/{total_bytes} {bytes_per_sec}")
@@ -0,0 +1,298 @@
+//! Transcript chunking and embeddings for semantic search.
+//!
+//! Vectors come from an OpenAI-compatible `/embeddings` endpoint. Pointing it at a
+//! local server (Ollama, llama.cpp's `llama-server --embeddings`, LM Studio) runs a
+//! gguf embedding model on the CPU without sending transcripts anywhere.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::models::TranscriptSegment;
+use crate::text::is_cjk;
+
+/// Inputs sent per `/embeddings` request.
+const BATCH_SIZE: usize = 32;
+
+/// A passage of a transcript to embed, with its position in the audio when known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub text: String,
+    pub start_secs: Option<f64>,
+    pub end_secs: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: Option<usize>,
+}
+
+/// Embed `inputs`, returning one vector per input in the same order.
+/// No `Authorization` header is sent without a key, for local servers.
+pub async fn embed(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let url = format!("{}/embeddings", api_base_url.trim_end_matches('/'));
+    let mut vectors = Vec::with_capacity(inputs.len());
+
+    for batch in inputs.chunks(BATCH_SIZE) {
+        let mut request = client.post(&url).json(&EmbeddingRequest {
+            model,
+            input: batch,
+        });
+        if let Some(key) = api_key {
+            request = request.header("Authorization", format!("Bearer {key}"));
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to call embeddings API at {url}"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::error::AppError::ClaudeApi {
+                status: status.as_u16(),
+                body,
+            }
+            .into());
+        }
+
+        let mut resp: EmbeddingResponse = response
+            .json()
+            .await
+            .context("Failed to parse embeddings API response")?;
+        if resp.data.len() != batch.len() {
+            anyhow::bail!(
+                "Embeddings API returned {} vectors for {} inputs",
+                resp.data.len(),
+                batch.len()
+            );
+        }
+        resp.data.sort_by_key(|d| d.index.unwrap_or(0));
+        vectors.extend(resp.data.into_iter().map(|d| d.embedding));
+    }
+    Ok(vectors)
+}
+
+/// Group consecutive segments into chunks of up to `max_chars` characters, so
+/// chunks never split a segment and keep the segments' timestamps.
+pub fn chunk_segments(segments: &[TranscriptSegment], max_chars: usize) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut current: Option<Chunk> = None;
+
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if let Some(ref chunk) = current
+            && chunk.text.chars().count() + text.chars().count() > max_chars
+        {
+            chunks.extend(current.take());
+        }
+        match current {
+            Some(ref mut chunk) => {
+                join_text(&mut chunk.text, text);
+                chunk.end_secs = Some(segment.end_secs);
+            }
+            None => {
+                current = Some(Chunk {
+                    text: text.to_string(),
+                    start_secs: Some(segment.start_secs),
+                    end_secs: Some(segment.end_secs),
+                });
+            }
+        }
+    }
+    chunks.extend(current);
+    chunks
+}
+
+/// Split untimed text into chunks of up to `max_chars` characters at sentence ends,
+/// for transcripts saved without segments.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<Chunk> {
+    let segments: Vec<TranscriptSegment> = sentences(text)
+        .into_iter()
+        .flat_map(|s| split_long(s, max_chars))
+        .map(|text| TranscriptSegment {
+            start_secs: 0.0,
+            end_secs: 0.0,
+            text,
+        })
+        .collect();
+    chunk_segments(&segments, max_chars)
+        .into_iter()
+        .map(|c| Chunk {
+            start_secs: None,
+            end_secs: None,
+            ..c
+        })
+        .collect()
+}
+
+/// Cosine similarity of two vectors; 0 for mismatched or zero vectors.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Serialize a vector as little-endian `f32`s for a BLOB column.
+pub fn encode_vector(v: &[f32]) -> Vec<u8> {
+    v.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Format seconds as `h:mm:ss` or `m:ss`.
+pub fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+/// Join two pieces of transcript, adding a space only between non-CJK text.
+fn join_text(acc: &mut String, next: &str) {
+    let needs_space = acc
+        .chars()
+        .last()
+        .zip(next.chars().next())
+        .is_some_and(|(a, b)| !is_cjk(a) && !is_cjk(b));
+    if needs_space {
+        acc.push(' ');
+    }
+    acc.push_str(next);
+}
+
+fn sentences(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n') {
+            let end = i + c.len_utf8();
+            let s = text[start..end].trim();
+            if !s.is_empty() {
+                out.push(s);
+            }
+            start = end;
+        }
+    }
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        out.push(rest);
+    }
+    out
+}
+
+/// Hard-split a sentence longer than `max_chars` (e.g. unpunctuated speech).
+fn split_long(s: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    chars
+        .chunks(max_chars.max(1))
+        .map(|c| c.iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f64, end: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_secs: start,
+            end_secs: end,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn chunk_segments_groups_and_keeps_times() {
+        let segments = vec![
+            seg(0.0, 2.0, " Hello there."),
+            seg(2.0, 4.0, " How are you?"),
+            seg(4.0, 6.0, " Fine."),
+        ];
+        let chunks = chunk_segments(&segments, 26);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text, "Hello there. How are you?");
+        assert_eq!(chunks[0].start_secs, Some(0.0));
+        assert_eq!(chunks[0].end_secs, Some(4.0));
+        assert_eq!(chunks[1].text, "Fine.");
+        assert_eq!(chunks[1].start_secs, Some(4.0));
+    }
+
+    #[test]
+    fn chunk_segments_joins_cjk_without_spaces() {
+        let segments = vec![seg(0.0, 1.0, "今天天氣"), seg(1.0, 2.0, "很好")];
+        let chunks = chunk_segments(&segments, 100);
+        assert_eq!(chunks[0].text, "今天天氣很好");
+    }
+
+    #[test]
+    fn chunk_text_splits_at_sentences() {
+        let chunks = chunk_text("One two. Three four. Five six.", 20);
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["One two. Three four.", "Five six."]);
+        assert!(chunks[0].start_secs.is_none());
+    }
+
+    #[test]
+    fn chunk_text_splits_long_sentences() {
+        let chunks = chunk_text(&"a".repeat(25), 10);
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn cosine_similarity_basics() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 2.0]), 0.0);
+    }
+
+    #[test]
+    fn vector_roundtrip() {
+        let v = vec![0.5, -1.25, 3.0];
+        assert_eq!(decode_vector(&encode_vector(&v)), v);
+    }
+
+    #[test]
+    fn format_timestamp_values() {
+        assert_eq!(format_timestamp(65.4), "1:05");
+        assert_eq!(format_timestamp(3725.0), "1:02:05");
+    }
+}
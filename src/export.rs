@@ -0,0 +1,537 @@
+//! Export summaries as files: Markdown with YAML front-matter, a static HTML
+//! site, or an Obsidian vault with links between podcast and episode notes.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::dates::format_duration;
+use crate::models::{Episode, Podcast, Summary};
+use crate::text::escape_html;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    #[value(alias = "md")]
+    Markdown,
+    Html,
+    Obsidian,
+}
+
+/// One summarized episode to export.
+#[derive(Debug, Clone)]
+pub struct ExportEntry {
+    pub podcast: Podcast,
+    pub episode: Episode,
+    pub summary: Summary,
+    pub topics: Vec<String>,
+    pub tags: Vec<String>,
+    pub transcript: Option<String>,
+}
+
+/// A file to write, relative to the export directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Every file of an export: a page for each of `entries`, and index files
+/// listing everything in `indexed`. Passing every summarized episode as
+/// `indexed` keeps the indexes whole when only some pages are exported.
+/// Output depends only on the arguments, so unchanged episodes render
+/// byte-for-byte the same as last time.
+pub fn render(
+    format: ExportFormat,
+    entries: &[ExportEntry],
+    indexed: &[ExportEntry],
+) -> Vec<ExportFile> {
+    let mut files = Vec::new();
+    for entry in entries {
+        files.push(match format {
+            ExportFormat::Markdown => ExportFile {
+                path: PathBuf::from(slug(&entry.podcast.title))
+                    .join(format!("{}.md", episode_slug(entry))),
+                content: markdown_note(entry, None),
+            },
+            ExportFormat::Obsidian => {
+                let index = note_name(&entry.podcast.title);
+                ExportFile {
+                    path: PathBuf::from(&index).join(format!("{}.md", episode_note_name(entry))),
+                    content: markdown_note(entry, Some(&index)),
+                }
+            }
+            ExportFormat::Html => ExportFile {
+                path: PathBuf::from(slug(&entry.podcast.title))
+                    .join(format!("{}.html", episode_slug(entry))),
+                content: html_episode(entry),
+            },
+        });
+    }
+
+    let mut podcasts: BTreeMap<(String, i64), Vec<&ExportEntry>> = BTreeMap::new();
+    for entry in indexed {
+        podcasts
+            .entry((entry.podcast.title.clone(), entry.podcast.id))
+            .or_default()
+            .push(entry);
+    }
+    for episodes in podcasts.values_mut() {
+        episodes.sort_by(|a, b| {
+            b.episode
+                .published_at
+                .cmp(&a.episode.published_at)
+                .then(b.episode.id.cmp(&a.episode.id))
+        });
+    }
+
+    match format {
+        ExportFormat::Markdown => {}
+        ExportFormat::Obsidian => {
+            for episodes in podcasts.values() {
+                let podcast = &episodes[0].podcast;
+                let index = note_name(&podcast.title);
+                let mut links = String::new();
+                for entry in episodes {
+                    let name = episode_note_name(entry);
+                    links.push_str(&format!("- {}[[{name}]]\n", date_prefix(entry)));
+                }
+                let mut content = String::from("---\ntype: podcast\n");
+                if let Some(ref url) = podcast.website_url {
+                    content.push_str(&format!("website: {}\n", yaml_str(url)));
+                }
+                content.push_str(&format!("feed: {}\n---\n\n", yaml_str(&podcast.feed_url)));
+                content.push_str(&format!("# {}\n\n", podcast.title));
+                if let Some(ref description) = podcast.description {
+                    content.push_str(&format!("{}\n\n", description.trim()));
+                }
+                content.push_str(&format!("## Episodes\n\n{links}"));
+                files.push(ExportFile {
+                    path: PathBuf::from(format!("{index}.md")),
+                    content,
+                });
+            }
+        }
+        ExportFormat::Html => {
+            let mut index = String::from("<ul>\n");
+            for episodes in podcasts.values() {
+                let podcast = &episodes[0].podcast;
+                let dir = slug(&podcast.title);
+                let mut list = String::from("<ul>\n");
+                for entry in episodes {
+                    let file = format!("{}.html", episode_slug(entry));
+                    list.push_str(&format!(
+                        "<li>{}<a href=\"{file}\">{}</a></li>\n",
+                        date_prefix(entry),
+                        escape_html(&entry.episode.title)
+                    ));
+                }
+                list.push_str("</ul>\n");
+                files.push(ExportFile {
+                    path: PathBuf::from(&dir).join("index.html"),
+                    content: html_page(
+                        &podcast.title,
+                        &format!("<p><a href=\"../index.html\">All podcasts</a></p>\n{list}"),
+                    ),
+                });
+                index.push_str(&format!(
+                    "<li><a href=\"{dir}/index.html\">{}</a> ({} episodes)</li>\n",
+                    escape_html(&podcast.title),
+                    episodes.len()
+                ));
+            }
+            index.push_str("</ul>\n");
+            files.push(ExportFile {
+                path: PathBuf::from("index.html"),
+                content: html_page("Podcast summaries", &index),
+            });
+        }
+    }
+    files
+}
+
+/// An episode note: YAML front-matter, the summary and, if exported, the
+/// transcript. `podcast_note` links back to an Obsidian podcast index note.
+fn markdown_note(entry: &ExportEntry, podcast_note: Option<&str>) -> String {
+    let e = &entry.episode;
+    let s = &entry.summary;
+    let mut out = String::from("---\n");
+    out.push_str(&format!("title: {}\n", yaml_str(&e.title)));
+    match podcast_note {
+        Some(note) => out.push_str(&format!("podcast: {}\n", yaml_str(&format!("[[{note}]]")))),
+        None => out.push_str(&format!("podcast: {}\n", yaml_str(&entry.podcast.title))),
+    }
+    if let Some(date) = e.published_at {
+        out.push_str(&format!("date: {}\n", date.format("%Y-%m-%d")));
+    }
+    if let Some(secs) = e.duration_secs {
+        out.push_str(&format!("duration: {}\n", format_duration(secs)));
+    }
+    out.push_str(&format!("episode_id: {}\n", e.id));
+    out.push_str(&format!("model: {}\n", yaml_str(&s.model)));
+    out.push_str(&format!("summary_version: {}\n", s.version));
+    if let Some(ref language) = s.language {
+        out.push_str(&format!("language: {}\n", yaml_str(language)));
+    }
+    // Obsidian tags can't contain spaces
+    let tags: Vec<_> = entry
+        .tags
+        .iter()
+        .map(|t| match podcast_note {
+            Some(_) => yaml_str(&t.replace(' ', "-")),
+            None => yaml_str(t),
+        })
+        .collect();
+    out.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    if !entry.topics.is_empty() {
+        let topics: Vec<_> = entry.topics.iter().map(|t| yaml_str(t)).collect();
+        out.push_str(&format!("topics: [{}]\n", topics.join(", ")));
+    }
+    out.push_str("---\n\n");
+
+    out.push_str(&format!("# {}\n\n", e.title));
+    if let Some(note) = podcast_note {
+        out.push_str(&format!("Podcast: [[{note}]]\n\n"));
+    }
+    out.push_str(s.content.trim());
+    out.push('\n');
+    if let Some(ref transcript) = entry.transcript {
+        out.push_str(&format!("\n## Transcript\n\n{}\n", transcript.trim()));
+    }
+    out
+}
+
+fn html_episode(entry: &ExportEntry) -> String {
+    let e = &entry.episode;
+    let mut body = format!(
+        "<p><a href=\"index.html\">{}</a>",
+        escape_html(&entry.podcast.title)
+    );
+    if let Some(date) = e.published_at {
+        body.push_str(&format!(" · {}", date.format("%Y-%m-%d")));
+    }
+    if let Some(secs) = e.duration_secs {
+        body.push_str(&format!(" · {}", format_duration(secs)));
+    }
+    body.push_str("</p>\n");
+    if !entry.tags.is_empty() {
+        let tags: Vec<_> = entry.tags.iter().map(|t| escape_html(t)).collect();
+        body.push_str(&format!("<p>Tags: {}</p>\n", tags.join(", ")));
+    }
+    body.push_str(&markdown_to_html(&entry.summary.content));
+    body.push_str(&format!(
+        "<p><small>Summary v{} by {}</small></p>\n",
+        entry.summary.version,
+        escape_html(&entry.summary.model)
+    ));
+    if let Some(ref transcript) = entry.transcript {
+        body.push_str("<h2>Transcript</h2>\n");
+        body.push_str(&markdown_to_html(transcript));
+    }
+    html_page(&e.title, &body)
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    let title = escape_html(title);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n"
+    )
+}
+
+/// The Markdown summaries use: `#` headings, `-`/`*` bullets and paragraphs
+/// separated by blank lines. Everything else is escaped text.
+pub fn markdown_to_html(text: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut in_list = false;
+    let flush = |paragraph: &mut Vec<&str>, out: &mut String| {
+        if !paragraph.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", escape_html(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+    for line in text.lines().map(str::trim) {
+        let item = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "));
+        if item.is_none() && in_list {
+            out.push_str("</ul>\n");
+            in_list = false;
+        }
+        if line.is_empty() {
+            flush(&mut paragraph, &mut out);
+        } else if let Some(item) = item {
+            flush(&mut paragraph, &mut out);
+            if !in_list {
+                out.push_str("<ul>\n");
+                in_list = true;
+            }
+            out.push_str(&format!("<li>{}</li>\n", escape_html(item)));
+        } else if line.starts_with('#') {
+            flush(&mut paragraph, &mut out);
+            let level = line.chars().take_while(|&c| c == '#').count().min(6);
+            // The page title is the h1
+            let level = (level + 1).min(6);
+            let heading = line.trim_start_matches('#').trim();
+            out.push_str(&format!("<h{level}>{}</h{level}>\n", escape_html(heading)));
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut out);
+    if in_list {
+        out.push_str("</ul>\n");
+    }
+    out
+}
+
+fn date_prefix(entry: &ExportEntry) -> String {
+    entry
+        .episode
+        .published_at
+        .map(|d| format!("{} ", d.format("%Y-%m-%d")))
+        .unwrap_or_default()
+}
+
+/// `2024-03-01-async-rust-42`: sorts by date, unique by episode ID.
+fn episode_slug(entry: &ExportEntry) -> String {
+    let date = entry
+        .episode
+        .published_at
+        .map(|d| format!("{}-", d.format("%Y-%m-%d")))
+        .unwrap_or_default();
+    format!("{date}{}-{}", slug(&entry.episode.title), entry.episode.id)
+}
+
+/// Obsidian links by note name, so names must be unique within the vault
+/// folder and readable. No `#`: inside `[[...]]` it starts a heading link.
+fn episode_note_name(entry: &ExportEntry) -> String {
+    format!(
+        "{}{} ({})",
+        date_prefix(entry),
+        note_name(&entry.episode.title),
+        entry.episode.id
+    )
+}
+
+/// Lowercase words joined by `-`, for file names. Letters outside ASCII
+/// (e.g. CJK titles) are kept.
+fn slug(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out: String = out.trim_end_matches('-').chars().take(60).collect();
+    let out = out.trim_end_matches('-').to_string();
+    if out.is_empty() {
+        "untitled".to_string()
+    } else {
+        out
+    }
+}
+
+/// A title without the characters Obsidian and file systems reject in note names.
+fn note_name(s: &str) -> String {
+    let cleaned: String = s
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | '#' | '^' | '|' | '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let name: String = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(100)
+        .collect();
+    let name = name.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn yaml_str(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EpisodeStatus;
+    use chrono::Utc;
+    use std::path::Path;
+
+    fn entry(id: i64, title: &str, date: &str) -> ExportEntry {
+        ExportEntry {
+            podcast: Podcast {
+                id: 1,
+                title: "Rust: Weekly".to_string(),
+                feed_url: "https://ex.com/feed".to_string(),
+                website_url: None,
+                description: None,
+                last_checked: None,
+                added_at: Utc::now(),
+            },
+            episode: Episode {
+                id,
+                podcast_id: 1,
+                guid: format!("g{id}"),
+                title: title.to_string(),
+                description: None,
+                audio_url: "https://ex.com/e.mp3".to_string(),
+                published_at: date.parse().ok(),
+                duration_secs: Some(3720),
+                status: EpisodeStatus::Summarized,
+                audio_path: None,
+                transcript_path: None,
+                discovered_at: Utc::now(),
+            },
+            summary: Summary {
+                id,
+                episode_id: id,
+                content: "## Overview\nAsync & you.\n\n- one\n- two".to_string(),
+                model: "gpt-4o-mini".to_string(),
+                prompt_tokens: None,
+                output_tokens: None,
+                created_at: Utc::now(),
+                style: None,
+                prompt_hash: None,
+                language: None,
+                version: 2,
+            },
+            topics: vec!["async".to_string()],
+            tags: vec!["rust lang".to_string()],
+            transcript: None,
+        }
+    }
+
+    #[test]
+    fn parse_formats() {
+        use clap::ValueEnum;
+        let parse = |s| ExportFormat::from_str(s, true);
+        assert_eq!(parse("md").unwrap(), ExportFormat::Markdown);
+        assert_eq!(parse("Obsidian").unwrap(), ExportFormat::Obsidian);
+        assert!(parse("pdf").is_err());
+    }
+
+    #[test]
+    fn markdown_has_front_matter() {
+        let entries = [entry(42, "Async \"Rust\"", "2024-03-04T00:00:00Z")];
+        let files = render(ExportFormat::Markdown, &entries, &entries);
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].path,
+            PathBuf::from("rust-weekly/2024-03-04-async-rust-42.md")
+        );
+        let content = &files[0].content;
+        assert!(
+            content.starts_with("---\ntitle: \"Async \\\"Rust\\\"\"\npodcast: \"Rust: Weekly\"\n")
+        );
+        assert!(content.contains("date: 2024-03-04\nduration: 1h02m\n"));
+        assert!(content.contains("model: \"gpt-4o-mini\"\n"));
+        assert!(content.contains("tags: [\"rust lang\"]\n"));
+        assert!(content.contains("---\n\n# Async \"Rust\"\n\n## Overview"));
+    }
+
+    #[test]
+    fn obsidian_links_podcast_and_episode_notes() {
+        let entries = [
+            entry(1, "Older", "2024-01-01T00:00:00Z"),
+            entry(2, "Newer / part 2", "2024-02-01T00:00:00Z"),
+        ];
+        let files = render(ExportFormat::Obsidian, &entries, &entries);
+        let index = files
+            .iter()
+            .find(|f| f.path == Path::new("Rust Weekly.md"))
+            .unwrap();
+        assert!(index.content.contains(
+            "## Episodes\n\n- 2024-02-01 [[2024-02-01 Newer part 2 (2)]]\n- 2024-01-01 [[2024-01-01 Older (1)]]\n"
+        ));
+        let note = files
+            .iter()
+            .find(|f| f.path == Path::new("Rust Weekly/2024-01-01 Older (1).md"))
+            .unwrap();
+        assert!(note.content.contains("podcast: \"[[Rust Weekly]]\"\n"));
+        assert!(note.content.contains("tags: [\"rust-lang\"]\n"));
+        assert!(note.content.contains("Podcast: [[Rust Weekly]]\n"));
+
+        // A `#` inside a link would point at a heading instead of the note
+        for file in &files {
+            for link in file.content.split("[[").skip(1) {
+                let target = link.split("]]").next().unwrap();
+                assert!(!target.contains('#'), "{target}");
+            }
+        }
+    }
+
+    #[test]
+    fn html_site_has_indexes_and_escapes() {
+        let entries = [entry(7, "<Intro>", "2024-03-04T00:00:00Z")];
+        let files = render(ExportFormat::Html, &entries, &entries);
+        let paths: Vec<_> = files.iter().map(|f| f.path.clone()).collect();
+        assert!(paths.contains(&PathBuf::from("index.html")));
+        assert!(paths.contains(&PathBuf::from("rust-weekly/index.html")));
+        let page = files
+            .iter()
+            .find(|f| f.path == Path::new("rust-weekly/2024-03-04-intro-7.html"))
+            .unwrap();
+        assert!(page.content.contains("<title>&lt;Intro&gt;</title>"));
+        assert!(page.content.contains(
+            "<h3>Overview</h3>\n<p>Async &amp; you.</p>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n"
+        ));
+    }
+
+    #[test]
+    fn rendering_is_deterministic() {
+        let entries = [entry(1, "A", "2024-01-01T00:00:00Z")];
+        for format in [
+            ExportFormat::Markdown,
+            ExportFormat::Html,
+            ExportFormat::Obsidian,
+        ] {
+            assert_eq!(
+                render(format, &entries, &entries),
+                render(format, &entries, &entries)
+            );
+        }
+    }
+
+    #[test]
+    fn indexes_list_every_indexed_episode() {
+        let all = [
+            entry(1, "First", "2024-01-01T00:00:00Z"),
+            entry(2, "Second", "2024-02-01T00:00:00Z"),
+        ];
+        let files = render(ExportFormat::Html, &all[..1], &all);
+        let pages: Vec<_> = files
+            .iter()
+            .filter(|f| !f.path.ends_with("index.html"))
+            .collect();
+        assert_eq!(pages.len(), 1);
+        let index = files
+            .iter()
+            .find(|f| f.path == Path::new("rust-weekly/index.html"))
+            .unwrap();
+        assert!(index.content.contains("2024-01-01-first-1.html"));
+        assert!(index.content.contains("2024-02-01-second-2.html"));
+    }
+
+    #[test]
+    fn slugs_and_note_names() {
+        assert_eq!(slug("Hello, World!"), "hello-world");
+        assert_eq!(slug("分散式 資料庫"), "分散式-資料庫");
+        assert_eq!(slug("???"), "untitled");
+        assert_eq!(note_name("Q&A: what's [next]?"), "Q&A what's next");
+    }
+}
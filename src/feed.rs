@@ -10,6 +10,49 @@ pub struct FeedEntry {
     pub duration_secs: Option<i64>,
 }
 
+/// Which of the episodes already in a feed get processed when subscribing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backfill {
+    /// None: only episodes published after subscribing
+    None,
+    /// The most recent N episodes
+    Latest(usize),
+    /// Episodes published on or after a time
+    Since(DateTime<Utc>),
+}
+
+impl Backfill {
+    /// Whether each of `entries` (in feed order) should be processed.
+    pub fn select(&self, entries: &[FeedEntry]) -> Vec<bool> {
+        let mut selected = vec![false; entries.len()];
+        match *self {
+            Self::None => {}
+            Self::Latest(n) => {
+                // Newest first; undated entries rank last, in feed order
+                let mut order: Vec<usize> = (0..entries.len()).collect();
+                order.sort_by_key(|&i| std::cmp::Reverse(entries[i].published_at));
+                for i in order.into_iter().take(n) {
+                    selected[i] = true;
+                }
+            }
+            Self::Since(since) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    selected[i] = entry.published_at.is_some_and(|t| t >= since);
+                }
+            }
+        }
+        selected
+    }
+
+    /// Why an entry the backfill didn't select is skipped.
+    pub fn skip_reason(&self) -> &'static str {
+        match self {
+            Self::None => "published before subscribing",
+            _ => "outside the backfill",
+        }
+    }
+}
+
 pub struct FeedInfo {
     pub title: String,
     pub website_url: Option<String>,
@@ -142,3 +185,42 @@ pub async fn sync_feed(
     db.update_last_checked(podcast.id)?;
     Ok(new_episodes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(guid: &str, published_at: Option<&str>) -> FeedEntry {
+        FeedEntry {
+            guid: guid.to_string(),
+            title: guid.to_string(),
+            description: None,
+            audio_url: format!("https://ex.com/{guid}.mp3"),
+            published_at: published_at.map(|t| t.parse().unwrap()),
+            duration_secs: None,
+        }
+    }
+
+    #[test]
+    fn backfill_selects_latest_or_since() {
+        // Feeds aren't always newest first
+        let entries = vec![
+            entry("b", Some("2024-05-20T00:00:00Z")),
+            entry("undated", None),
+            entry("c", Some("2024-05-30T00:00:00Z")),
+            entry("a", Some("2023-01-01T00:00:00Z")),
+        ];
+
+        assert_eq!(Backfill::None.select(&entries), vec![false; 4]);
+        assert_eq!(
+            Backfill::Latest(2).select(&entries),
+            vec![true, false, true, false]
+        );
+        assert_eq!(Backfill::Latest(10).select(&entries), vec![true; 4]);
+        let since = "2024-05-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            Backfill::Since(since).select(&entries),
+            vec![true, false, true, false]
+        );
+    }
+}
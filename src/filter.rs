@@ -0,0 +1,154 @@
+//! Which newly found episodes get processed: a podcast's include/exclude rules
+//! and age limit from config. Episodes a policy leaves out are marked skipped
+//! with the reason.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+
+use crate::config::AppConfig;
+use crate::models::Podcast;
+
+/// A podcast's selection policy, compiled from config.
+#[derive(Debug)]
+pub struct Policy {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    max_age: Option<Duration>,
+}
+
+impl Policy {
+    pub fn for_podcast(config: &AppConfig, podcast: &Podcast) -> Result<Self> {
+        let podcast_config = config.podcast_config(podcast)?;
+        let compile = |patterns: Option<Vec<String>>| {
+            patterns
+                .unwrap_or_default()
+                .iter()
+                .map(|p| {
+                    Regex::new(p).with_context(|| {
+                        format!("Invalid filter pattern `{p}` for \"{}\"", podcast.title)
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            include: compile(podcast_config.include)?,
+            exclude: compile(podcast_config.exclude)?,
+            max_age: config.max_episode_age(podcast)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty() && self.max_age.is_none()
+    }
+
+    /// Why an episode should be skipped, or `None` to process it. Rules match
+    /// against the title or the description; exclusions win over inclusions.
+    pub fn skip_reason(
+        &self,
+        title: &str,
+        description: Option<&str>,
+        published_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        let matches =
+            |re: &Regex| re.is_match(title) || description.is_some_and(|d| re.is_match(d));
+        if let Some(re) = self.exclude.iter().find(|re| matches(re)) {
+            return Some(format!("excluded by `{}`", re.as_str()));
+        }
+        if !self.include.is_empty() && !self.include.iter().any(matches) {
+            return Some("matches no include rule".to_string());
+        }
+        if let (Some(published), Some(age)) = (published_at, self.max_age)
+            && published < now - age
+        {
+            return Some(format!("older than {} days", age.num_days()));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PodcastConfig;
+
+    fn podcast() -> Podcast {
+        Podcast {
+            id: 1,
+            title: "Daily News".to_string(),
+            feed_url: String::new(),
+            website_url: None,
+            description: None,
+            last_checked: None,
+            added_at: Utc::now(),
+        }
+    }
+
+    fn policy(podcast_config: PodcastConfig) -> Result<Policy> {
+        let mut config = AppConfig::default();
+        config
+            .podcasts
+            .insert("Daily News".to_string(), podcast_config);
+        Policy::for_podcast(&config, &podcast())
+    }
+
+    #[test]
+    fn rules_pick_episodes_and_name_the_rule() {
+        let policy = policy(PodcastConfig {
+            include: Some(vec!["Rust".to_string()]),
+            exclude: Some(vec!["(?i)trailer|rerun|best of".to_string()]),
+            max_episode_age_days: Some(30),
+            ..Default::default()
+        })
+        .unwrap();
+        let now: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+        let recent = Some("2024-05-30T00:00:00Z".parse().unwrap());
+        let check = |title, description| policy.skip_reason(title, description, recent, now);
+
+        assert_eq!(check("Rust 2024 in review", None), None);
+        assert_eq!(check("Weekly roundup", Some("Mostly about Rust")), None);
+        assert_eq!(
+            check("Best Of Rust", None).as_deref(),
+            Some("excluded by `(?i)trailer|rerun|best of`")
+        );
+        assert_eq!(
+            check("Go generics", None).as_deref(),
+            Some("matches no include rule")
+        );
+        assert_eq!(
+            policy
+                .skip_reason(
+                    "Rust history",
+                    None,
+                    Some("2024-01-01T00:00:00Z".parse().unwrap()),
+                    now
+                )
+                .as_deref(),
+            Some("older than 30 days")
+        );
+        // Undated episodes are never too old
+        assert_eq!(policy.skip_reason("Rust history", None, None, now), None);
+    }
+
+    #[test]
+    fn no_rules_process_everything() {
+        let policy = policy(PodcastConfig::default()).unwrap();
+        assert!(policy.is_empty());
+        assert_eq!(policy.skip_reason("Anything", None, None, Utc::now()), None);
+    }
+
+    #[test]
+    fn invalid_pattern_names_podcast() {
+        let err = policy(PodcastConfig {
+            exclude: Some(vec!["(unclosed".to_string()]),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid filter pattern `(unclosed` for \"Daily News\""),
+            "{err}"
+        );
+    }
+}
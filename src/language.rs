@@ -0,0 +1,104 @@
+use anyhow::Result;
+use ferrous_opencc::OpenCC;
+
+/// Output language for summaries, parsed from a code like `zh-TW` or a free-form name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryLanguage {
+    /// The value as configured, stored alongside each summary
+    pub code: String,
+    /// Human-readable name injected into the prompt
+    pub name: String,
+    /// OpenCC conversion applied to the generated summary, if the output is Chinese
+    pub chinese_conversion: Option<&'static str>,
+}
+
+impl SummaryLanguage {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        let (name, conversion) = match value.to_lowercase().as_str() {
+            "en" | "english" => ("English", None),
+            "zh-tw" | "zh-hant-tw" => ("Traditional Chinese (Taiwan)", Some("s2twp")),
+            "zh-hk" | "zh-hant-hk" => ("Traditional Chinese (Hong Kong)", Some("s2hk")),
+            "zh-hant" => ("Traditional Chinese", Some("s2t")),
+            "zh" | "zh-cn" | "zh-hans" | "zh-sg" => ("Simplified Chinese", Some("t2s")),
+            "ja" | "japanese" => ("Japanese", None),
+            "ko" | "korean" => ("Korean", None),
+            "es" | "spanish" => ("Spanish", None),
+            "fr" | "french" => ("French", None),
+            "de" | "german" => ("German", None),
+            other => {
+                let conversion = if other.contains("traditional") {
+                    Some("s2t")
+                } else if other.contains("simplified") {
+                    Some("t2s")
+                } else {
+                    None
+                };
+                return Self {
+                    code: value.to_string(),
+                    name: value.to_string(),
+                    chinese_conversion: conversion,
+                };
+            }
+        };
+        Self {
+            code: value.to_string(),
+            name: name.to_string(),
+            chinese_conversion: conversion,
+        }
+    }
+
+    /// Normalize the script of a generated summary, e.g. so a `zh-TW` summary never
+    /// contains Simplified characters the model slipped in.
+    pub fn localize(&self, text: &str) -> Result<String> {
+        Ok(match self.converter()? {
+            Some(cc) => cc.convert(text),
+            None => text.to_string(),
+        })
+    }
+
+    /// The converter [`Self::localize`] applies, for callers with several texts
+    /// to convert.
+    pub fn converter(&self) -> Result<Option<OpenCC>> {
+        self.chinese_conversion
+            .map(crate::transcribe::opencc)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_known_codes() {
+        let en = SummaryLanguage::parse("en");
+        assert_eq!(en.name, "English");
+        assert!(en.chinese_conversion.is_none());
+
+        let tw = SummaryLanguage::parse("zh-TW");
+        assert_eq!(tw.code, "zh-TW");
+        assert_eq!(tw.name, "Traditional Chinese (Taiwan)");
+        assert_eq!(tw.chinese_conversion, Some("s2twp"));
+
+        let cn = SummaryLanguage::parse("zh");
+        assert_eq!(cn.chinese_conversion, Some("t2s"));
+    }
+
+    #[test]
+    fn parse_free_form_names() {
+        let lang = SummaryLanguage::parse("Traditional Chinese");
+        assert_eq!(lang.name, "Traditional Chinese");
+        assert_eq!(lang.chinese_conversion, Some("s2t"));
+
+        let lang = SummaryLanguage::parse("Brazilian Portuguese");
+        assert_eq!(lang.name, "Brazilian Portuguese");
+        assert!(lang.chinese_conversion.is_none());
+    }
+
+    #[test]
+    fn localize_without_conversion_is_identity() {
+        let lang = SummaryLanguage::parse("en");
+        assert_eq!(lang.localize("hello").unwrap(), "hello");
+    }
+}
@@ -1,20 +1,44 @@
+mod ask;
 mod audio;
 mod cli;
 mod commands;
 mod config;
+mod daemon;
+mod dates;
 mod db;
+mod diff;
+mod digest;
 mod download;
+mod embed;
 mod error;
+mod export;
 mod feed;
+mod filter;
+mod language;
 mod models;
+mod notify;
+mod output;
+mod pipeline;
+mod player;
+mod progress;
+mod prompts;
+mod query;
+mod rss;
+mod search;
+mod server;
 mod summarize;
+mod tags;
+#[cfg(test)]
+mod testutil;
+mod text;
 mod transcribe;
+mod tui;
 
 use anyhow::Result;
 use clap::Parser;
 use tracing_subscriber::EnvFilter;
 
-use cli::{Cli, Command, ConfigAction};
+use cli::{Cli, Command, ConfigAction, FilterAction};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -25,38 +49,241 @@ async fn main() -> Result<()> {
     } else {
         EnvFilter::new("warn")
     };
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+    // The TUI owns the screen; log lines would be drawn over it
+    if !matches!(cli.command, Command::Tui) {
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+    }
 
     let config = config::AppConfig::load()?;
+    let format = cli.output;
+    if !format.is_table() && !cli.command.supports_output() {
+        anyhow::bail!(
+            "--output only applies to list, show, search, topics, queue list, tag list and config show"
+        );
+    }
 
     match &cli.command {
-        Command::Add { url } => {
-            commands::add::run(url, &config).await?;
+        Command::Add {
+            url,
+            backfill,
+            since,
+        } => {
+            let backfill = match (backfill, since) {
+                (Some(n), _) => feed::Backfill::Latest(*n),
+                (_, Some(since)) => {
+                    feed::Backfill::Since(dates::parse_since(since, chrono::Utc::now())?)
+                }
+                (None, None) => feed::Backfill::None,
+            };
+            commands::add::run(url, backfill, &config).await?;
         }
         Command::Remove { name, yes, purge } => {
             commands::remove::run(name, *yes, *purge, &config)?;
         }
-        Command::List { name } => {
-            commands::list::run(name.as_deref(), &config)?;
+        Command::List {
+            name,
+            failed,
+            tag,
+            status,
+            since,
+            until,
+            sort,
+            limit,
+            offset,
+        } => {
+            let options = query::ListOptions {
+                status: status.clone(),
+                since: since.clone(),
+                until: until.clone(),
+                tag: tag.clone(),
+                sort: sort.clone(),
+                limit: *limit,
+                offset: *offset,
+            };
+            commands::list::run(name.as_deref(), *failed, &options, format, &config)?;
         }
         Command::Sync {
             name,
             episode,
             download_only,
             redo,
+            resummarize,
+            model,
             cpu,
+            style,
+            lang,
+            retry_failed,
+            watch,
+            interval,
         } => {
             let mut config = config;
             if let Some(pct) = cpu {
                 config.transcription.cpu_percent = *pct;
             }
-            commands::sync::run(name.as_deref(), *episode, *download_only, *redo, &config).await?;
+            if let Some(model) = model {
+                config.summarization.model = model.clone();
+            }
+            let options = commands::sync::SyncOptions {
+                download_only: *download_only,
+                redo: *redo,
+                resummarize: *resummarize,
+                retry_failed: *retry_failed,
+                summary: prompts::SummaryOptions {
+                    style: style.clone(),
+                    language: lang.clone(),
+                },
+            };
+            if *watch {
+                let interval = interval.as_deref().map(dates::parse_duration).transpose()?;
+                commands::sync::watch(interval, &options, &config).await?;
+            } else {
+                commands::sync::run(name.as_deref(), *episode, &options, &config).await?;
+            }
+        }
+        Command::Queue { action } => {
+            commands::queue::run(action, format, &config)?;
+        }
+        Command::Skip { selection } => {
+            commands::queue::skip(selection, &config)?;
         }
+        Command::Unskip { selection } => {
+            commands::queue::unskip(selection, &config)?;
+        }
+        Command::Filter { action } => match action {
+            FilterAction::Test { podcast, limit } => {
+                commands::filter::test(podcast, *limit, &config)?;
+            }
+        },
         Command::Show {
             episode_id,
             transcript,
+            version,
+            model,
+            lang,
+            versions,
+            diff,
+        } => {
+            let selection = commands::show::SummarySelection {
+                version: *version,
+                model: model.clone(),
+                language: lang.clone(),
+                list_versions: *versions,
+                diff_against: *diff,
+            };
+            commands::show::run(*episode_id, *transcript, &selection, format, &config)?;
+        }
+        Command::Styles { name } => {
+            commands::styles::run(name.as_deref(), &config)?;
+        }
+        Command::Search {
+            query,
+            podcast,
+            since,
+            until,
+            tag,
+            limit,
+            semantic,
+            reindex,
+        } => {
+            if *reindex {
+                commands::search::reindex(*semantic, &config).await?;
+            }
+            if !query.is_empty() {
+                let options = commands::search::SearchOptions {
+                    podcast: podcast.clone(),
+                    since: since.clone(),
+                    until: until.clone(),
+                    tag: tag.clone(),
+                    limit: *limit,
+                    semantic: *semantic,
+                };
+                commands::search::run(&query.join(" "), &options, format, &config).await?;
+            }
+        }
+        Command::Ask {
+            question,
+            podcast,
+            since,
+            until,
+            tag,
+            passages,
+        } => {
+            let options = commands::search::SearchOptions {
+                podcast: podcast.clone(),
+                since: since.clone(),
+                until: until.clone(),
+                tag: tag.clone(),
+                limit: *passages,
+                semantic: true,
+            };
+            commands::ask::run(&question.join(" "), &options, &config).await?;
+        }
+        Command::Digest {
+            since,
+            format,
+            lang,
+            tag,
+            out,
+        } => {
+            commands::digest::run(
+                since,
+                *format,
+                lang.as_deref(),
+                tag.as_deref(),
+                out.as_deref(),
+                &config,
+            )
+            .await?;
+        }
+        Command::Export {
+            dir,
+            format,
+            podcast,
+            episode,
+            tag,
+            transcripts,
+        } => {
+            let options = commands::export::ExportOptions {
+                format: *format,
+                podcast: podcast.clone(),
+                episode: *episode,
+                tag: tag.clone(),
+                transcripts: *transcripts,
+            };
+            commands::export::run(dir, &options, &config)?;
+        }
+        Command::Rss {
+            podcast,
+            limit,
+            link,
+            out,
         } => {
-            commands::show::run(*episode_id, *transcript, &config)?;
+            commands::rss::run(
+                podcast.as_deref(),
+                *limit,
+                link.as_deref(),
+                out.as_deref(),
+                &config,
+            )?;
+        }
+        Command::Play {
+            episode_id,
+            at,
+            player,
+        } => {
+            commands::play::run(*episode_id, at.as_deref(), player.as_deref(), &config).await?;
+        }
+        Command::Serve { bind } => {
+            commands::serve::run(bind.as_deref(), &config).await?;
+        }
+        Command::Tui => {
+            commands::tui::run(&config)?;
+        }
+        Command::Topics { topic } => {
+            commands::topics::run(topic.as_deref(), format, &config)?;
+        }
+        Command::Tag { action } => {
+            commands::tag::run(action, format, &config)?;
         }
         Command::Config { action } => match action {
             Some(ConfigAction::Path) => {
@@ -65,10 +292,20 @@ async fn main() -> Result<()> {
             Some(ConfigAction::Set { key, value }) => {
                 commands::config_set::run(key, value)?;
             }
-            Some(ConfigAction::Show) | None => {
-                let content = toml::to_string_pretty(&config)?;
-                println!("{content}");
-            }
+            Some(ConfigAction::Show) | None => match format {
+                output::OutputFormat::Table => {
+                    let content = toml::to_string_pretty(&config)?;
+                    println!("{content}");
+                }
+                output::OutputFormat::Csv => {
+                    let rows: Vec<_> = output::flatten(&serde_json::to_value(&config)?)
+                        .into_iter()
+                        .map(|(key, value)| serde_json::json!({ "key": key, "value": value }))
+                        .collect();
+                    output::print_records(format, &rows)?;
+                }
+                _ => output::print_record(format, &config)?,
+            },
         },
     }
 
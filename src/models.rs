@@ -34,7 +34,16 @@ pub enum EpisodeStatus {
     Downloaded,
     Transcribed,
     Summarized,
-    Failed(String),
+    /// Left out by a selection policy or by hand; sync ignores it until queued
+    Skipped {
+        /// The policy or filter rule that left it out; `None` when skipped by hand
+        reason: Option<String>,
+    },
+    Failed {
+        failure: Failure,
+        /// Last stage that succeeded before the failure, where a retry picks up
+        completed: Option<JobStage>,
+    },
 }
 
 impl EpisodeStatus {
@@ -44,27 +53,256 @@ impl EpisodeStatus {
             Self::Downloaded => "downloaded",
             Self::Transcribed => "transcribed",
             Self::Summarized => "summarized",
-            Self::Failed(_) => "failed",
+            Self::Skipped { .. } => "skipped",
+            Self::Failed { .. } => "failed",
         }
     }
 
-    pub fn from_db(status: &str, fail_reason: Option<&str>) -> Self {
+    /// The short label the episode tables show.
+    pub fn badge(&self) -> &'static str {
+        match self {
+            Self::New => "[new]",
+            Self::Downloaded => "[dl]",
+            Self::Transcribed => "[txt]",
+            Self::Summarized => "[done]",
+            Self::Skipped { .. } => "[skip]",
+            Self::Failed { .. } => "[err]",
+        }
+    }
+
+    pub fn from_db(status: &str, reason: Option<&str>, completed: Option<&str>) -> Self {
         match status {
             "new" => Self::New,
             "downloaded" => Self::Downloaded,
             "transcribed" => Self::Transcribed,
             "summarized" => Self::Summarized,
-            "failed" => Self::Failed(fail_reason.unwrap_or("unknown").to_string()),
+            "skipped" => Self::Skipped {
+                reason: reason.map(str::to_string),
+            },
+            "failed" => Self::Failed {
+                failure: Failure::parse(reason.unwrap_or("unknown")),
+                completed: completed.map(JobStage::from_db),
+            },
             _ => Self::New,
         }
     }
 
-    pub fn fail_reason(&self) -> Option<&str> {
+    /// The stored reason: a failure as `"<kind>: <message>"`, or why an episode
+    /// was skipped.
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            Self::Failed { failure, .. } => Some(failure.to_string()),
+            Self::Skipped { reason } => reason.clone(),
+            _ => None,
+        }
+    }
+
+    /// The stored last successful stage of a failed episode.
+    pub fn completed_stage(&self) -> Option<JobStage> {
         match self {
-            Self::Failed(reason) => Some(reason),
+            Self::Failed { completed, .. } => *completed,
             _ => None,
         }
     }
+
+    /// The stage processing picks up from: the one after the last that succeeded.
+    pub fn resume_stage(&self) -> JobStage {
+        match self {
+            Self::New | Self::Skipped { .. } => JobStage::Download,
+            Self::Downloaded => JobStage::Transcribe,
+            Self::Transcribed | Self::Summarized => JobStage::Summarize,
+            Self::Failed { completed, .. } => completed
+                .and_then(|stage| stage.next())
+                .unwrap_or(JobStage::Download),
+        }
+    }
+}
+
+/// What went wrong with an episode, which decides whether retrying can help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureKind {
+    Download,
+    /// The audio couldn't be read; retrying the same file won't help
+    Decode,
+    Transcribe,
+    Summarize,
+    /// The API rejected the key; needs a config change
+    Auth,
+    /// Rate limit or quota exhausted; clears up with time
+    Quota,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Download => "download",
+            Self::Decode => "decode",
+            Self::Transcribe => "transcribe",
+            Self::Summarize => "summarize",
+            Self::Auth => "auth",
+            Self::Quota => "quota",
+        }
+    }
+
+    fn from_str(kind: &str) -> Option<Self> {
+        match kind {
+            "download" => Some(Self::Download),
+            "decode" => Some(Self::Decode),
+            "transcribe" => Some(Self::Transcribe),
+            "summarize" => Some(Self::Summarize),
+            "auth" => Some(Self::Auth),
+            "quota" => Some(Self::Quota),
+            _ => None,
+        }
+    }
+
+    /// Human-readable description for listings.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Download => "download failed",
+            Self::Decode => "audio could not be decoded",
+            Self::Transcribe => "transcription failed",
+            Self::Summarize => "summarization failed",
+            Self::Auth => "API key rejected",
+            Self::Quota => "rate limit or quota exceeded",
+        }
+    }
+
+    /// Whether sync retries this kind of failure on its own.
+    pub fn is_transient(&self) -> bool {
+        !matches!(self, Self::Decode | Self::Auth)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Failure {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl Failure {
+    /// Categorize an error raised while running `stage`.
+    pub fn classify(stage: JobStage, message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let kind = if ["(401)", "(403)", "api key not set", "invalid api key"]
+            .iter()
+            .any(|p| lower.contains(p))
+        {
+            FailureKind::Auth
+        } else if ["(429)", "quota", "rate limit", "resource_exhausted"]
+            .iter()
+            .any(|p| lower.contains(p))
+        {
+            FailureKind::Quota
+        } else {
+            match stage {
+                JobStage::Download => FailureKind::Download,
+                JobStage::Transcribe
+                    if ["decode", "probe audio", "no audio track", "open audio"]
+                        .iter()
+                        .any(|p| lower.contains(p)) =>
+                {
+                    FailureKind::Decode
+                }
+                JobStage::Transcribe => FailureKind::Transcribe,
+                JobStage::Summarize => FailureKind::Summarize,
+            }
+        };
+        Self {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    /// Parse a stored `"<kind>: <message>"` reason. Reasons written before
+    /// categories existed start with a stage name and are classified again.
+    pub fn parse(reason: &str) -> Self {
+        let (prefix, message) = reason.split_once(": ").unwrap_or(("", reason));
+        match FailureKind::from_str(prefix) {
+            Some(FailureKind::Download) => Self::classify(JobStage::Download, message),
+            Some(FailureKind::Transcribe) => Self::classify(JobStage::Transcribe, message),
+            Some(FailureKind::Summarize) => Self::classify(JobStage::Summarize, message),
+            Some(kind) => Self {
+                kind,
+                message: message.to_string(),
+            },
+            None => Self::classify(JobStage::Download, reason),
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.message)
+    }
+}
+
+/// A step of the sync pipeline, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum JobStage {
+    Download,
+    Transcribe,
+    Summarize,
+}
+
+impl JobStage {
+    pub const ALL: [JobStage; 3] = [Self::Download, Self::Transcribe, Self::Summarize];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Download => "download",
+            Self::Transcribe => "transcribe",
+            Self::Summarize => "summarize",
+        }
+    }
+
+    pub fn from_db(stage: &str) -> Self {
+        match stage {
+            "transcribe" => Self::Transcribe,
+            "summarize" => Self::Summarize,
+            _ => Self::Download,
+        }
+    }
+
+    pub fn next(&self) -> Option<Self> {
+        match self {
+            Self::Download => Some(Self::Transcribe),
+            Self::Transcribe => Some(Self::Summarize),
+            Self::Summarize => None,
+        }
+    }
+
+    pub fn prev(&self) -> Option<Self> {
+        match self {
+            Self::Download => None,
+            Self::Transcribe => Some(Self::Download),
+            Self::Summarize => Some(Self::Transcribe),
+        }
+    }
+}
+
+/// Persisted pipeline work for one episode. The job sits at the stage it will
+/// run next and is deleted once `final_stage` completes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: i64,
+    pub episode_id: i64,
+    pub podcast_id: i64,
+    pub stage: JobStage,
+    /// Last stage to run (`Download` for `sync --download-only`)
+    pub final_stage: JobStage,
+    /// Summarize even if the episode already has a summary
+    pub force_summary: bool,
+    /// Summary style and language requested when the job was queued
+    pub style: Option<String>,
+    pub language: Option<String>,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    /// When the job may run; `None` parks a failed job
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    /// Higher-priority jobs run first within a stage
+    pub priority: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +314,92 @@ pub struct Summary {
     pub prompt_tokens: Option<i64>,
     pub output_tokens: Option<i64>,
     pub created_at: DateTime<Utc>,
+    /// Summary style (template name) used to generate this summary
+    pub style: Option<String>,
+    /// Hash of the prompt template, to tell apart edits of the same style
+    pub prompt_hash: Option<String>,
+    /// Requested output language (e.g. "zh-TW"), `None` for the transcript's language
+    pub language: Option<String>,
+    /// 1-based position among the episode's summaries, oldest first
+    pub version: i64,
+}
+
+/// Typed summary sections, from JSON-mode output or parsed from the text format.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredSummary {
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub key_takeaways: Vec<String>,
+    #[serde(default)]
+    pub notable_quotes: Vec<Quote>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    #[serde(alias = "quote")]
+    pub text: String,
+    #[serde(default)]
+    pub speaker: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+}
+
+/// A timed piece of a transcript, as produced by whisper.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+/// An embedded passage of a transcript.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptChunk {
+    pub id: i64,
+    pub episode_id: i64,
+    pub position: i64,
+    pub text: String,
+    pub start_secs: Option<f64>,
+    pub end_secs: Option<f64>,
+    pub embedding: Vec<f32>,
+}
+
+/// A transcript passage ranked by semantic similarity to a query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassageHit {
+    pub episode_id: i64,
+    pub episode_title: String,
+    pub podcast_title: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub text: String,
+    pub start_secs: Option<f64>,
+    pub end_secs: Option<f64>,
+    /// Cosine similarity to the query
+    pub score: f32,
+}
+
+/// One full-text search result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub episode_id: i64,
+    pub episode_title: String,
+    pub podcast_title: String,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Matching excerpt, with matches wrapped in [`crate::search::HIGHLIGHT_START`]
+    /// and [`crate::search::HIGHLIGHT_END`]
+    pub snippet: String,
+}
+
+/// A user-defined label with how many podcasts and episodes carry it directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub podcasts: i64,
+    pub episodes: i64,
 }
 
 #[cfg(test)]
@@ -104,7 +428,12 @@ mod tests {
 
     #[test]
     fn status_as_str_failed() {
-        assert_eq!(EpisodeStatus::Failed("oops".to_string()).as_str(), "failed");
+        let failure = Failure::classify(JobStage::Download, "oops");
+        let status = EpisodeStatus::Failed {
+            failure,
+            completed: None,
+        };
+        assert_eq!(status.as_str(), "failed");
     }
 
     #[test]
@@ -114,8 +443,9 @@ mod tests {
             ("downloaded", EpisodeStatus::Downloaded),
             ("transcribed", EpisodeStatus::Transcribed),
             ("summarized", EpisodeStatus::Summarized),
+            ("skipped", EpisodeStatus::Skipped { reason: None }),
         ] {
-            let status = EpisodeStatus::from_db(status_str, None);
+            let status = EpisodeStatus::from_db(status_str, None, None);
             assert_eq!(status, expected);
             assert_eq!(status.as_str(), status_str);
         }
@@ -123,15 +453,117 @@ mod tests {
 
     #[test]
     fn status_failed_roundtrip() {
-        let status = EpisodeStatus::from_db("failed", Some("download error"));
-        assert_eq!(status, EpisodeStatus::Failed("download error".to_string()));
+        let status = EpisodeStatus::from_db("failed", Some("download: connection reset"), None);
+        assert_eq!(
+            status,
+            EpisodeStatus::Failed {
+                failure: Failure {
+                    kind: FailureKind::Download,
+                    message: "connection reset".to_string(),
+                },
+                completed: None,
+            }
+        );
         assert_eq!(status.as_str(), "failed");
-        assert_eq!(status.fail_reason(), Some("download error"));
+        assert_eq!(
+            status.reason().as_deref(),
+            Some("download: connection reset")
+        );
+    }
+
+    #[test]
+    fn status_skipped_keeps_reason() {
+        let status = EpisodeStatus::from_db("skipped", Some("excluded by `trailer`"), None);
+        assert_eq!(
+            status,
+            EpisodeStatus::Skipped {
+                reason: Some("excluded by `trailer`".to_string())
+            }
+        );
+        assert_eq!(status.reason().as_deref(), Some("excluded by `trailer`"));
+        assert_eq!(status.resume_stage(), JobStage::Download);
+    }
+
+    #[test]
+    fn failures_are_classified() {
+        let kind = |stage, msg| Failure::classify(stage, msg).kind;
+        assert_eq!(
+            kind(JobStage::Summarize, "Claude API error (401): bad key"),
+            FailureKind::Auth
+        );
+        assert_eq!(
+            kind(
+                JobStage::Summarize,
+                "API error (429): Resource has been exhausted"
+            ),
+            FailureKind::Quota
+        );
+        assert_eq!(
+            kind(JobStage::Transcribe, "Failed to probe audio format: x.mp3"),
+            FailureKind::Decode
+        );
+        assert_eq!(
+            kind(JobStage::Transcribe, "Whisper transcription failed: -1"),
+            FailureKind::Transcribe
+        );
+        assert_eq!(
+            kind(JobStage::Download, "Failed to download: https://x"),
+            FailureKind::Download
+        );
+        assert!(!FailureKind::Auth.is_transient());
+        assert!(FailureKind::Quota.is_transient());
+    }
+
+    #[test]
+    fn legacy_reasons_are_reclassified() {
+        let failure = Failure::parse("summarize: API error (429): slow down");
+        assert_eq!(failure.kind, FailureKind::Quota);
+        assert_eq!(failure.message, "API error (429): slow down");
+        assert_eq!(failure.to_string(), "quota: API error (429): slow down");
+
+        // Stored categories are kept as they are
+        assert_eq!(
+            Failure::parse("decode: bad frame").kind,
+            FailureKind::Decode
+        );
+        assert_eq!(Failure::parse("unknown").kind, FailureKind::Download);
     }
 
     #[test]
     fn status_unknown_falls_back_to_new() {
-        let status = EpisodeStatus::from_db("bogus", None);
+        let status = EpisodeStatus::from_db("bogus", None, None);
         assert_eq!(status, EpisodeStatus::New);
     }
+
+    #[test]
+    fn failed_status_resumes_after_last_completed_stage() {
+        let failed = |completed: Option<&str>| {
+            EpisodeStatus::from_db("failed", Some("summarize: API error (500)"), completed)
+        };
+        assert_eq!(
+            failed(Some("transcribe")).resume_stage(),
+            JobStage::Summarize
+        );
+        assert_eq!(
+            failed(Some("transcribe")).completed_stage(),
+            Some(JobStage::Transcribe)
+        );
+        assert_eq!(
+            failed(Some("download")).resume_stage(),
+            JobStage::Transcribe
+        );
+        // Failures recorded before stages were kept start over
+        assert_eq!(failed(None).resume_stage(), JobStage::Download);
+
+        assert_eq!(EpisodeStatus::New.resume_stage(), JobStage::Download);
+        assert_eq!(
+            EpisodeStatus::Downloaded.resume_stage(),
+            JobStage::Transcribe
+        );
+        assert_eq!(
+            EpisodeStatus::Summarized.resume_stage(),
+            JobStage::Summarize
+        );
+        assert_eq!(EpisodeStatus::Summarized.completed_stage(), None);
+    }
 }
@@ -0,0 +1,176 @@
+//! Delivery of new summaries to email, webhooks and chat.
+//!
+//! Each configured notifier is a [`NotifierConfig`] variant; [`deliver`] routes a
+//! summary to the podcast's notifiers and retries failures with backoff. Delivery
+//! is best effort: errors are reported to the caller, never recorded on the episode.
+
+mod smtp;
+mod webhook;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::{AppConfig, NotifierConfig};
+use crate::models::{Episode, Podcast};
+
+/// A new summary, as delivered to notifiers.
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryNotification {
+    pub podcast_id: i64,
+    pub podcast_title: String,
+    pub episode_id: i64,
+    pub episode_title: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub model: String,
+    pub summary: String,
+}
+
+impl SummaryNotification {
+    pub fn new(podcast: &Podcast, episode: &Episode, model: &str, summary: &str) -> Self {
+        Self {
+            podcast_id: podcast.id,
+            podcast_title: podcast.title.clone(),
+            episode_id: episode.id,
+            episode_title: episode.title.clone(),
+            published_at: episode.published_at,
+            model: model.to_string(),
+            summary: summary.to_string(),
+        }
+    }
+
+    /// One-line title, used as email subject and chat heading.
+    pub fn title(&self) -> String {
+        format!("{} - {}", self.episode_title, self.podcast_title)
+    }
+}
+
+/// Outcome of delivering to one notifier.
+#[derive(Debug)]
+pub struct Delivery {
+    pub notifier: String,
+    pub attempts: u32,
+    pub result: Result<()>,
+}
+
+/// Send a summary to every notifier routed for its podcast.
+pub async fn deliver(
+    client: &reqwest::Client,
+    podcast: &Podcast,
+    notification: &SummaryNotification,
+    config: &AppConfig,
+) -> Result<Vec<Delivery>> {
+    let mut deliveries = Vec::new();
+    for notifier in config.notifiers_for(podcast)? {
+        deliveries.push(deliver_to(client, notifier, notification, config).await);
+    }
+    Ok(deliveries)
+}
+
+/// Send to one notifier, retrying per `[notifications]`.
+pub async fn deliver_to(
+    client: &reqwest::Client,
+    notifier: &NotifierConfig,
+    notification: &SummaryNotification,
+    config: &AppConfig,
+) -> Delivery {
+    let retries = config.notifications.retries;
+    let mut delay = Duration::from_secs(config.notifications.retry_delay_secs);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = send(client, notifier, notification).await;
+        match result {
+            Err(e) if attempts <= retries => {
+                tracing::warn!(
+                    "Notifier {} failed (attempt {attempts}), retrying in {}s: {e}",
+                    notifier.name(),
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => {
+                return Delivery {
+                    notifier: notifier.name().to_string(),
+                    attempts,
+                    result,
+                };
+            }
+        }
+    }
+}
+
+async fn send(
+    client: &reqwest::Client,
+    notifier: &NotifierConfig,
+    notification: &SummaryNotification,
+) -> Result<()> {
+    match notifier {
+        NotifierConfig::Smtp(cfg) => smtp::send(cfg, notification).await,
+        NotifierConfig::Webhook(cfg) => {
+            webhook::post(client, &cfg.url, &webhook::generic_payload(notification)).await
+        }
+        NotifierConfig::Slack(cfg) => {
+            webhook::post(client, &cfg.url, &webhook::slack_payload(notification)).await
+        }
+        NotifierConfig::Discord(cfg) => {
+            webhook::post(client, &cfg.url, &webhook::discord_payload(notification)).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookConfig;
+    use crate::testutil::stub_http_server;
+
+    fn notification() -> SummaryNotification {
+        SummaryNotification {
+            podcast_id: 1,
+            podcast_title: "Rust Weekly".to_string(),
+            episode_id: 42,
+            episode_title: "Async".to_string(),
+            published_at: None,
+            model: "m".to_string(),
+            summary: "Summary text".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_retries_until_success() {
+        let (url, server) = stub_http_server(vec![(500, "{}"), (503, "{}"), (200, "{}")]).await;
+        let mut config = AppConfig::default();
+        config.notifications.retry_delay_secs = 0;
+        let notifier = NotifierConfig::Webhook(WebhookConfig {
+            name: "hook".to_string(),
+            url,
+        });
+
+        let delivery =
+            deliver_to(&reqwest::Client::new(), &notifier, &notification(), &config).await;
+        assert!(delivery.result.is_ok());
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(server.await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn deliver_gives_up_after_retries() {
+        let (url, _server) = stub_http_server(vec![(500, "{}"), (500, "{}")]).await;
+        let mut config = AppConfig::default();
+        config.notifications.retries = 1;
+        config.notifications.retry_delay_secs = 0;
+        let notifier = NotifierConfig::Slack(WebhookConfig {
+            name: "slack".to_string(),
+            url,
+        });
+
+        let delivery =
+            deliver_to(&reqwest::Client::new(), &notifier, &notification(), &config).await;
+        assert!(delivery.result.is_err());
+        assert_eq!(delivery.attempts, 2);
+    }
+}
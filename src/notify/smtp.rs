@@ -0,0 +1,457 @@
+//! Minimal SMTP submission: EHLO, optional STARTTLS or implicit TLS, AUTH PLAIN,
+//! one message.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use super::SummaryNotification;
+use crate::config::{self, SmtpConfig, SmtpTls};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+pub async fn send(cfg: &SmtpConfig, n: &SummaryNotification) -> Result<()> {
+    if cfg.password_env.is_some() && cfg.tls == SmtpTls::None && !is_loopback(&cfg.host) {
+        anyhow::bail!(
+            "Refusing to send the SMTP password to {} unencrypted; set tls to \"starttls\" or \"tls\"",
+            cfg.host
+        );
+    }
+    let password = match cfg.password_env {
+        Some(ref env) => Some(config::secret(env).with_context(|| {
+            format!("SMTP password not set. Set the {env} environment variable")
+        })?),
+        None => None,
+    };
+    let auth = cfg.username.as_deref().zip(password.as_deref());
+    let message = build_message(cfg, n, Utc::now());
+
+    tokio::time::timeout(TIMEOUT, submit(cfg, auth, &message))
+        .await
+        .with_context(|| format!("SMTP to {}:{} timed out", cfg.host, cfg.port))?
+}
+
+async fn submit(cfg: &SmtpConfig, auth: Option<(&str, &str)>, message: &str) -> Result<()> {
+    let tcp = TcpStream::connect((cfg.host.as_str(), cfg.port))
+        .await
+        .with_context(|| format!("Failed to connect to SMTP server {}:{}", cfg.host, cfg.port))?;
+
+    match cfg.tls {
+        SmtpTls::None => {
+            let mut session = Session::open(tcp).await?;
+            session.ehlo().await?;
+            session.deliver(cfg, auth, message).await
+        }
+        SmtpTls::Tls => {
+            let tls = tls_connect(&cfg.host, tcp).await?;
+            let mut session = Session::open(tls).await?;
+            session.ehlo().await?;
+            session.deliver(cfg, auth, message).await
+        }
+        SmtpTls::Starttls => {
+            let mut session = Session::open(tcp).await?;
+            session.ehlo().await?;
+            session.command("STARTTLS", &[220]).await?;
+            let tls = tls_connect(&cfg.host, session.into_inner()).await?;
+            let mut session = Session::resume(tls);
+            session.ehlo().await?;
+            session.deliver(cfg, auth, message).await
+        }
+    }
+}
+
+async fn tls_connect(host: &str, tcp: TcpStream) -> Result<tokio_native_tls::TlsStream<TcpStream>> {
+    let connector = tokio_native_tls::native_tls::TlsConnector::new()?;
+    tokio_native_tls::TlsConnector::from(connector)
+        .connect(host, tcp)
+        .await
+        .with_context(|| format!("TLS handshake with {host} failed"))
+}
+
+struct Session<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    /// Connect and read the server greeting.
+    async fn open(stream: S) -> Result<Self> {
+        let mut session = Self::resume(stream);
+        session.expect(&[220]).await?;
+        Ok(session)
+    }
+
+    /// Continue on a stream whose greeting was already read (after STARTTLS).
+    fn resume(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    async fn ehlo(&mut self) -> Result<()> {
+        self.command("EHLO podcast-summarize", &[250]).await?;
+        Ok(())
+    }
+
+    async fn deliver(
+        &mut self,
+        cfg: &SmtpConfig,
+        auth: Option<(&str, &str)>,
+        message: &str,
+    ) -> Result<()> {
+        if let Some((user, password)) = auth {
+            let token = base64_encode(format!("\0{user}\0{password}").as_bytes());
+            self.command(&format!("AUTH PLAIN {token}"), &[235]).await?;
+        }
+        self.command(&format!("MAIL FROM:<{}>", address(&cfg.from)), &[250])
+            .await?;
+        for to in &cfg.to {
+            self.command(&format!("RCPT TO:<{}>", address(to)), &[250, 251])
+                .await?;
+        }
+        self.command("DATA", &[354]).await?;
+
+        let mut data = String::with_capacity(message.len() + 8);
+        for line in message.split("\r\n") {
+            // Dot-stuffing so a line with a lone "." doesn't end the message
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push_str(".\r\n");
+        self.stream.get_mut().write_all(data.as_bytes()).await?;
+        self.expect(&[250]).await?;
+
+        // The message is accepted at this point
+        let _ = self.command("QUIT", &[221]).await;
+        Ok(())
+    }
+
+    async fn command(&mut self, line: &str, expected: &[u16]) -> Result<String> {
+        self.stream
+            .get_mut()
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await?;
+        let verb = line.split(' ').next().unwrap_or(line);
+        self.expect(expected)
+            .await
+            .with_context(|| format!("SMTP {verb} failed"))
+    }
+
+    /// Read a (possibly multi-line) reply and check its code.
+    async fn expect(&mut self, expected: &[u16]) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                anyhow::bail!("SMTP server closed the connection");
+            }
+            let line = line.trim_end();
+            let code: u16 = line
+                .get(..3)
+                .and_then(|c| c.parse().ok())
+                .with_context(|| format!("Malformed SMTP reply: {line}"))?;
+            text.push_str(line.get(4..).unwrap_or(""));
+            text.push('\n');
+            if line.as_bytes().get(3) != Some(&b'-') {
+                if !expected.contains(&code) {
+                    anyhow::bail!("SMTP server replied {code}: {}", text.trim());
+                }
+                return Ok(text);
+            }
+        }
+    }
+}
+
+/// RFC 5322 message with a base64 UTF-8 body.
+fn build_message(cfg: &SmtpConfig, n: &SummaryNotification, now: DateTime<Utc>) -> String {
+    let mut body = n.summary.trim().to_string();
+    body.push_str(&format!(
+        "\n\n--\nEpisode #{} · {} · {}\n",
+        n.episode_id, n.podcast_title, n.model
+    ));
+
+    let encoded = base64_encode(body.replace('\n', "\r\n").as_bytes());
+    let wrapped: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect();
+
+    let domain = address(&cfg.from)
+        .rsplit_once('@')
+        .map_or("localhost", |(_, d)| d)
+        .to_string();
+    [
+        format!("From: {}", header_value(&cfg.from)),
+        format!("To: {}", header_value(&cfg.to.join(", "))),
+        format!("Subject: {}", encode_header(&n.title())),
+        format!("Date: {}", now.to_rfc2822()),
+        format!(
+            "Message-ID: <podcast-summarize.{}.{}@{domain}>",
+            n.episode_id,
+            now.timestamp_millis()
+        ),
+        "MIME-Version: 1.0".to_string(),
+        "Content-Type: text/plain; charset=utf-8".to_string(),
+        "Content-Transfer-Encoding: base64".to_string(),
+        String::new(),
+        wrapped.join("\r\n"),
+    ]
+    .join("\r\n")
+}
+
+/// The bare address from `Name <addr>` or `addr`.
+fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => mailbox[start + 1..end].trim(),
+        _ => mailbox.trim(),
+    }
+}
+
+/// Whether `host` names this machine, where a plain connection never leaves it.
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_matches(['[', ']'])
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// A header value with control characters replaced by spaces. A CR or LF from
+/// a feed title would otherwise start a header of its own.
+fn header_value(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+/// A header value, as an RFC 2047 encoded-word if it isn't ASCII.
+fn encode_header(value: &str) -> String {
+    let value = header_value(value);
+    if value.is_ascii() {
+        value
+    } else {
+        format!("=?UTF-8?B?{}?=", base64_encode(value.as_bytes()))
+    }
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::net::TcpListener;
+
+    fn config(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            name: "email".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: SmtpTls::None,
+            username: None,
+            password_env: None,
+            from: "Podcast Bot <bot@example.com>".to_string(),
+            to: vec!["a@example.com".to_string(), "B <b@example.com>".to_string()],
+        }
+    }
+
+    fn notification() -> SummaryNotification {
+        SummaryNotification {
+            podcast_id: 1,
+            podcast_title: "Rust Weekly".to_string(),
+            episode_id: 42,
+            episode_title: "Async".to_string(),
+            published_at: None,
+            model: "m".to_string(),
+            summary: "Summary text".to_string(),
+        }
+    }
+
+    /// A local SMTP sink accepting one message; resolves to the commands and data
+    /// it received.
+    async fn smtp_sink() -> (u16, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(socket);
+            let mut received = Vec::new();
+            stream
+                .get_mut()
+                .write_all(b"220 sink ready\r\n")
+                .await
+                .unwrap();
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                let reply: &[u8] = if line.starts_with("EHLO") {
+                    b"250-sink\r\n250 AUTH PLAIN\r\n"
+                } else if line.starts_with("AUTH") {
+                    b"235 ok\r\n"
+                } else if line == "DATA" {
+                    stream.get_mut().write_all(b"354 go\r\n").await.unwrap();
+                    let mut data = String::new();
+                    loop {
+                        let mut l = String::new();
+                        stream.read_line(&mut l).await.unwrap();
+                        if l == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&l);
+                    }
+                    received.push("DATA".to_string());
+                    received.push(data);
+                    stream.get_mut().write_all(b"250 queued\r\n").await.unwrap();
+                    continue;
+                } else if line == "QUIT" {
+                    received.push(line);
+                    stream.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                received.push(line);
+                stream.get_mut().write_all(reply).await.unwrap();
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn sends_to_local_sink() {
+        let (port, sink) = smtp_sink().await;
+        config::set_test_secret("PODSUM_TEST_SMTP_PASSWORD", "secret");
+        let mut cfg = config(port);
+        cfg.username = Some("bot".to_string());
+        cfg.password_env = Some("PODSUM_TEST_SMTP_PASSWORD".to_string());
+
+        send(&cfg, &notification()).await.unwrap();
+
+        let received = sink.await.unwrap();
+        assert_eq!(received[0], "EHLO podcast-summarize");
+        assert_eq!(
+            received[1],
+            format!("AUTH PLAIN {}", base64_encode(b"\0bot\0secret"))
+        );
+        assert_eq!(received[2], "MAIL FROM:<bot@example.com>");
+        assert_eq!(received[3], "RCPT TO:<a@example.com>");
+        assert_eq!(received[4], "RCPT TO:<b@example.com>");
+        assert_eq!(received[5], "DATA");
+        assert!(received[6].contains("Subject: Async - Rust Weekly\r\n"));
+        assert!(received[6].contains("To: a@example.com, B <b@example.com>\r\n"));
+        assert_eq!(received[7], "QUIT");
+    }
+
+    #[tokio::test]
+    async fn rejected_recipient_is_an_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket
+                .write_all(b"220 hi\r\n250 ok\r\n250 ok\r\n550 no such user\r\n")
+                .await
+                .unwrap();
+            // Hold the connection open until the client gives up
+            let mut buf = String::new();
+            let mut reader = BufReader::new(socket);
+            while reader.read_line(&mut buf).await.unwrap_or(0) > 0 {}
+        });
+
+        let err = send(&config(port), &notification()).await.unwrap_err();
+        assert!(format!("{err:#}").contains("550"));
+    }
+
+    #[test]
+    fn message_headers_and_body() {
+        let mut n = notification();
+        n.episode_title = "非同步".to_string();
+        let now = "2024-03-01T12:00:00Z".parse().unwrap();
+        let msg = build_message(&config(25), &n, now);
+        assert!(msg.starts_with("From: Podcast Bot <bot@example.com>\r\n"));
+        assert!(msg.contains(&format!(
+            "Subject: =?UTF-8?B?{}?=\r\n",
+            base64_encode("非同步 - Rust Weekly".as_bytes())
+        )));
+        assert!(msg.contains("Date: Fri, 1 Mar 2024 12:00:00 +0000\r\n"));
+        assert!(msg.contains("Message-ID: <podcast-summarize.42."));
+        assert!(msg.contains(&base64_encode(b"Summary text\r\n\r\n--")[..16]));
+    }
+
+    #[test]
+    fn feed_titles_cannot_add_headers() {
+        let mut n = notification();
+        n.episode_title = "Async\r\nBcc: victim@example.com\r\n\r\nfake body".to_string();
+        let msg = build_message(&config(25), &n, Utc::now());
+        let (headers, _) = msg.split_once("\r\n\r\n").unwrap();
+        assert!(headers.lines().all(|l| !l.starts_with("Bcc:")));
+        assert!(
+            headers
+                .contains("Subject: Async  Bcc: victim@example.com    fake body - Rust Weekly\r\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn no_password_over_plain_connections() {
+        let mut cfg = config(25);
+        cfg.host = "smtp.example.com".to_string();
+        cfg.username = Some("bot".to_string());
+        cfg.password_env = Some("PODSUM_TEST_UNSET_PASSWORD".to_string());
+        let err = send(&cfg, &notification()).await.unwrap_err();
+        assert!(err.to_string().contains("unencrypted"));
+
+        assert!(is_loopback("localhost"));
+        assert!(is_loopback("127.0.0.1"));
+        assert!(is_loopback("[::1]"));
+        assert!(!is_loopback("smtp.example.com"));
+    }
+
+    #[test]
+    fn base64_vectors() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn address_extraction() {
+        assert_eq!(address("Bot <bot@example.com>"), "bot@example.com");
+        assert_eq!(address(" bot@example.com "), "bot@example.com");
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+
+use super::SummaryNotification;
+
+/// Slack rejects `text` over 40k characters but truncates display far earlier.
+const SLACK_MAX_CHARS: usize = 3000;
+/// Discord's hard limit for `content`.
+const DISCORD_MAX_CHARS: usize = 2000;
+
+pub async fn post(client: &reqwest::Client, url: &str, payload: &Value) -> Result<()> {
+    let response = client
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        .with_context(|| format!("Failed to call webhook {url}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Webhook returned HTTP {status}: {body}");
+    }
+    Ok(())
+}
+
+/// The full notification as JSON.
+pub fn generic_payload(n: &SummaryNotification) -> Value {
+    json!({
+        "event": "summary.created",
+        "podcast": { "id": n.podcast_id, "title": n.podcast_title },
+        "episode": {
+            "id": n.episode_id,
+            "title": n.episode_title,
+            "published_at": n.published_at,
+        },
+        "summary": { "model": n.model, "content": n.summary },
+    })
+}
+
+/// Slack incoming-webhook message.
+pub fn slack_payload(n: &SummaryNotification) -> Value {
+    let text = format!("*{}*\n{}", n.title(), n.summary.trim());
+    json!({ "text": truncate(&text, SLACK_MAX_CHARS) })
+}
+
+/// Discord webhook message.
+pub fn discord_payload(n: &SummaryNotification) -> Value {
+    let text = format!("**{}**\n{}", n.title(), n.summary.trim());
+    json!({ "content": truncate(&text, DISCORD_MAX_CHARS) })
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else {
+        let cut: String = s.chars().take(max_chars - 1).collect();
+        format!("{cut}…")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(summary: &str) -> SummaryNotification {
+        SummaryNotification {
+            podcast_id: 1,
+            podcast_title: "Rust Weekly".to_string(),
+            episode_id: 42,
+            episode_title: "Async".to_string(),
+            published_at: None,
+            model: "m".to_string(),
+            summary: summary.to_string(),
+        }
+    }
+
+    #[test]
+    fn generic_payload_has_ids() {
+        let p = generic_payload(&notification("text"));
+        assert_eq!(p["event"], "summary.created");
+        assert_eq!(p["episode"]["id"], 42);
+        assert_eq!(p["podcast"]["title"], "Rust Weekly");
+        assert_eq!(p["summary"]["content"], "text");
+    }
+
+    #[test]
+    fn chat_payloads_are_formatted_and_truncated() {
+        let slack = slack_payload(&notification("text"));
+        assert_eq!(slack["text"], "*Async - Rust Weekly*\ntext");
+
+        let discord = discord_payload(&notification(&"x".repeat(5000)));
+        let content = discord["content"].as_str().unwrap();
+        assert_eq!(content.chars().count(), DISCORD_MAX_CHARS);
+        assert!(content.starts_with("**Async - Rust Weekly**\n"));
+        assert!(content.ends_with('…'));
+    }
+}
@@ -0,0 +1,192 @@
+//! Machine-readable output (`--output json|ndjson|csv`) for commands that list
+//! or show records. `table`, the default, is each command's own layout.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    #[value(alias = "jsonl")]
+    Ndjson,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn is_table(self) -> bool {
+        self == Self::Table
+    }
+}
+
+/// Print records as a JSON array, one JSON object per line, or CSV with a
+/// header row.
+pub fn print_records<T: Serialize>(format: OutputFormat, records: &[T]) -> Result<()> {
+    print!("{}", render_records(format, records)?);
+    Ok(())
+}
+
+/// Print a single record: a JSON object, one NDJSON line, or a CSV header and row.
+pub fn print_record<T: Serialize>(format: OutputFormat, record: &T) -> Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(record)?);
+        return Ok(());
+    }
+    print_records(format, std::slice::from_ref(record))
+}
+
+pub fn render_records<T: Serialize>(format: OutputFormat, records: &[T]) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(records)? + "\n"),
+        OutputFormat::Ndjson => {
+            let mut out = String::new();
+            for record in records {
+                out.push_str(&serde_json::to_string(record)?);
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        OutputFormat::Csv => {
+            let rows = records
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(render_csv(&rows))
+        }
+        OutputFormat::Table => anyhow::bail!("Table output is rendered by each command"),
+    }
+}
+
+/// CSV with one column per field seen in any record, in first-seen order.
+/// Nested values are written as JSON.
+fn render_csv(rows: &[Value]) -> String {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        if let Value::Object(map) = row {
+            for key in map.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+    }
+    if columns.is_empty() && !rows.is_empty() {
+        columns.push("value".to_string());
+    }
+
+    let mut out = String::new();
+    if columns.is_empty() {
+        return out;
+    }
+    push_csv_line(&mut out, columns.iter().map(String::as_str));
+    for row in rows {
+        let cells: Vec<String> = match row {
+            Value::Object(map) => columns
+                .iter()
+                .map(|c| map.get(c).map(csv_cell).unwrap_or_default())
+                .collect(),
+            other => vec![csv_cell(other)],
+        };
+        push_csv_line(&mut out, cells.iter().map(String::as_str));
+    }
+    out
+}
+
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn push_csv_line<'a>(out: &mut String, cells: impl Iterator<Item = &'a str>) {
+    let line: Vec<String> = cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect();
+    out.push_str(&line.join(","));
+    out.push_str("\r\n");
+}
+
+/// Nested settings as `(dotted.key, value)` pairs, for CSV output of a
+/// single document such as the config.
+pub fn flatten(value: &Value) -> Vec<(String, Value)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, v) in map {
+                    let key = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    walk(&key, v, out);
+                }
+            }
+            _ => out.push((prefix.to_string(), value.clone())),
+        }
+    }
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_formats() {
+        use clap::ValueEnum;
+        let parse = |s| OutputFormat::from_str(s, true);
+        assert_eq!(parse("JSON").unwrap(), OutputFormat::Json);
+        assert_eq!(parse("jsonl").unwrap(), OutputFormat::Ndjson);
+        assert_eq!(parse("csv").unwrap(), OutputFormat::Csv);
+        assert!(parse("table").unwrap().is_table());
+        assert!(parse("xml").is_err());
+    }
+
+    #[test]
+    fn ndjson_is_one_object_per_line() {
+        let rows = [json!({"id": 1}), json!({"id": 2})];
+        let out = render_records(OutputFormat::Ndjson, &rows).unwrap();
+        assert_eq!(out, "{\"id\":1}\n{\"id\":2}\n");
+    }
+
+    #[test]
+    fn csv_unions_columns_and_escapes() {
+        let rows = [
+            json!({"id": 1, "title": "Hello, world"}),
+            json!({"id": 2, "note": "say \"hi\"", "tags": ["a", "b"], "title": null}),
+        ];
+        let out = render_records(OutputFormat::Csv, &rows).unwrap();
+        let lines: Vec<_> = out.split("\r\n").collect();
+        assert_eq!(lines[0], "id,title,note,tags");
+        assert_eq!(lines[1], "1,\"Hello, world\",,");
+        assert_eq!(lines[2], "2,,\"say \"\"hi\"\"\",\"[\"\"a\"\",\"\"b\"\"]\"");
+    }
+
+    #[test]
+    fn csv_of_nothing_is_empty() {
+        let rows: [Value; 0] = [];
+        assert_eq!(render_records(OutputFormat::Csv, &rows).unwrap(), "");
+    }
+
+    #[test]
+    fn flatten_nested_keys() {
+        let value = json!({"general": {"max_attempts": 5, "data_dir": null}, "podcasts": {}});
+        let pairs = flatten(&value);
+        assert!(pairs.contains(&("general.max_attempts".to_string(), json!(5))));
+        assert!(pairs.contains(&("general.data_dir".to_string(), Value::Null)));
+        assert!(pairs.contains(&("podcasts".to_string(), json!({}))));
+    }
+}
@@ -0,0 +1,953 @@
+//! The sync engine. Every episode being processed has a persisted job that sits
+//! at the stage it runs next (download, transcribe, summarize); [`run`] takes due
+//! jobs through the stages in order, so a run interrupted by a crash or shutdown
+//! resumes where it stopped. Single-episode and batch syncs both queue jobs and
+//! call [`run`].
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use indicatif::ProgressStyle;
+use tokio::sync::Semaphore;
+
+use crate::config::AppConfig;
+use crate::daemon::{self, Leases};
+use crate::db::Database;
+use crate::models::{Episode, EpisodeStatus, Failure, Job, JobStage};
+use crate::progress::{self, Task, say, say_err};
+use crate::prompts::{self, SummaryOptions};
+use crate::text::count_text_length;
+use crate::transcribe::Transcript;
+use crate::{download, embed, notify, summarize, tags, transcribe};
+
+/// Which jobs a run picks up.
+#[derive(Debug, Clone)]
+pub enum Scope {
+    All,
+    Podcasts(Vec<i64>),
+    Episode(i64),
+}
+
+impl Scope {
+    fn includes(&self, episode_id: i64, podcast_id: i64) -> bool {
+        match self {
+            Self::All => true,
+            Self::Podcasts(ids) => ids.contains(&podcast_id),
+            Self::Episode(id) => episode_id == *id,
+        }
+    }
+}
+
+/// Episodes whose jobs finished or failed during a run.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub completed: Vec<i64>,
+    pub failed: Vec<(i64, String)>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Queue an episode for the pipeline, replacing any earlier job for it. The job
+/// starts after the last stage the episode got through, so a failed summary
+/// doesn't send it back through whisper.
+pub fn enqueue(
+    db: &Database,
+    episode_id: i64,
+    final_stage: JobStage,
+    force_summary: bool,
+    summary: &SummaryOptions,
+) -> Result<()> {
+    let episode = db.get_episode(episode_id)?;
+    db.enqueue_job(
+        episode_id,
+        start_stage(&episode).min(final_stage),
+        final_stage,
+        force_summary,
+        summary.style.as_deref(),
+        summary.language.as_deref(),
+    )
+}
+
+/// The stage after the episode's last successful one, falling back to an earlier
+/// stage when the files it produced are gone.
+fn start_stage(episode: &Episode) -> JobStage {
+    let have_audio = existing_file(episode.audio_path.as_deref()).is_some();
+    let have_transcript = existing_file(episode.transcript_path.as_deref()).is_some();
+    match episode.status.resume_stage() {
+        JobStage::Summarize if have_transcript => JobStage::Summarize,
+        JobStage::Summarize | JobStage::Transcribe if have_audio => JobStage::Transcribe,
+        _ => JobStage::Download,
+    }
+}
+
+/// Make failed episodes in `scope` due again once their backoff has passed,
+/// including those sync no longer retries on its own. Returns each failed
+/// episode with `None` if it was queued, or the time its backoff ends.
+pub fn retry_failed(
+    db: &Database,
+    scope: &Scope,
+    config: &AppConfig,
+) -> Result<Vec<(Episode, Option<DateTime<Utc>>)>> {
+    let now = Utc::now();
+    let mut plan = Vec::new();
+    for episode in db.list_episodes_by_status("failed")? {
+        if !scope.includes(episode.id, episode.podcast_id) {
+            continue;
+        }
+        match db.get_job(episode.id)? {
+            Some(job) => {
+                let ready_at = job
+                    .last_attempt_at
+                    .map(|t| t + config.retry_delay(job.attempts))
+                    .unwrap_or(now);
+                if ready_at <= now {
+                    db.schedule_job(job.id, now)?;
+                    plan.push((episode, None));
+                } else {
+                    plan.push((episode, Some(ready_at)));
+                }
+            }
+            // Failed before jobs were kept; starts after its last completed stage
+            None => {
+                enqueue(
+                    db,
+                    episode.id,
+                    JobStage::Summarize,
+                    false,
+                    &SummaryOptions::default(),
+                )?;
+                plan.push((episode, None));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// Queue an episode that has no job yet, bringing it back first if it was
+/// skipped. Returns false if it was already queued.
+pub fn queue_episode(db: &Database, episode: &Episode) -> Result<bool> {
+    if db.get_job(episode.id)?.is_some() {
+        return Ok(false);
+    }
+    if matches!(episode.status, EpisodeStatus::Skipped { .. }) {
+        db.update_episode_status(episode.id, &settled_status(db, episode)?)?;
+    }
+    enqueue(
+        db,
+        episode.id,
+        JobStage::Summarize,
+        false,
+        &SummaryOptions::default(),
+    )?;
+    Ok(true)
+}
+
+/// Take an episode out of the queue and mark it skipped. Returns false for
+/// summarized or already skipped episodes, which are left as they are.
+pub fn skip_episode(db: &Database, episode: &Episode) -> Result<bool> {
+    if matches!(
+        episode.status,
+        EpisodeStatus::Summarized | EpisodeStatus::Skipped { .. }
+    ) {
+        return Ok(false);
+    }
+    db.delete_job(episode.id)?;
+    db.update_episode_status(episode.id, &EpisodeStatus::Skipped { reason: None })?;
+    Ok(true)
+}
+
+/// Bring back a skipped episode and queue it. Returns false if it wasn't skipped.
+pub fn unskip_episode(db: &Database, episode: &Episode) -> Result<bool> {
+    if !matches!(episode.status, EpisodeStatus::Skipped { .. }) {
+        return Ok(false);
+    }
+    db.update_episode_status(episode.id, &settled_status(db, episode)?)?;
+    enqueue(
+        db,
+        episode.id,
+        JobStage::Summarize,
+        false,
+        &SummaryOptions::default(),
+    )?;
+    Ok(true)
+}
+
+/// Run every due job in `scope` through its remaining stages. Episodes claimed by
+/// another process are left to it.
+pub async fn run(
+    db: &Database,
+    client: &reqwest::Client,
+    scope: &Scope,
+    config: &AppConfig,
+) -> Result<Report> {
+    let mut leases = Leases::new(db);
+    let mut report = Report::default();
+
+    for stage in JobStage::ALL {
+        let mut jobs = Vec::new();
+        for job in db.list_runnable_jobs(stage)? {
+            if !scope.includes(job.episode_id, job.podcast_id) {
+                continue;
+            }
+            if leases.claim(job.episode_id)? {
+                jobs.push(job);
+            } else {
+                say!(
+                    "  Skipping episode #{}: claimed by {}",
+                    job.episode_id,
+                    leases.holder(job.episode_id)
+                );
+            }
+        }
+        if jobs.is_empty() {
+            continue;
+        }
+
+        match stage {
+            JobStage::Download => {
+                download_stage(db, client, &jobs, &mut leases, &mut report, config).await?
+            }
+            JobStage::Transcribe => {
+                transcribe_stage(db, client, &jobs, &mut leases, &mut report, config).await?
+            }
+            JobStage::Summarize => {
+                summarize_stage(db, client, &jobs, &mut leases, &mut report, config).await?
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Move a job past a stage that succeeded: on to the next one, or done.
+fn stage_done(db: &Database, job: &Job, report: &mut Report, config: &AppConfig) -> Result<()> {
+    match job.stage.next() {
+        Some(next) if job.stage != job.final_stage => db.advance_job(job.id, next)?,
+        _ => {
+            db.complete_job(job.id)?;
+            clear_failure(db, job.episode_id)?;
+            report.completed.push(job.episode_id);
+            if job.final_stage == JobStage::Summarize && config.general.auto_cleanup_audio {
+                remove_audio(db, job.episode_id)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Record a stage failure on the job and the episode, keeping the stage before
+/// it as the episode's last completed one. Transient failures are
+/// retried after a backoff until `general.max_attempts`; others wait for
+/// `sync --retry-failed`.
+fn stage_failed(
+    db: &Database,
+    job: &Job,
+    error: &anyhow::Error,
+    report: &mut Report,
+    config: &AppConfig,
+) -> Result<()> {
+    let failure = Failure::classify(job.stage, &format!("{error:#}"));
+    let attempts = job.attempts + 1;
+    let retry_at = (failure.kind.is_transient() && attempts < config.general.max_attempts as i64)
+        .then(|| Utc::now() + config.retry_delay(attempts));
+
+    let status = EpisodeStatus::Failed {
+        failure: failure.clone(),
+        completed: job.stage.prev(),
+    };
+    db.update_episode_status(job.episode_id, &status)?;
+    db.fail_job(job.id, &failure.to_string(), retry_at)?;
+    match retry_at {
+        Some(at) => say!(
+            "    Will retry after {}.",
+            at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+        ),
+        None => say!("    Not retrying automatically; use `sync --retry-failed`."),
+    }
+    report.failed.push((job.episode_id, failure.to_string()));
+    Ok(())
+}
+
+/// A retried job can finish without redoing the stage that failed (say, when a
+/// transcript already exists); don't leave the episode marked failed.
+fn clear_failure(db: &Database, episode_id: i64) -> Result<()> {
+    let episode = db.get_episode(episode_id)?;
+    if !matches!(episode.status, EpisodeStatus::Failed { .. }) {
+        return Ok(());
+    }
+    db.update_episode_status(episode_id, &settled_status(db, &episode)?)
+}
+
+/// The status an episode's stored results put it in, for episodes leaving the
+/// failed or skipped state.
+pub fn settled_status(db: &Database, episode: &Episode) -> Result<EpisodeStatus> {
+    Ok(if db.get_summary_by_episode(episode.id)?.is_some() {
+        EpisodeStatus::Summarized
+    } else if episode.transcript_path.is_some() {
+        EpisodeStatus::Transcribed
+    } else if episode.audio_path.is_some() {
+        EpisodeStatus::Downloaded
+    } else {
+        EpisodeStatus::New
+    })
+}
+
+/// Clear the old transcript so it gets regenerated. Earlier summaries are kept
+/// as previous versions.
+pub fn clear_episode_results(db: &Database, ep_id: i64) -> Result<()> {
+    let episode = db.get_episode(ep_id)?;
+
+    // Delete transcript file
+    if let Some(ref path) = episode.transcript_path {
+        let p = std::path::Path::new(path);
+        if p.exists() {
+            std::fs::remove_file(p)?;
+        }
+    }
+    db.clear_episode_transcript(ep_id)?;
+
+    progress::say!("  Cleared old transcript for episode #{ep_id}.");
+    Ok(())
+}
+
+fn remove_audio(db: &Database, episode_id: i64) -> Result<()> {
+    if let Some(path) = db.get_episode(episode_id)?.audio_path {
+        let path = std::path::Path::new(&path);
+        if path.exists() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    Ok(())
+}
+
+fn existing_file(path: Option<&str>) -> Option<PathBuf> {
+    path.map(PathBuf::from).filter(|p| p.exists())
+}
+
+async fn download_stage(
+    db: &Database,
+    client: &reqwest::Client,
+    jobs: &[Job],
+    leases: &mut Leases<'_>,
+    report: &mut Report,
+    config: &AppConfig,
+) -> Result<()> {
+    let audio_dir = config.audio_dir()?;
+    let semaphore = Arc::new(Semaphore::new(config.general.max_concurrent_downloads));
+
+    let mut pending = Vec::new();
+    for job in jobs {
+        let episode = db.get_episode(job.episode_id)?;
+        // Audio is only needed to transcribe; skip it when a transcript exists
+        let have_transcript = job.final_stage != JobStage::Download
+            && existing_file(episode.transcript_path.as_deref()).is_some();
+        if have_transcript || existing_file(episode.audio_path.as_deref()).is_some() {
+            stage_done(db, job, report, config)?;
+        } else {
+            pending.push((job, episode));
+        }
+    }
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    say!("\nDownloading {} episode(s)...", pending.len());
+
+    let mut download_tasks = Vec::new();
+    for (job, episode) in pending {
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let audio_dir = audio_dir.clone();
+
+        download_tasks.push((
+            job,
+            episode.title.clone(),
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                download::download_episode(
+                    &client,
+                    &episode.audio_url,
+                    &audio_dir,
+                    episode.podcast_id,
+                )
+                .await
+            }),
+        ));
+    }
+
+    for (job, title, task) in download_tasks {
+        // Long downloads would otherwise let the leases go stale
+        while !task.is_finished() {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            leases.heartbeat();
+        }
+        let result = task.await?;
+        match result {
+            Ok(path) => {
+                db.update_episode_audio_path(job.episode_id, &path.to_string_lossy())?;
+                say!("  Downloaded: {title}");
+                stage_done(db, job, report, config)?;
+            }
+            Err(e) => {
+                say_err!("  Failed to download \"{title}\": {e}");
+                stage_failed(db, job, &e, report, config)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn transcribe_stage(
+    db: &Database,
+    client: &reqwest::Client,
+    jobs: &[Job],
+    leases: &mut Leases<'_>,
+    report: &mut Report,
+    config: &AppConfig,
+) -> Result<()> {
+    let bar_style = ProgressStyle::default_bar()
+        .template("  [{bar:30.cyan/dim}] {pos}% {msg}")
+        .unwrap()
+        .progress_chars("##-");
+
+    let mut announced = false;
+    for (i, job) in jobs.iter().enumerate() {
+        let episode = db.get_episode(job.episode_id)?;
+        if existing_file(episode.transcript_path.as_deref()).is_some() {
+            stage_done(db, job, report, config)?;
+            continue;
+        }
+        // Whisper can't be interrupted mid-file; stop between episodes instead
+        if daemon::shutdown_requested() {
+            say!(
+                "  Shutdown requested: {} episode(s) stay queued for transcription.",
+                jobs.len() - i
+            );
+            break;
+        }
+        if !announced {
+            say!("\nTranscribing {} episode(s)...", jobs.len() - i);
+            announced = true;
+        }
+
+        let pb = Task::percent(
+            bar_style.clone(),
+            format!("Transcribing: {}", episode.title),
+        );
+
+        let result = match existing_file(episode.audio_path.as_deref()) {
+            Some(audio_path) => run_whisper(&audio_path, config, &pb, leases).await,
+            None => Err(anyhow::anyhow!("audio file is missing")),
+        };
+        match result {
+            Ok(transcript) => {
+                save_transcript(db, &episode, &transcript, config)?;
+
+                let word_count = count_text_length(&transcript.text);
+                pb.finish(format!(
+                    "Transcribed: {} ({} words)",
+                    episode.title, word_count,
+                ));
+                embed_if_enabled(db, client, job.episode_id, &transcript, config).await;
+                stage_done(db, job, report, config)?;
+            }
+            Err(e) => {
+                pb.finish(format!("Failed: {}", episode.title));
+                say_err!("    Error transcribing: {e}");
+                stage_failed(db, job, &e, report, config)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn summarize_stage(
+    db: &Database,
+    client: &reqwest::Client,
+    jobs: &[Job],
+    leases: &mut Leases<'_>,
+    report: &mut Report,
+    config: &AppConfig,
+) -> Result<()> {
+    let mut todo = Vec::new();
+    for job in jobs {
+        if !job.force_summary && db.get_summary_by_episode(job.episode_id)?.is_some() {
+            say!(
+                "  Episode #{} already has a summary (use --resummarize for a new version).",
+                job.episode_id
+            );
+            stage_done(db, job, report, config)?;
+        } else {
+            todo.push(job);
+        }
+    }
+    if todo.is_empty() {
+        return Ok(());
+    }
+
+    let api_key = match config.api_key() {
+        Ok(key) => key,
+        Err(e) => {
+            say_err!("\nSkipping summarization: {e}");
+            say!("Transcripts are saved; summaries will be made on the next sync.");
+            return Ok(());
+        }
+    };
+
+    say!("\nSummarizing {} episode(s)...", todo.len());
+
+    let spinner_style = ProgressStyle::default_spinner()
+        .template("  {spinner} {msg}")
+        .unwrap();
+
+    for job in todo {
+        if daemon::shutdown_requested() {
+            break;
+        }
+        leases.heartbeat();
+        let episode = db.get_episode(job.episode_id)?;
+
+        let pb = Task::spinner(
+            spinner_style.clone(),
+            format!("Summarizing: {}", episode.title),
+        );
+
+        match summarize_job(db, client, &api_key, job, &episode, config).await {
+            Ok(()) => {
+                pb.finish(format!("Summarized: {} [done]", episode.title));
+                stage_done(db, job, report, config)?;
+            }
+            Err(e) => {
+                pb.finish(format!("Summary failed: {}", episode.title));
+                say_err!("    Error: {e}");
+                stage_failed(db, job, &e, report, config)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn summarize_job(
+    db: &Database,
+    client: &reqwest::Client,
+    api_key: &str,
+    job: &Job,
+    episode: &Episode,
+    config: &AppConfig,
+) -> Result<()> {
+    let path =
+        existing_file(episode.transcript_path.as_deref()).context("transcript file is missing")?;
+    let transcript = transcribe::load_transcript(&path)?;
+    let podcast = db.get_podcast(episode.podcast_id)?;
+    let options = SummaryOptions {
+        style: job.style.clone(),
+        language: job.language.clone(),
+    };
+    let system_prompt = prompts::prepare_system_prompt(&options, &podcast, episode, config)?;
+    summarize_and_store(
+        db,
+        client,
+        api_key,
+        episode.id,
+        &transcript.text,
+        &system_prompt,
+        config,
+    )
+    .await
+}
+
+/// Run whisper on a blocking thread, showing progress on `pb` and renewing
+/// `leases` while it works.
+async fn run_whisper(
+    audio_path: &std::path::Path,
+    config: &AppConfig,
+    pb: &Task,
+    leases: &mut daemon::Leases<'_>,
+) -> Result<Transcript> {
+    let progress = progress::transcription_counter();
+    let progress_clone = progress.clone();
+
+    let audio_path = audio_path.to_path_buf();
+    let config = config.clone();
+    let handle = tokio::task::spawn_blocking(move || {
+        transcribe::transcribe(&audio_path, &config, progress_clone)
+    });
+
+    // Poll progress until transcription finishes
+    loop {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let pct = progress.load(std::sync::atomic::Ordering::Relaxed);
+        pb.set_position(pct.max(0) as u64);
+        leases.heartbeat();
+        if handle.is_finished() {
+            break;
+        }
+    }
+    handle.await?
+}
+
+/// Write a transcript (and its segment timings) to the transcripts directory and
+/// record it on the episode.
+fn save_transcript(
+    db: &Database,
+    episode: &Episode,
+    transcript: &Transcript,
+    config: &AppConfig,
+) -> Result<()> {
+    let transcript_file = config
+        .transcript_dir()?
+        .join(episode.podcast_id.to_string())
+        .join(format!("{}.txt", episode.id));
+    transcribe::save_transcript(&transcript_file, transcript)?;
+    db.update_episode_transcript_path(episode.id, &transcript_file.to_string_lossy())?;
+    db.index_transcript(episode.id, &transcript.text)?;
+    Ok(())
+}
+
+/// Chunk and embed a transcript, replacing its earlier chunks. Returns the number
+/// of chunks stored.
+pub async fn embed_transcript(
+    db: &Database,
+    client: &reqwest::Client,
+    ep_id: i64,
+    transcript: &Transcript,
+    config: &AppConfig,
+) -> Result<usize> {
+    let e = &config.embeddings;
+    let chunks = if transcript.segments.is_empty() {
+        embed::chunk_text(&transcript.text, e.chunk_chars)
+    } else {
+        embed::chunk_segments(&transcript.segments, e.chunk_chars)
+    };
+    let inputs: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+    let vectors = embed::embed(
+        client,
+        e.api_base_url(config),
+        e.api_key(config).as_deref(),
+        &e.model,
+        &inputs,
+    )
+    .await?;
+
+    let rows: Vec<_> = chunks.into_iter().zip(vectors).collect();
+    db.replace_transcript_chunks(ep_id, &e.model, &rows)?;
+    Ok(rows.len())
+}
+
+/// Embedding is an add-on to sync: a failure is reported but leaves the episode
+/// as it is.
+async fn embed_if_enabled(
+    db: &Database,
+    client: &reqwest::Client,
+    ep_id: i64,
+    transcript: &Transcript,
+    config: &AppConfig,
+) {
+    if !config.embeddings.enabled {
+        return;
+    }
+    if let Err(e) = embed_transcript(db, client, ep_id, transcript, config).await {
+        say_err!("  Embedding failed for episode #{ep_id}: {e}");
+    }
+}
+
+/// Generate a summary with the prepared prompt and store it with its typed sections.
+/// JSON mode is only used for the default style, since custom templates define their
+/// own output format.
+async fn summarize_and_store(
+    db: &Database,
+    client: &reqwest::Client,
+    api_key: &str,
+    ep_id: i64,
+    transcript: &str,
+    system_prompt: &prompts::SystemPrompt,
+    config: &AppConfig,
+) -> Result<()> {
+    let s = &config.summarization;
+    let result = if s.json_mode && system_prompt.style == prompts::DEFAULT_STYLE {
+        summarize::generate_structured_summary(
+            client,
+            &s.api_base_url,
+            api_key,
+            &s.model,
+            s.max_tokens,
+            &system_prompt.text,
+            transcript,
+        )
+        .await?
+    } else {
+        summarize::generate_summary(
+            client,
+            &s.api_base_url,
+            api_key,
+            &s.model,
+            s.max_tokens,
+            &system_prompt.text,
+            transcript,
+        )
+        .await?
+    };
+
+    let (content, sections) = system_prompt.localize(&result.content, result.structured)?;
+
+    // The episode only counts as summarized once its sections are in too
+    db.transaction(|db| {
+        let summary_id = db.insert_summary(
+            ep_id,
+            &content,
+            &result.model,
+            result.prompt_tokens,
+            result.output_tokens,
+            Some(&system_prompt.style),
+            Some(&system_prompt.hash),
+            system_prompt.language_code(),
+        )?;
+        if let Some(sections) = &sections {
+            db.insert_summary_sections(summary_id, sections)?;
+        }
+        Ok(())
+    })?;
+    if let Some(sections) = sections
+        && config.general.auto_tag_topics
+    {
+        let added = tags::auto_tag(db, ep_id, &sections.topics)?;
+        if !added.is_empty() {
+            tracing::info!("Episode {ep_id} tagged from topics: {}", added.join(", "));
+        }
+    }
+
+    notify_summary(db, client, ep_id, &result.model, &content, config).await;
+    Ok(())
+}
+
+/// Send a new summary to the podcast's notifiers. Failures are printed as
+/// warnings only; the summary is already stored and the episode stays done.
+async fn notify_summary(
+    db: &Database,
+    client: &reqwest::Client,
+    ep_id: i64,
+    model: &str,
+    summary: &str,
+    config: &AppConfig,
+) {
+    if config.notifiers.is_empty() {
+        return;
+    }
+    let loaded = db
+        .get_episode(ep_id)
+        .and_then(|episode| Ok((db.get_podcast(episode.podcast_id)?, episode)));
+    let (podcast, episode) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            say_err!("    Warning: could not load episode {ep_id} for notifications: {e}");
+            return;
+        }
+    };
+    let notification = notify::SummaryNotification::new(&podcast, &episode, model, summary);
+    let deliveries = match notify::deliver(client, &podcast, &notification, config).await {
+        Ok(deliveries) => deliveries,
+        Err(e) => {
+            say_err!("    Warning: no notifications sent: {e:#}");
+            return;
+        }
+    };
+    for delivery in deliveries {
+        if let Err(e) = delivery.result {
+            say_err!(
+                "    Warning: notifier {} failed after {} attempt(s): {e:#}",
+                delivery.notifier,
+                delivery.attempts
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FailureKind;
+    use crate::testutil::stub_http_server;
+
+    /// A config keeping its data in a fresh temporary directory.
+    fn test_config(name: &str) -> (AppConfig, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("podsum-pipeline-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = AppConfig::default();
+        config.general.data_dir = Some(dir.to_string_lossy().to_string());
+        (config, dir)
+    }
+
+    fn test_episode(db: &Database, audio_url: &str) -> Episode {
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        let id = db
+            .insert_episode(p.id, "g1", "Ep", None, audio_url, None, None)
+            .unwrap();
+        db.get_episode(id).unwrap()
+    }
+
+    /// Queue the episode the way `sync -e` does and run it.
+    async fn sync_episode(db: &Database, episode_id: i64, config: &AppConfig) -> Report {
+        enqueue(
+            db,
+            episode_id,
+            JobStage::Summarize,
+            false,
+            &SummaryOptions::default(),
+        )
+        .unwrap();
+        run(
+            db,
+            &reqwest::Client::new(),
+            &Scope::Episode(episode_id),
+            config,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn failure_of(db: &Database, episode_id: i64) -> (FailureKind, Option<JobStage>) {
+        match db.get_episode(episode_id).unwrap().status {
+            EpisodeStatus::Failed { failure, completed } => (failure.kind, completed),
+            status => panic!("episode not failed: {status:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn download_failure_starts_over() {
+        let db = Database::open_in_memory().unwrap();
+        let (config, dir) = test_config("download");
+        // Nothing listens on a port freed right after binding
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let ep = test_episode(&db, &format!("http://127.0.0.1:{port}/ep.mp3"));
+
+        let report = sync_episode(&db, ep.id, &config).await;
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(failure_of(&db, ep.id), (FailureKind::Download, None));
+        let job = db.get_job(ep.id).unwrap().unwrap();
+        assert_eq!(job.stage, JobStage::Download);
+        assert!(job.next_run_at.is_some());
+
+        enqueue(
+            &db,
+            ep.id,
+            JobStage::Summarize,
+            false,
+            &SummaryOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            db.get_job(ep.id).unwrap().unwrap().stage,
+            JobStage::Download
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn transcribe_failure_keeps_the_download() {
+        let db = Database::open_in_memory().unwrap();
+        let (config, dir) = test_config("transcribe");
+        let ep = test_episode(&db, "https://ex.com/ep.mp3");
+        let audio = dir.join("audio").join("ep.mp3");
+        std::fs::create_dir_all(audio.parent().unwrap()).unwrap();
+        std::fs::write(&audio, b"not audio").unwrap();
+        db.update_episode_audio_path(ep.id, &audio.to_string_lossy())
+            .unwrap();
+        // Stand-in model so whisper fails on the audio, not on a model download
+        let model = dir
+            .join("models")
+            .join(format!("ggml-{}.bin", config.transcription.whisper_model));
+        std::fs::create_dir_all(model.parent().unwrap()).unwrap();
+        std::fs::write(&model, b"").unwrap();
+
+        let report = sync_episode(&db, ep.id, &config).await;
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(
+            failure_of(&db, ep.id),
+            (FailureKind::Decode, Some(JobStage::Download))
+        );
+        let job = db.get_job(ep.id).unwrap().unwrap();
+        assert_eq!(job.stage, JobStage::Transcribe);
+        // Undecodable audio isn't retried on its own
+        assert!(job.next_run_at.is_none());
+
+        let summary = SummaryOptions::default();
+        enqueue(&db, ep.id, JobStage::Summarize, false, &summary).unwrap();
+        assert_eq!(
+            db.get_job(ep.id).unwrap().unwrap().stage,
+            JobStage::Transcribe
+        );
+
+        // Without the audio the episode is downloaded again
+        std::fs::remove_file(&audio).unwrap();
+        enqueue(&db, ep.id, JobStage::Summarize, false, &summary).unwrap();
+        assert_eq!(
+            db.get_job(ep.id).unwrap().unwrap().stage,
+            JobStage::Download
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn summarize_failure_resumes_without_transcribing() {
+        let db = Database::open_in_memory().unwrap();
+        let (mut config, dir) = test_config("summarize");
+        crate::config::set_test_secret("PODSUM_TEST_PIPELINE_KEY", "key");
+        config.summarization.api_key_env = "PODSUM_TEST_PIPELINE_KEY".to_string();
+        let ep = test_episode(&db, "https://ex.com/ep.mp3");
+        let transcript = dir.join("transcripts").join("ep.txt");
+        transcribe::save_transcript(
+            &transcript,
+            &Transcript {
+                text: "We talked about tides.".to_string(),
+                segments: Vec::new(),
+            },
+        )
+        .unwrap();
+        db.update_episode_transcript_path(ep.id, &transcript.to_string_lossy())
+            .unwrap();
+
+        let (url, _server) = stub_http_server(vec![(500, r#"{"error":"overloaded"}"#)]).await;
+        config.summarization.api_base_url = url;
+        let report = sync_episode(&db, ep.id, &config).await;
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(
+            failure_of(&db, ep.id),
+            (FailureKind::Summarize, Some(JobStage::Transcribe))
+        );
+        let job = db.get_job(ep.id).unwrap().unwrap();
+        assert_eq!(job.stage, JobStage::Summarize);
+        assert!(job.next_run_at.is_some());
+
+        let (url, server) = stub_http_server(vec![(
+            200,
+            r#"{"choices":[{"message":{"content":"Tides, mostly."}}]}"#,
+        )])
+        .await;
+        config.summarization.api_base_url = url;
+        let report = sync_episode(&db, ep.id, &config).await;
+        assert_eq!(report.completed, vec![ep.id]);
+        assert_eq!(server.await.unwrap().len(), 1);
+        assert_eq!(
+            db.get_episode(ep.id).unwrap().status,
+            EpisodeStatus::Summarized
+        );
+        assert!(db.get_job(ep.id).unwrap().is_none());
+        // Neither audio nor a whisper model was ever needed
+        assert!(!dir.join("audio").exists());
+        assert!(!dir.join("models").exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
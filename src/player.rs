@@ -0,0 +1,258 @@
+//! Episode playback: decoded audio goes to a pluggable [`Sink`] while the
+//! transcript follows along in time.
+
+use std::io::Write;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::audio::ChunkedAudioDecoder;
+use crate::models::TranscriptSegment;
+
+/// Audio decoded per step.
+const CHUNK_SECS: u32 = 1;
+/// Slices written to the sink per second; the transcript catches up before each.
+const SLICES_PER_SEC: u32 = 4;
+
+/// Players tried in order when none is configured. `{rate}` is replaced with
+/// the sample rate; samples arrive on stdin as mono 32-bit little-endian floats.
+const KNOWN_PLAYERS: &[&str] = &[
+    "ffplay -nodisp -autoexit -loglevel error -f f32le -ar {rate} -ch_layout mono -i -",
+    "mpv --really-quiet --no-video --demuxer=rawaudio --demuxer-rawaudio-format=floatle --demuxer-rawaudio-rate={rate} --demuxer-rawaudio-channels=1 -",
+    "play -q -t f32 -r {rate} -c 1 -",
+    "aplay -q -t raw -f FLOAT_LE -r {rate} -c 1",
+];
+
+/// Where decoded audio goes: mono f32 samples at the rate the sink was made for.
+pub trait Sink {
+    /// Queue samples for playback; may block until the output has room.
+    fn write(&mut self, samples: &[f32]) -> Result<()>;
+
+    /// Wait for queued audio to finish playing.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Discards audio, optionally taking as long as playing it would.
+pub struct NullSink {
+    rate: u32,
+    realtime: bool,
+    written: usize,
+}
+
+impl NullSink {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate,
+            realtime: false,
+            written: 0,
+        }
+    }
+
+    /// A sink that keeps time, so the transcript still follows along.
+    pub fn realtime(rate: u32) -> Self {
+        Self {
+            realtime: true,
+            ..Self::new(rate)
+        }
+    }
+
+    /// Samples written so far.
+    #[cfg(test)]
+    pub fn written(&self) -> usize {
+        self.written
+    }
+}
+
+impl Sink for NullSink {
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        self.written += samples.len();
+        if self.realtime {
+            std::thread::sleep(Duration::from_secs_f64(
+                samples.len() as f64 / self.rate as f64,
+            ));
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Pipes raw samples into an external player's stdin.
+pub struct CommandSink {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl CommandSink {
+    /// Start `template` (split on whitespace, `{rate}` filled in) reading from stdin.
+    pub fn spawn(template: &str, rate: u32) -> Result<Self> {
+        let command = template.replace("{rate}", &rate.to_string());
+        let mut args = command.split_whitespace();
+        let program = args.next().context("Empty player command")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start player: {program}"))?;
+        let stdin = child.stdin.take();
+        Ok(Self { child, stdin })
+    }
+}
+
+impl Sink for CommandSink {
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        self.stdin
+            .as_mut()
+            .context("Player input already closed")?
+            .write_all(&bytes)
+            .context("Player stopped")
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // Closing stdin tells the player the stream is over
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            anyhow::bail!("Player exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CommandSink {
+    fn drop(&mut self) {
+        // Stopped early: don't leave the player running
+        if self.stdin.take().is_some() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// The configured player command, or the first known player found on PATH.
+pub fn find_player(configured: Option<&str>) -> Option<String> {
+    if let Some(command) = configured {
+        return Some(command.to_string());
+    }
+    let path = std::env::var_os("PATH")?;
+    KNOWN_PLAYERS
+        .iter()
+        .find(|template| {
+            let program = template.split_whitespace().next().unwrap_or_default();
+            std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+        })
+        .map(|template| template.to_string())
+}
+
+/// Play from `position` (where the decoder stands, in seconds) to the end,
+/// calling `on_segment` with each transcript segment as playback reaches it,
+/// starting with the one playing at `position`. Returns the end position.
+pub fn play(
+    decoder: &mut ChunkedAudioDecoder,
+    sink: &mut dyn Sink,
+    position: f64,
+    segments: &[TranscriptSegment],
+    mut on_segment: impl FnMut(&TranscriptSegment),
+) -> Result<f64> {
+    let rate = decoder.output_rate();
+    let slice_len = (rate / SLICES_PER_SEC).max(1) as usize;
+    let mut next = segments.partition_point(|s| s.end_secs <= position);
+    let mut position = position;
+    while let Some(chunk) = decoder.next_chunk(CHUNK_SECS)? {
+        for slice in chunk.chunks(slice_len) {
+            while let Some(segment) = segments.get(next)
+                && segment.start_secs <= position
+            {
+                on_segment(segment);
+                next += 1;
+            }
+            sink.write(slice)?;
+            position += slice.len() as f64 / rate as f64;
+        }
+    }
+    sink.finish()?;
+    Ok(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mono 16-bit PCM WAV file of `secs` seconds of a 440Hz tone.
+    fn write_wav(path: &std::path::Path, rate: u32, secs: u32) {
+        let samples: Vec<i16> = (0..rate * secs)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                ((t * 440.0 * std::f32::consts::TAU).sin() * 8000.0) as i16
+            })
+            .collect();
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&rate.to_le_bytes());
+        wav.extend_from_slice(&(rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for s in samples {
+            wav.extend_from_slice(&s.to_le_bytes());
+        }
+        std::fs::write(path, wav).unwrap();
+    }
+
+    fn segment(start: f64, end: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_secs: start,
+            end_secs: end,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn plays_from_timestamp_following_transcript() {
+        let path = std::env::temp_dir().join(format!("podsum-play-{}.wav", std::process::id()));
+        write_wav(&path, 8000, 3);
+        let segments = vec![
+            segment(0.0, 0.8, "a"),
+            segment(0.8, 1.5, "b"),
+            segment(1.5, 2.5, "c"),
+            segment(2.5, 3.0, "d"),
+        ];
+
+        let mut decoder = ChunkedAudioDecoder::open(&path).unwrap();
+        decoder.set_output_rate(decoder.source_rate());
+        let start = decoder.seek(1.0).unwrap();
+        assert!((start - 1.0).abs() < 0.001, "seeked to {start}");
+
+        let mut sink = NullSink::new(8000);
+        let mut shown = Vec::new();
+        let end = play(&mut decoder, &mut sink, start, &segments, |s| {
+            shown.push(s.text.clone())
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(shown, vec!["b", "c", "d"]);
+        assert!((end - 3.0).abs() < 0.01, "ended at {end}");
+        let played = sink.written() as f64 / 8000.0;
+        assert!((played - (3.0 - start)).abs() < 0.01, "played {played}s");
+    }
+
+    #[test]
+    fn configured_player_wins() {
+        assert_eq!(
+            find_player(Some("my-player {rate}")).as_deref(),
+            Some("my-player {rate}")
+        );
+    }
+}
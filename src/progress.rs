@@ -0,0 +1,213 @@
+//! Where a sync reports what it's doing. Normally lines are printed and each
+//! piece of work draws an indicatif bar; a caller that owns the screen (the
+//! TUI) installs a [`Monitor`] instead, which keeps the lines and the current
+//! activity for it to draw, along with whisper's progress counter.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+static MONITOR: OnceLock<Arc<Monitor>> = OnceLock::new();
+
+/// Log lines a monitor keeps.
+const MAX_LINES: usize = 500;
+
+/// Sync output collected for display instead of being printed.
+#[derive(Default)]
+pub struct Monitor {
+    lines: Mutex<VecDeque<LogLine>>,
+    activity: Mutex<Option<Activity>>,
+    /// Percent done of the transcription in progress, updated by
+    /// `transcribe::transcribe`.
+    transcription: Arc<AtomicI32>,
+}
+
+/// A line of sync output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub text: String,
+    pub error: bool,
+}
+
+/// The piece of work a sync is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    pub message: String,
+    /// Whether it reports a percentage (see [`Monitor::percent`]).
+    pub measured: bool,
+}
+
+impl Monitor {
+    /// The most recent lines, oldest first.
+    pub fn lines(&self) -> Vec<LogLine> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn activity(&self) -> Option<Activity> {
+        self.activity.lock().unwrap().clone()
+    }
+
+    /// Percent done of the current transcription.
+    pub fn percent(&self) -> u16 {
+        self.transcription.load(Ordering::Relaxed).clamp(0, 100) as u16
+    }
+
+    fn push(&self, text: &str, error: bool) {
+        let mut lines = self.lines.lock().unwrap();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            lines.push_back(LogLine {
+                text: line.trim_end().to_string(),
+                error,
+            });
+        }
+        while lines.len() > MAX_LINES {
+            lines.pop_front();
+        }
+    }
+
+    fn set_activity(&self, activity: Option<Activity>) {
+        *self.activity.lock().unwrap() = activity;
+    }
+}
+
+/// Send sync output to `monitor` for the rest of the process. Only the first
+/// call has an effect.
+pub fn install(monitor: Arc<Monitor>) {
+    let _ = MONITOR.set(monitor);
+}
+
+fn monitor() -> Option<&'static Arc<Monitor>> {
+    MONITOR.get()
+}
+
+/// Print a line of sync output, or hand it to the monitor.
+pub fn write_line(text: String, error: bool) {
+    match monitor() {
+        Some(monitor) => monitor.push(&text, error),
+        None if error => eprintln!("{text}"),
+        None => println!("{text}"),
+    }
+}
+
+/// `println!` for sync output.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        $crate::progress::write_line(format!($($arg)*), false)
+    };
+}
+
+/// `eprintln!` for sync output.
+macro_rules! say_err {
+    ($($arg:tt)*) => {
+        $crate::progress::write_line(format!($($arg)*), true)
+    };
+}
+
+pub(crate) use {say, say_err};
+
+/// A progress bar of `len` steps, hidden while a monitor is installed.
+pub fn bar(len: u64) -> ProgressBar {
+    match monitor() {
+        Some(_) => ProgressBar::hidden(),
+        None => ProgressBar::new(len),
+    }
+}
+
+/// The counter to hand `transcribe::transcribe`: the monitor's, reset, or a
+/// fresh one.
+pub fn transcription_counter() -> Arc<AtomicI32> {
+    match monitor() {
+        Some(monitor) => {
+            monitor.transcription.store(0, Ordering::Relaxed);
+            monitor.transcription.clone()
+        }
+        None => Arc::new(AtomicI32::new(0)),
+    }
+}
+
+/// One piece of work: a bar on the terminal, or the monitor's activity.
+pub struct Task {
+    bar: ProgressBar,
+}
+
+impl Task {
+    /// Work measured in percent.
+    pub fn percent(style: ProgressStyle, message: String) -> Self {
+        let bar = bar(100);
+        bar.set_style(style);
+        Self::start(bar, message, true)
+    }
+
+    pub fn spinner(style: ProgressStyle, message: String) -> Self {
+        let bar = match monitor() {
+            Some(_) => ProgressBar::hidden(),
+            None => {
+                let bar = ProgressBar::new_spinner();
+                bar.enable_steady_tick(std::time::Duration::from_millis(100));
+                bar
+            }
+        };
+        bar.set_style(style);
+        Self::start(bar, message, false)
+    }
+
+    fn start(bar: ProgressBar, message: String, measured: bool) -> Self {
+        if let Some(monitor) = monitor() {
+            monitor.set_activity(Some(Activity {
+                message: message.clone(),
+                measured,
+            }));
+        }
+        bar.set_message(message);
+        Self { bar }
+    }
+
+    pub fn set_position(&self, pos: u64) {
+        self.bar.set_position(pos);
+    }
+
+    /// Leave `message` in place of the bar.
+    pub fn finish(self, message: String) {
+        match monitor() {
+            Some(monitor) => {
+                monitor.set_activity(None);
+                monitor.push(&format!("  {message}"), false);
+            }
+            None => self.bar.finish_with_message(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monitor_keeps_recent_nonempty_lines() {
+        let monitor = Monitor::default();
+        monitor.push("\nDownloading 2 episode(s)...", false);
+        monitor.push("  Failed to download \"x\": 404", true);
+        assert_eq!(
+            monitor.lines(),
+            vec![
+                LogLine {
+                    text: "Downloading 2 episode(s)...".to_string(),
+                    error: false,
+                },
+                LogLine {
+                    text: "  Failed to download \"x\": 404".to_string(),
+                    error: true,
+                },
+            ]
+        );
+
+        for i in 0..MAX_LINES {
+            monitor.push(&i.to_string(), false);
+        }
+        let lines = monitor.lines();
+        assert_eq!(lines.len(), MAX_LINES);
+        assert_eq!(lines[0].text, "0");
+    }
+}
@@ -0,0 +1,467 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::config::AppConfig;
+use crate::language::SummaryLanguage;
+use crate::models::{Episode, Podcast, StructuredSummary};
+
+pub const DEFAULT_STYLE: &str = "default";
+
+const DEFAULT_TEMPLATE: &str = r#"You are a podcast summarizer. Given a transcript of a podcast episode, produce a structured summary with the following sections:
+
+TOPICS: List the main topics discussed (comma-separated)
+
+SUMMARY: A concise narrative summary (2-3 paragraphs)
+
+KEY TAKEAWAYS:
+- Bullet points of the most important insights and conclusions
+
+NOTABLE QUOTES:
+- Direct quotes with approximate timestamps if available
+
+Be concise but comprehensive. Focus on actionable insights and key information."#;
+
+const BRIEF_TEMPLATE: &str = r#"You are a podcast summarizer. The transcript below is from "{{episode_title}}" ({{podcast_title}}, published {{published_date}}, {{duration}}).
+
+Write a brief summary of at most 5 sentences covering the main topic and the single most important takeaway. Do not use headings or bullet points."#;
+
+const DETAILED_TEMPLATE: &str = r#"You are a podcast summarizer. The transcript below is from "{{episode_title}}" ({{podcast_title}}, published {{published_date}}, {{duration}}).
+
+Produce a detailed summary with the following sections:
+
+TOPICS: List the main topics discussed (comma-separated)
+
+SUMMARY: A thorough narrative summary (4-6 paragraphs) that follows the order of the conversation
+
+KEY TAKEAWAYS:
+- Bullet points of the most important insights, conclusions and recommendations
+
+NOTABLE QUOTES:
+- Direct quotes with approximate timestamps if available
+
+OPEN QUESTIONS:
+- Questions raised in the episode that were left unanswered
+
+Be comprehensive, but do not pad the summary with filler."#;
+
+const BULLET_POINTS_TEMPLATE: &str = r#"You are a podcast summarizer. The transcript below is from "{{episode_title}}" ({{podcast_title}}, published {{published_date}}, {{duration}}).
+
+Summarize the episode as 8-15 bullet points, one idea per bullet, in the order they were discussed. Start each bullet with "- ". Do not add any other text."#;
+
+/// Built-in templates as (name, template) pairs.
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (DEFAULT_STYLE, DEFAULT_TEMPLATE),
+    ("brief", BRIEF_TEMPLATE),
+    ("detailed", DETAILED_TEMPLATE),
+    ("bullet-points", BULLET_POINTS_TEMPLATE),
+];
+
+/// File extensions recognised for user templates in the templates directory.
+const TEMPLATE_EXTENSIONS: &[&str] = &["txt", "md"];
+
+/// Where a template was loaded from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSource {
+    Builtin,
+    User(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub body: String,
+    pub source: TemplateSource,
+}
+
+/// Values substituted into `{{variable}}` placeholders when rendering a template.
+#[derive(Debug, Clone, Default)]
+pub struct PromptVars {
+    pub podcast_title: String,
+    pub episode_title: String,
+    pub published_date: String,
+    pub duration: String,
+    pub language: String,
+}
+
+impl PromptVars {
+    pub fn new(podcast: &Podcast, episode: &Episode, language: Option<&SummaryLanguage>) -> Self {
+        Self {
+            podcast_title: podcast.title.clone(),
+            episode_title: episode.title.clone(),
+            published_date: episode
+                .published_at
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "unknown date".to_string()),
+            duration: episode
+                .duration_secs
+                .map(crate::dates::format_duration)
+                .unwrap_or_else(|| "unknown duration".to_string()),
+            language: language
+                .map(|l| l.name.clone())
+                .unwrap_or_else(|| "the same language as the transcript".to_string()),
+        }
+    }
+
+    fn pairs(&self) -> [(&'static str, &str); 5] {
+        [
+            ("podcast_title", &self.podcast_title),
+            ("episode_title", &self.episode_title),
+            ("published_date", &self.published_date),
+            ("duration", &self.duration),
+            ("language", &self.language),
+        ]
+    }
+}
+
+/// Replace `{{variable}}` placeholders. Unknown placeholders are left untouched.
+pub fn render(template: &str, vars: &PromptVars) -> String {
+    let mut out = template.to_string();
+    for (key, value) in vars.pairs() {
+        out = out.replace(&format!("{{{{{key}}}}}"), value);
+    }
+    out
+}
+
+/// Look up a template by name. User templates take precedence over built-ins
+/// with the same name.
+pub fn find_template(name: &str) -> Result<Template> {
+    let dir = AppConfig::templates_dir()?;
+    find_template_in(&dir, name)
+}
+
+fn find_template_in(dir: &Path, name: &str) -> Result<Template> {
+    for ext in TEMPLATE_EXTENSIONS {
+        let path = dir.join(format!("{name}.{ext}"));
+        if path.is_file() {
+            let body = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template at {}", path.display()))?;
+            return Ok(Template {
+                name: name.to_string(),
+                body,
+                source: TemplateSource::User(path),
+            });
+        }
+    }
+
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(n, body)| Template {
+            name: n.to_string(),
+            body: body.to_string(),
+            source: TemplateSource::Builtin,
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown summary style: {name}\nRun `podcast-summarize styles` to see available styles."
+            )
+        })
+}
+
+/// List all available templates, built-ins first, then user templates sorted by name.
+/// A user template that shadows a built-in replaces it in place.
+pub fn list_templates() -> Result<Vec<Template>> {
+    let dir = AppConfig::templates_dir()?;
+    list_templates_in(&dir)
+}
+
+fn list_templates_in(dir: &Path) -> Result<Vec<Template>> {
+    let mut templates: Vec<Template> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, body)| Template {
+            name: name.to_string(),
+            body: body.to_string(),
+            source: TemplateSource::Builtin,
+        })
+        .collect();
+
+    if !dir.is_dir() {
+        return Ok(templates);
+    }
+
+    let mut user_names: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| TEMPLATE_EXTENSIONS.contains(&e))
+        })
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(String::from))
+        .collect();
+    user_names.sort();
+    user_names.dedup();
+
+    for name in user_names {
+        let template = find_template_in(dir, &name)?;
+        if let Some(existing) = templates.iter_mut().find(|t| t.name == name) {
+            *existing = template;
+        } else {
+            templates.push(template);
+        }
+    }
+    Ok(templates)
+}
+
+/// Resolve the style to use for a podcast.
+///
+/// Precedence: explicit `--style`, the podcast's `style` override, then the global
+/// `summarization.style`. Returns `None` when no style is configured anywhere.
+pub fn resolve_style(
+    cli_style: Option<&str>,
+    podcast: &Podcast,
+    config: &AppConfig,
+) -> Result<Option<String>> {
+    if let Some(style) = cli_style {
+        return Ok(Some(style.to_string()));
+    }
+    Ok(config
+        .podcast_config(podcast)?
+        .style
+        .or_else(|| config.summarization.style.clone()))
+}
+
+/// Per-run summary overrides from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct SummaryOptions {
+    pub style: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Resolve the summary language for a podcast: `--lang`, then the podcast's
+/// `summary_language`, then the global `summarization.summary_language`.
+pub fn resolve_language(
+    cli_lang: Option<&str>,
+    podcast: &Podcast,
+    config: &AppConfig,
+) -> Result<Option<SummaryLanguage>> {
+    let lang = match cli_lang {
+        Some(lang) => Some(lang.to_string()),
+        None => config
+            .podcast_config(podcast)?
+            .summary_language
+            .or_else(|| config.summarization.summary_language.clone()),
+    };
+    Ok(lang.map(|l| SummaryLanguage::parse(&l)))
+}
+
+/// A rendered system prompt plus what produced it, recorded with each summary.
+pub struct SystemPrompt {
+    pub text: String,
+    /// Style name, `custom` for a raw `system_prompt`
+    pub style: String,
+    /// Hash of the unrendered template
+    pub hash: String,
+    /// Requested output language, if any
+    pub language: Option<SummaryLanguage>,
+}
+
+impl SystemPrompt {
+    /// Post-process a generated summary and its sections for the requested
+    /// language (script conversion), with one converter for every field.
+    pub fn localize(
+        &self,
+        content: &str,
+        sections: Option<StructuredSummary>,
+    ) -> Result<(String, Option<StructuredSummary>)> {
+        let converter = match self.language {
+            Some(ref lang) => lang.converter()?,
+            None => None,
+        };
+        let Some(cc) = converter else {
+            return Ok((content.to_string(), sections));
+        };
+        let sections = sections.map(|mut sections| {
+            sections.summary = cc.convert(&sections.summary);
+            for text in sections
+                .topics
+                .iter_mut()
+                .chain(sections.key_takeaways.iter_mut())
+            {
+                *text = cc.convert(text);
+            }
+            for quote in &mut sections.notable_quotes {
+                quote.text = cc.convert(&quote.text);
+            }
+            sections
+        });
+        Ok((cc.convert(content), sections))
+    }
+
+    pub fn language_code(&self) -> Option<&str> {
+        self.language.as_ref().map(|l| l.code.as_str())
+    }
+}
+
+/// Resolve style and language for an episode and build its system prompt.
+pub fn prepare_system_prompt(
+    options: &SummaryOptions,
+    podcast: &Podcast,
+    episode: &Episode,
+    config: &AppConfig,
+) -> Result<SystemPrompt> {
+    let style = resolve_style(options.style.as_deref(), podcast, config)?;
+    let language = resolve_language(options.language.as_deref(), podcast, config)?;
+    let vars = PromptVars::new(podcast, episode, language.as_ref());
+    build_system_prompt(style.as_deref(), language, config, &vars)
+}
+
+/// Build the rendered system prompt for an episode.
+///
+/// When no style is configured, a raw `summarization.system_prompt` is used if set,
+/// otherwise the built-in default template. If a language is requested and the
+/// template doesn't place `{{language}}` itself, an instruction is appended.
+pub fn build_system_prompt(
+    style: Option<&str>,
+    language: Option<SummaryLanguage>,
+    config: &AppConfig,
+    vars: &PromptVars,
+) -> Result<SystemPrompt> {
+    let (style, body) = match style {
+        Some(name) => (name.to_string(), find_template(name)?.body),
+        None => match config.summarization.system_prompt {
+            Some(ref raw) => ("custom".to_string(), raw.clone()),
+            None => (DEFAULT_STYLE.to_string(), DEFAULT_TEMPLATE.to_string()),
+        },
+    };
+    let mut text = render(&body, vars);
+    if let Some(ref lang) = language
+        && !body.contains("{{language}}")
+    {
+        text.push_str(&format!(
+            "\n\nWrite the entire summary in {}, regardless of the transcript's language.",
+            lang.name
+        ));
+    }
+    Ok(SystemPrompt {
+        text,
+        style,
+        hash: template_hash(&body),
+        language,
+    })
+}
+
+/// Short, stable hash of a template (FNV-1a, hex). Stable across builds, unlike
+/// `DefaultHasher`, so stored hashes stay comparable.
+pub fn template_hash(template: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in template.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> PromptVars {
+        PromptVars {
+            podcast_title: "Rust Weekly".to_string(),
+            episode_title: "Async Drop".to_string(),
+            published_date: "2025-01-02".to_string(),
+            duration: "1h05m".to_string(),
+            language: "English".to_string(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "podcast-summarize-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn render_substitutes_all_variables() {
+        let out = render(
+            "{{podcast_title}}|{{episode_title}}|{{published_date}}|{{duration}}|{{language}}",
+            &vars(),
+        );
+        assert_eq!(out, "Rust Weekly|Async Drop|2025-01-02|1h05m|English");
+    }
+
+    #[test]
+    fn render_leaves_unknown_placeholders() {
+        let out = render("{{nope}} {\"json\": true}", &vars());
+        assert_eq!(out, "{{nope}} {\"json\": true}");
+    }
+
+    #[test]
+    fn builtin_templates_resolve() {
+        let dir = temp_dir("builtin");
+        for name in ["default", "brief", "detailed", "bullet-points"] {
+            let t = find_template_in(&dir, name).unwrap();
+            assert_eq!(t.source, TemplateSource::Builtin);
+        }
+    }
+
+    #[test]
+    fn unknown_template_fails() {
+        let dir = temp_dir("unknown");
+        let err = find_template_in(&dir, "haiku").unwrap_err();
+        assert!(err.to_string().contains("Unknown summary style"));
+    }
+
+    #[test]
+    fn user_template_overrides_builtin() {
+        let dir = temp_dir("override");
+        std::fs::write(dir.join("brief.md"), "Custom brief for {{podcast_title}}").unwrap();
+        std::fs::write(dir.join("team.txt"), "Team format").unwrap();
+
+        let t = find_template_in(&dir, "brief").unwrap();
+        assert_eq!(t.body, "Custom brief for {{podcast_title}}");
+        assert!(matches!(t.source, TemplateSource::User(_)));
+
+        let names: Vec<String> = list_templates_in(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["default", "brief", "detailed", "bullet-points", "team"]
+        );
+    }
+
+    #[test]
+    fn build_prompt_falls_back_to_system_prompt() {
+        let mut config = AppConfig::default();
+        let prompt = build_system_prompt(None, None, &config, &vars()).unwrap();
+        assert_eq!(prompt.text, DEFAULT_TEMPLATE);
+        assert_eq!(prompt.style, "default");
+
+        config.summarization.system_prompt = Some("Summarize {{episode_title}}".to_string());
+        let prompt = build_system_prompt(None, None, &config, &vars()).unwrap();
+        assert_eq!(prompt.text, "Summarize Async Drop");
+        assert_eq!(prompt.style, "custom");
+        assert_eq!(prompt.hash, template_hash("Summarize {{episode_title}}"));
+    }
+
+    #[test]
+    fn language_instruction_appended_unless_template_places_it() {
+        let mut config = AppConfig::default();
+        let lang = SummaryLanguage::parse("zh-TW");
+        let prompt = build_system_prompt(None, Some(lang.clone()), &config, &vars()).unwrap();
+        assert!(prompt.text.starts_with(DEFAULT_TEMPLATE));
+        assert!(prompt.text.ends_with(
+            "Write the entire summary in Traditional Chinese (Taiwan), regardless of the transcript's language."
+        ));
+
+        config.summarization.system_prompt = Some("Answer in {{language}}.".to_string());
+        let prompt = build_system_prompt(None, Some(lang), &config, &vars()).unwrap();
+        assert_eq!(prompt.text, "Answer in English.");
+    }
+
+    #[test]
+    fn template_hash_is_stable() {
+        assert_eq!(template_hash(""), "cbf29ce484222325");
+        assert_eq!(template_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(template_hash("brief"), template_hash("brief "));
+    }
+}
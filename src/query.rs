@@ -0,0 +1,113 @@
+//! Episode filters given as text, by `list` flags or web UI query parameters,
+//! and the [`EpisodeQuery`] they resolve to.
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::db::{Database, EpisodeQuery, EpisodeSort};
+use crate::{dates, tags};
+
+/// Episode filters, order and paging for `list`, as given on the command line
+/// or in the web UI's query string.
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    pub status: Vec<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub tag: Option<String>,
+    pub sort: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl ListOptions {
+    /// Whether any option picks or orders episodes, which turns `list` without
+    /// a podcast into the episode view across all podcasts.
+    pub fn selects_episodes(&self) -> bool {
+        !self.status.is_empty()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.tag.is_some()
+            || self.sort.is_some()
+            || self.limit.is_some()
+            || self.offset > 0
+    }
+
+    pub fn query(&self, db: &Database, podcast_id: Option<i64>) -> Result<EpisodeQuery> {
+        let statuses = self
+            .status
+            .iter()
+            .map(|s| parse_status(s).map(str::to_string))
+            .collect::<Result<Vec<_>>>()?;
+        let since = self
+            .since
+            .as_deref()
+            .map(|s| dates::parse_since(s, Utc::now()))
+            .transpose()?;
+        // --until is inclusive of the whole day
+        let until = self
+            .until
+            .as_deref()
+            .map(|d| dates::parse_date(d).map(|d| d + chrono::Duration::days(1)))
+            .transpose()?;
+        let tag_id = self
+            .tag
+            .as_deref()
+            .map(|name| tags::resolve(db, name))
+            .transpose()?;
+        Ok(EpisodeQuery {
+            podcast_id,
+            statuses,
+            since,
+            until,
+            tag_id,
+            sort: self
+                .sort
+                .as_deref()
+                .map(EpisodeSort::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            limit: self.limit,
+            offset: self.offset,
+        })
+    }
+}
+
+/// A `--status` value as stored in the database. Accepts the short labels the
+/// episode table shows (`done`, `dl`, `txt`, `err`, `skip`).
+fn parse_status(s: &str) -> Result<&'static str> {
+    match s.to_lowercase().as_str() {
+        "new" => Ok("new"),
+        "downloaded" | "dl" => Ok("downloaded"),
+        "transcribed" | "txt" => Ok("transcribed"),
+        "summarized" | "done" => Ok("summarized"),
+        "failed" | "err" => Ok("failed"),
+        "skipped" | "skip" => Ok("skipped"),
+        _ => anyhow::bail!(
+            "Unknown status: {s} (expected new, downloaded, transcribed, done, failed or skipped)"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_status_accepts_table_labels() {
+        assert_eq!(parse_status("done").unwrap(), "summarized");
+        assert_eq!(parse_status("Failed").unwrap(), "failed");
+        assert_eq!(parse_status("dl").unwrap(), "downloaded");
+        assert!(parse_status("finished").is_err());
+    }
+
+    #[test]
+    fn any_episode_option_selects_episodes() {
+        assert!(!ListOptions::default().selects_episodes());
+        let options = ListOptions {
+            status: vec!["done".to_string()],
+            ..Default::default()
+        };
+        assert!(options.selects_episodes());
+    }
+}
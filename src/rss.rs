@@ -0,0 +1,254 @@
+//! RSS 2.0 feed of summaries, for reading them in a feed reader. Each item is
+//! one episode's latest summary, linking to its audio and the podcast website.
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::export::markdown_to_html;
+use crate::models::{Episode, Podcast, Summary};
+use crate::text::escape_html;
+
+/// A summarized episode to publish.
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    pub podcast: Podcast,
+    pub episode: Episode,
+    pub summary: Summary,
+}
+
+/// The feed's own title, link and description.
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub title: String,
+    pub link: String,
+    pub description: String,
+}
+
+/// The summaries feed of one podcast, or of all podcasts with `podcast_id`
+/// unset. `link` is the channel's link; a podcast's feed defaults to its website,
+/// but RSS requires one that the feed of all podcasts has no default for.
+pub fn build(
+    db: &Database,
+    podcast_id: Option<i64>,
+    limit: usize,
+    link: Option<&str>,
+) -> Result<String> {
+    let mut items = Vec::new();
+    for summary in db.list_latest_summaries(podcast_id, limit)? {
+        let episode = db.get_episode(summary.episode_id)?;
+        items.push(FeedItem {
+            podcast: db.get_podcast(episode.podcast_id)?,
+            episode,
+            summary,
+        });
+    }
+
+    let channel = match podcast_id {
+        Some(id) => {
+            let podcast = db.get_podcast(id)?;
+            Channel {
+                title: format!("{} (summaries)", podcast.title),
+                link: link
+                    .map(str::to_string)
+                    .or(podcast.website_url)
+                    .unwrap_or(podcast.feed_url),
+                description: format!("Episode summaries of {}", podcast.title),
+            }
+        }
+        None => Channel {
+            title: "Podcast summaries".to_string(),
+            link: link
+                .ok_or_else(|| anyhow::anyhow!("The feed of all podcasts needs a link"))?
+                .to_string(),
+            description: "Episode summaries from all subscribed podcasts".to_string(),
+        },
+    };
+    Ok(render(&channel, &items))
+}
+
+/// Render items (newest first) as an RSS 2.0 document. With items from more
+/// than one podcast, titles are prefixed with the podcast's.
+pub fn render(channel: &Channel, items: &[FeedItem]) -> String {
+    let mixed = items.iter().any(|i| i.podcast.id != items[0].podcast.id);
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n",
+    );
+    out.push_str(&format!("<title>{}</title>\n", escape_html(&channel.title)));
+    out.push_str(&format!("<link>{}</link>\n", escape_html(&channel.link)));
+    out.push_str(&format!(
+        "<description>{}</description>\n",
+        escape_html(&channel.description)
+    ));
+    out.push_str("<generator>podcast-summarize</generator>\n");
+    // Derived from the items rather than the clock, so an unchanged feed renders the same
+    if let Some(newest) = items.iter().map(|i| i.summary.created_at).max() {
+        out.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>\n",
+            newest.to_rfc2822()
+        ));
+    }
+
+    for item in items {
+        let (p, e, s) = (&item.podcast, &item.episode, &item.summary);
+        let title = if mixed {
+            format!("{}: {}", p.title, e.title)
+        } else {
+            e.title.clone()
+        };
+        let link = p.website_url.as_deref().unwrap_or(&e.audio_url);
+
+        let mut html = markdown_to_html(&s.content);
+        html.push_str(&format!(
+            "<p><a href=\"{}\">Listen to the episode</a>",
+            escape_html(&e.audio_url)
+        ));
+        if let Some(ref website) = p.website_url {
+            html.push_str(&format!(
+                " · <a href=\"{}\">Website</a>",
+                escape_html(website)
+            ));
+        }
+        html.push_str("</p>\n");
+
+        out.push_str("<item>\n");
+        out.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+        out.push_str(&format!("<link>{}</link>\n", escape_html(link)));
+        // A new summary version is a new item
+        out.push_str(&format!(
+            "<guid isPermaLink=\"false\">podcast-summarize:episode:{}:summary:{}</guid>\n",
+            e.id, s.id
+        ));
+        out.push_str(&format!(
+            "<pubDate>{}</pubDate>\n",
+            s.created_at.to_rfc2822()
+        ));
+        out.push_str(&format!("<category>{}</category>\n", escape_html(&p.title)));
+        out.push_str(&format!(
+            "<enclosure url=\"{}\" length=\"0\" type=\"{}\"/>\n",
+            escape_html(&e.audio_url),
+            audio_mime_type(&e.audio_url)
+        ));
+        out.push_str(&format!(
+            "<description>{}</description>\n",
+            escape_html(&html)
+        ));
+        out.push_str("</item>\n");
+    }
+    out.push_str("</channel>\n</rss>\n");
+    out
+}
+
+/// MIME type for an enclosure, guessed from the URL's extension.
+fn audio_mime_type(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("m4a" | "mp4" | "aac") => "audio/mp4",
+        Some("ogg" | "oga" | "opus") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        _ => "audio/mpeg",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EpisodeStatus;
+    use chrono::Utc;
+
+    fn item(podcast_id: i64, podcast: &str, episode_id: i64, title: &str) -> FeedItem {
+        FeedItem {
+            podcast: Podcast {
+                id: podcast_id,
+                title: podcast.to_string(),
+                feed_url: "https://ex.com/feed".to_string(),
+                website_url: Some("https://ex.com/?a=1&b=2".to_string()),
+                description: None,
+                last_checked: None,
+                added_at: Utc::now(),
+            },
+            episode: Episode {
+                id: episode_id,
+                podcast_id,
+                guid: format!("g{episode_id}"),
+                title: title.to_string(),
+                description: None,
+                audio_url: "https://cdn.ex.com/ep.m4a?token=x".to_string(),
+                published_at: None,
+                duration_secs: None,
+                status: EpisodeStatus::Summarized,
+                audio_path: None,
+                transcript_path: None,
+                discovered_at: Utc::now(),
+            },
+            summary: Summary {
+                id: episode_id * 10,
+                episode_id,
+                content: "Covers <generics>.\n\n- one".to_string(),
+                model: "m".to_string(),
+                prompt_tokens: None,
+                output_tokens: None,
+                created_at: "2024-03-04T12:00:00Z".parse().unwrap(),
+                style: None,
+                prompt_hash: None,
+                language: None,
+                version: 1,
+            },
+        }
+    }
+
+    fn channel() -> Channel {
+        Channel {
+            title: "Summaries".to_string(),
+            link: "https://ex.com".to_string(),
+            description: "All podcasts".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_escapes_summary_html() {
+        let out = render(&channel(), &[item(1, "Pod", 7, "Q&A")]);
+        assert!(
+            out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">")
+        );
+        assert!(out.contains("<title>Q&amp;A</title>"));
+        assert!(out.contains("<link>https://ex.com/?a=1&amp;b=2</link>"));
+        assert!(
+            out.contains(
+                "<guid isPermaLink=\"false\">podcast-summarize:episode:7:summary:70</guid>"
+            )
+        );
+        assert!(out.contains("<pubDate>Mon, 4 Mar 2024 12:00:00 +0000</pubDate>"));
+        assert!(out.contains(
+            "<enclosure url=\"https://cdn.ex.com/ep.m4a?token=x\" length=\"0\" type=\"audio/mp4\"/>"
+        ));
+        // The summary's HTML is escaped once more as RSS text
+        assert!(out.contains("&lt;p&gt;Covers &amp;lt;generics&amp;gt;.&lt;/p&gt;"));
+        assert!(out.contains("&lt;li&gt;one&lt;/li&gt;"));
+    }
+
+    #[test]
+    fn mixed_feeds_prefix_podcast_titles() {
+        let single = render(&channel(), &[item(1, "Pod", 1, "Ep")]);
+        assert!(single.contains("<title>Ep</title>"));
+        let mixed = render(
+            &channel(),
+            &[item(1, "Pod", 1, "Ep"), item(2, "Other", 2, "Ep 2")],
+        );
+        assert!(mixed.contains("<title>Pod: Ep</title>"));
+        assert!(mixed.contains("<title>Other: Ep 2</title>"));
+    }
+
+    #[test]
+    fn empty_feed_is_valid() {
+        let out = render(&channel(), &[]);
+        assert!(out.ends_with("</channel>\n</rss>\n"));
+        assert!(!out.contains("<item>"));
+    }
+
+    #[test]
+    fn mime_types_from_extension() {
+        assert_eq!(audio_mime_type("https://x/a.MP3"), "audio/mpeg");
+        assert_eq!(audio_mime_type("https://x/a.ogg#t=1"), "audio/ogg");
+        assert_eq!(audio_mime_type("https://x/stream"), "audio/mpeg");
+    }
+}
@@ -0,0 +1,157 @@
+//! Query helpers for the full-text index over episodes, summaries and transcripts.
+//!
+//! The index uses FTS5's trigram tokenizer so Chinese and other unsegmented text
+//! matches on substrings. Trigrams can't match terms shorter than three characters,
+//! so those queries fall back to `LIKE` and snippets are cut in Rust.
+
+/// Marks the start of a match in a snippet.
+pub const HIGHLIGHT_START: &str = "\u{2}";
+/// Marks the end of a match in a snippet.
+pub const HIGHLIGHT_END: &str = "\u{3}";
+
+/// Characters of context kept on each side of a match in fallback snippets.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Whitespace-separated query terms.
+pub fn terms(query: &str) -> Vec<&str> {
+    query.split_whitespace().collect()
+}
+
+/// Whether every term is long enough for the trigram index.
+pub fn is_indexable(terms: &[&str]) -> bool {
+    !terms.is_empty() && terms.iter().all(|t| t.chars().count() >= 3)
+}
+
+/// FTS5 `MATCH` expression requiring every term, each quoted so that user input
+/// is never parsed as query syntax.
+pub fn match_expression(terms: &[&str]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// `LIKE` pattern matching a term anywhere, with wildcards in the term escaped by `\`.
+pub fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Excerpt of `text` around the first case-insensitive match of any term, with
+/// every match in the excerpt highlighted. `None` if no term occurs.
+pub fn snippet(text: &str, terms: &[&str]) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| fold(*c)).collect();
+    let needles: Vec<Vec<char>> = terms
+        .iter()
+        .map(|t| t.chars().map(fold).collect::<Vec<_>>())
+        .filter(|n| !n.is_empty())
+        .collect();
+
+    let matches_at = |i: usize| -> Option<usize> {
+        needles
+            .iter()
+            .filter(|n| lower[i..].starts_with(n))
+            .map(|n| n.len())
+            .max()
+    };
+
+    let first = (0..lower.len()).find(|&i| matches_at(i).is_some())?;
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = (first + SNIPPET_CONTEXT * 2).min(chars.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        match matches_at(i) {
+            Some(len) => {
+                let stop = (i + len).min(chars.len());
+                out.push_str(HIGHLIGHT_START);
+                out.extend(&chars[i..stop]);
+                out.push_str(HIGHLIGHT_END);
+                i = stop;
+            }
+            None => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    if i < chars.len() {
+        out.push('…');
+    }
+    Some(collapse_whitespace(&out))
+}
+
+/// Replace highlight markers with the given strings (e.g. ANSI codes or `**`).
+pub fn render_highlights(snippet: &str, start: &str, end: &str) -> String {
+    snippet
+        .replace(HIGHLIGHT_START, start)
+        .replace(HIGHLIGHT_END, end)
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_expression_quotes_terms() {
+        assert_eq!(
+            match_expression(&["postgres", "vacuum"]),
+            "\"postgres\" AND \"vacuum\""
+        );
+        assert_eq!(match_expression(&["say\"hi"]), "\"say\"\"hi\"");
+    }
+
+    #[test]
+    fn short_terms_are_not_indexable() {
+        assert!(is_indexable(&terms("postgres vacuum")));
+        assert!(!is_indexable(&terms("go generics")));
+        assert!(is_indexable(&terms("資料庫")));
+        assert!(!is_indexable(&terms("投資")));
+        assert!(!is_indexable(&terms("   ")));
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("50%_off"), "%50\\%\\_off%");
+    }
+
+    #[test]
+    fn snippet_highlights_matches() {
+        let s = snippet("We talked about Go and Rust today", &["go"]).unwrap();
+        assert_eq!(
+            render_highlights(&s, "[", "]"),
+            "We talked about [Go] and Rust today"
+        );
+    }
+
+    #[test]
+    fn snippet_trims_long_text() {
+        let text = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let s = render_highlights(&snippet(&text, &["needle"]).unwrap(), "[", "]");
+        assert!(s.starts_with('…'));
+        assert!(s.ends_with('…'));
+        assert!(s.contains("[needle]"));
+    }
+
+    #[test]
+    fn snippet_none_without_match() {
+        assert!(snippet("nothing here", &["xyz"]).is_none());
+    }
+}
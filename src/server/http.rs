@@ -0,0 +1,301 @@
+//! Just enough HTTP/1.1 for the local UI: one request per connection, bodies
+//! ignored, and every response closes the connection.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Longest request line or header line accepted.
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+/// Request bodies are read and dropped up to this size; none of the routes take one.
+const MAX_BODY: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// A header value; names are matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// A decoded query parameter.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Read one request. Returns `None` when the client closed the connection
+/// without sending one.
+pub async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Request>> {
+    let Some(line) = read_line(reader).await? else {
+        return Ok(None);
+    };
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        anyhow::bail!("Malformed request line: {line}");
+    };
+    if !version.starts_with("HTTP/1.") {
+        anyhow::bail!("Unsupported HTTP version: {version}");
+    }
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)
+            .await?
+            .context("Connection closed in the headers")?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            anyhow::bail!("Too many headers");
+        }
+        let (name, value) = line
+            .split_once(':')
+            .with_context(|| format!("Malformed header: {line}"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let request = {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Request {
+            method: method.to_string(),
+            path: percent_decode(path),
+            query: parse_query(query),
+            headers,
+        }
+    };
+    let length: usize = request
+        .header("content-length")
+        .map(|v| v.parse().context("Invalid Content-Length"))
+        .transpose()?
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        anyhow::bail!("Request body too large");
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(request))
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<String>> {
+    let mut line = Vec::new();
+    let n = reader
+        .take(MAX_LINE as u64 + 2)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') {
+        anyhow::bail!("Request line too long");
+    }
+    let line = String::from_utf8(line).context("Request is not UTF-8")?;
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` (as in form-encoded queries). Invalid escapes
+/// are kept as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let decoded = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(b) => {
+                out.push(b);
+                i += 3;
+            }
+            None => {
+                out.push(if bytes[i] == b'+' { b' ' } else { bytes[i] });
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json<T: Serialize + ?Sized>(status: u16, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self {
+                status,
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Self::error(500, &format!("{e}")),
+        }
+    }
+
+    /// A JSON `{"error": message}` response.
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": message })
+                .to_string()
+                .into_bytes(),
+        }
+    }
+
+    pub fn text(content_type: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body: body.into().into_bytes(),
+        }
+    }
+}
+
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &Response,
+) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: {}\r\ncontent-length: {}\r\ncache-control: no-store\r\nconnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&response.body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Start a `text/event-stream` response; events follow with [`write_event`].
+pub async fn start_event_stream<W: AsyncWrite + Unpin>(writer: &mut W) -> Result<()> {
+    writer
+        .write_all(
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncache-control: no-store\r\nconnection: close\r\n\r\n",
+        )
+        .await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Send one server-sent event with a JSON payload.
+pub async fn write_event<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    event: &str,
+    data: &T,
+) -> Result<()> {
+    let frame = format!("event: {event}\ndata: {}\n\n", serde_json::to_string(data)?);
+    writer.write_all(frame.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Send an SSE comment, which clients ignore; keeps idle streams from timing out.
+pub async fn write_comment<W: AsyncWrite + Unpin>(writer: &mut W, comment: &str) -> Result<()> {
+    writer
+        .write_all(format!(": {comment}\n\n").as_bytes())
+        .await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(raw: &str) -> Result<Option<Request>> {
+        read_request(&mut tokio::io::BufReader::new(raw.as_bytes())).await
+    }
+
+    #[tokio::test]
+    async fn reads_request_line_headers_and_query() {
+        let req = parse(
+            "GET /api/episodes?podcast=3&status=new%2Cfailed&q=a+b HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer s3cret\r\n\r\n",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(req.method, "GET");
+        assert_eq!(req.path, "/api/episodes");
+        assert_eq!(req.query("podcast"), Some("3"));
+        assert_eq!(req.query("status"), Some("new,failed"));
+        assert_eq!(req.query("q"), Some("a b"));
+        assert_eq!(req.header("authorization"), Some("Bearer s3cret"));
+        assert_eq!(req.query("missing"), None);
+    }
+
+    #[tokio::test]
+    async fn skips_request_bodies() {
+        let mut reader = tokio::io::BufReader::new(
+            "POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcGET /b HTTP/1.1\r\n\r\n".as_bytes(),
+        );
+        let first = read_request(&mut reader).await.unwrap().unwrap();
+        assert_eq!(first.path, "/a");
+        let second = read_request(&mut reader).await.unwrap().unwrap();
+        assert_eq!(second.path, "/b");
+        assert!(read_request(&mut reader).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_requests() {
+        assert!(parse("GET\r\n\r\n").await.is_err());
+        assert!(parse("GET / SPDY/3\r\n\r\n").await.is_err());
+        assert!(parse("GET / HTTP/1.1\r\nno-colon\r\n\r\n").await.is_err());
+        assert!(
+            parse(&format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE)))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("a%20b%2"), "a b%2");
+        assert_eq!(percent_decode("%zz%C3%A9"), "%zzé");
+    }
+}
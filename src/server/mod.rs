@@ -0,0 +1,693 @@
+//! Local web UI and JSON API over the database. Sync progress (from this server
+//! or any other sync process) is polled from the job queue and streamed to
+//! clients as server-sent events.
+
+mod http;
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::io::{AsyncWrite, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::config::AppConfig;
+use crate::daemon::Leases;
+use crate::db::Database;
+use crate::export::markdown_to_html;
+use crate::models::{Episode, Job, JobStage, Podcast, StructuredSummary, Summary};
+use crate::pipeline;
+use crate::prompts::SummaryOptions;
+use crate::query::ListOptions;
+use http::{Request, Response};
+
+const INDEX_HTML: &str = include_str!("index.html");
+/// How often the job queue is checked for progress.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Idle event streams get a comment this often so proxies don't drop them.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+/// Episodes per page when the request doesn't give a limit.
+const PAGE_SIZE: usize = 50;
+const FEED_LIMIT: usize = 50;
+
+/// Where an episode's sync stands, sent to event stream clients when it changes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Progress {
+    pub episode_id: i64,
+    pub status: String,
+    /// Stage the episode's job is at; `None` once the job is done
+    pub stage: Option<&'static str>,
+    /// Whether a sync is working on the episode right now
+    pub running: bool,
+    pub attempts: i64,
+    pub error: Option<String>,
+}
+
+pub struct Server {
+    db: Database,
+    /// The address listened on, which the `Host` header must name
+    addr: SocketAddr,
+    token: Option<String>,
+    progress: broadcast::Sender<Progress>,
+    syncs: mpsc::UnboundedSender<i64>,
+}
+
+/// Serve until the process is stopped. Must run inside a `LocalSet`, since the
+/// database connection stays on one thread.
+pub async fn run(listener: TcpListener, config: AppConfig, token: Option<String>) -> Result<()> {
+    let db_path = config.db_path()?;
+    let (progress, _) = broadcast::channel(256);
+    let (syncs, requests) = mpsc::unbounded_channel();
+    let server = Rc::new(Server {
+        db: Database::open(&db_path)?,
+        addr: listener.local_addr()?,
+        token,
+        progress,
+        syncs,
+    });
+    tokio::task::spawn_local(sync_worker(Database::open(&db_path)?, config, requests));
+    tokio::task::spawn_local(watch_progress(server.clone()));
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = server.clone();
+        tokio::task::spawn_local(async move {
+            if let Err(e) = server.connection(stream).await {
+                tracing::debug!("Connection failed: {e:#}");
+            }
+        });
+    }
+}
+
+/// Run requested syncs one at a time, so episodes don't compete for whisper.
+async fn sync_worker(db: Database, config: AppConfig, mut requests: mpsc::UnboundedReceiver<i64>) {
+    let client = reqwest::Client::new();
+    while let Some(episode_id) = requests.recv().await {
+        let scope = pipeline::Scope::Episode(episode_id);
+        if let Err(e) = pipeline::run(&db, &client, &scope, &config).await {
+            eprintln!("Sync of episode #{episode_id} failed: {e:#}");
+        }
+    }
+}
+
+async fn watch_progress(server: Rc<Server>) {
+    let mut last = BTreeMap::new();
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        ticker.tick().await;
+        match progress_changes(&server.db, &mut last) {
+            Ok(changes) => {
+                for progress in changes {
+                    // No subscribers is fine
+                    let _ = server.progress.send(progress);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to check sync progress: {e:#}"),
+        }
+    }
+}
+
+/// Progress of every episode with a job.
+fn progress_snapshot(db: &Database) -> Result<BTreeMap<i64, Progress>> {
+    let mut snapshot = BTreeMap::new();
+    for job in db.list_jobs()? {
+        let episode = db.get_episode(job.episode_id)?;
+        snapshot.insert(
+            job.episode_id,
+            Progress {
+                episode_id: job.episode_id,
+                status: episode.status.as_str().to_string(),
+                stage: Some(job.stage.as_str()),
+                running: db.episode_lease_owner(job.episode_id)?.is_some(),
+                attempts: job.attempts,
+                error: job.last_error,
+            },
+        );
+    }
+    Ok(snapshot)
+}
+
+/// Entries that changed since `last`, plus a final one for each episode whose
+/// job has finished. Replaces `last` with the current snapshot.
+fn progress_changes(db: &Database, last: &mut BTreeMap<i64, Progress>) -> Result<Vec<Progress>> {
+    let current = progress_snapshot(db)?;
+    let mut changes: Vec<Progress> = current
+        .values()
+        .filter(|p| last.get(&p.episode_id) != Some(p))
+        .cloned()
+        .collect();
+    for &episode_id in last.keys().filter(|id| !current.contains_key(id)) {
+        // A removed episode has nothing left to report
+        if let Ok(episode) = db.get_episode(episode_id) {
+            changes.push(Progress {
+                episode_id,
+                status: episode.status.as_str().to_string(),
+                stage: None,
+                running: false,
+                attempts: 0,
+                error: None,
+            });
+        }
+    }
+    *last = current;
+    Ok(changes)
+}
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    Index,
+    Podcasts,
+    Episodes,
+    Episode(i64),
+    Transcript(i64),
+    Sync(i64),
+    Events,
+    Feed(Option<i64>),
+}
+
+impl Route {
+    fn parse(method: &str, path: &str) -> std::result::Result<Self, Response> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let id = |s: &str| {
+            s.parse::<i64>()
+                .map_err(|_| Response::error(404, "Not found"))
+        };
+        let route = match segments.as_slice() {
+            [""] => Self::Index,
+            ["feed.xml"] => Self::Feed(None),
+            ["podcasts", podcast, "feed.xml"] => Self::Feed(Some(id(podcast)?)),
+            ["api", "podcasts"] => Self::Podcasts,
+            ["api", "episodes"] => Self::Episodes,
+            ["api", "episodes", episode] => Self::Episode(id(episode)?),
+            ["api", "episodes", episode, "transcript"] => Self::Transcript(id(episode)?),
+            ["api", "episodes", episode, "sync"] => Self::Sync(id(episode)?),
+            ["api", "events"] => Self::Events,
+            _ => return Err(Response::error(404, "Not found")),
+        };
+        let method_allowed = match route {
+            Self::Sync(_) => method == "POST",
+            _ => method == "GET",
+        };
+        if !method_allowed {
+            return Err(Response::error(405, "Method not allowed"));
+        }
+        Ok(route)
+    }
+}
+
+/// A subscription with its episode counts.
+#[derive(Serialize)]
+struct PodcastRow {
+    #[serde(flatten)]
+    podcast: Podcast,
+    episodes: i64,
+    new: i64,
+}
+
+#[derive(Serialize)]
+struct EpisodePage {
+    total: usize,
+    episodes: Vec<Episode>,
+}
+
+/// An episode with everything the UI shows about it.
+#[derive(Serialize)]
+struct EpisodeDetail {
+    episode: Episode,
+    podcast: Podcast,
+    tags: Vec<String>,
+    summary: Option<Summary>,
+    /// The summary rendered for display
+    summary_html: Option<String>,
+    sections: Option<StructuredSummary>,
+    has_transcript: bool,
+    job: Option<Job>,
+}
+
+impl Server {
+    async fn connection(&self, stream: TcpStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let request = match http::read_request(&mut BufReader::new(reader)).await {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                let response = Response::error(400, &format!("{e:#}"));
+                return http::write_response(&mut writer, &response).await;
+            }
+        };
+        if let Err(response) = self.check_origin(&request) {
+            return http::write_response(&mut writer, &response).await;
+        }
+        let route = match Route::parse(&request.method, &request.path) {
+            Ok(route) => route,
+            Err(response) => return http::write_response(&mut writer, &response).await,
+        };
+        // The page itself holds no data; it asks for the token to call the API
+        if route != Route::Index && !self.authorized(&request) {
+            let response = Response::error(401, "Missing or wrong token");
+            return http::write_response(&mut writer, &response).await;
+        }
+        if route == Route::Events {
+            return self.stream_progress(&mut writer).await;
+        }
+        let response = self.handle(route, &request).unwrap_or_else(|e| {
+            tracing::warn!("{} {} failed: {e:#}", request.method, request.path);
+            Response::error(500, &format!("{e:#}"))
+        });
+        http::write_response(&mut writer, &response).await
+    }
+
+    /// Turn away requests from other sites. A `Host` naming something other
+    /// than this server means DNS rebinding: a page whose domain now resolves
+    /// here, reading the API as its own origin. A POST with a foreign `Origin`
+    /// is a page starting syncs cross-site, which browsers allow without asking.
+    fn check_origin(&self, request: &Request) -> std::result::Result<(), Response> {
+        let host = request.header("host").unwrap_or_default();
+        if !host_allowed(host, self.addr) {
+            return Err(Response::error(403, "Unexpected Host header"));
+        }
+        if request.method == "POST"
+            && let Some(origin) = request.header("origin")
+            && origin != format!("http://{host}")
+        {
+            return Err(Response::error(
+                403,
+                "Cross-origin requests are not allowed",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the request carries the token, as a bearer token or (for
+    /// EventSource and feed readers, which can't set headers) `?token=`.
+    fn authorized(&self, request: &Request) -> bool {
+        let Some(ref token) = self.token else {
+            return true;
+        };
+        request
+            .header("authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or_else(|| request.query("token"))
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+    }
+
+    fn handle(&self, route: Route, request: &Request) -> Result<Response> {
+        match route {
+            Route::Index => Ok(Response::text("text/html; charset=utf-8", INDEX_HTML)),
+            Route::Podcasts => self.podcasts(),
+            Route::Episodes => self.episodes(request),
+            Route::Episode(id) => self.episode(id),
+            Route::Transcript(id) => self.transcript(id),
+            Route::Sync(id) => self.sync(id),
+            Route::Feed(podcast_id) => self.feed(podcast_id, request),
+            // Streamed by `connection`
+            Route::Events => Ok(Response::error(404, "Not found")),
+        }
+    }
+
+    fn podcasts(&self) -> Result<Response> {
+        let rows = self
+            .db
+            .list_podcasts()?
+            .into_iter()
+            .map(|podcast| {
+                Ok(PodcastRow {
+                    episodes: self.db.episode_count(podcast.id)?,
+                    new: self.db.episode_count_by_status(podcast.id, "new")?,
+                    podcast,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Response::json(200, &rows))
+    }
+
+    /// Episodes newest first, with the filters and paging of `list`.
+    fn episodes(&self, request: &Request) -> Result<Response> {
+        let number = |name: &str| request.query(name).map(str::parse::<usize>).transpose();
+        let (Ok(podcast_id), Ok(limit), Ok(offset)) = (
+            request.query("podcast").map(str::parse::<i64>).transpose(),
+            number("limit"),
+            number("offset"),
+        ) else {
+            return Ok(Response::error(
+                400,
+                "podcast, limit and offset must be numbers",
+            ));
+        };
+        let options = ListOptions {
+            status: request
+                .query("status")
+                .map(|s| s.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            since: request.query("since").map(str::to_string),
+            until: request.query("until").map(str::to_string),
+            tag: request.query("tag").map(str::to_string),
+            sort: request.query("sort").map(str::to_string),
+            limit: Some(limit.unwrap_or(PAGE_SIZE)),
+            offset: offset.unwrap_or(0),
+        };
+        let query = match options.query(&self.db, podcast_id) {
+            Ok(query) => query,
+            Err(e) => return Ok(Response::error(400, &format!("{e:#}"))),
+        };
+        let page = EpisodePage {
+            total: self.db.count_episodes(&query)?,
+            episodes: self.db.query_episodes(&query)?,
+        };
+        Ok(Response::json(200, &page))
+    }
+
+    fn episode(&self, id: i64) -> Result<Response> {
+        let Ok(episode) = self.db.get_episode(id) else {
+            return Ok(Response::error(404, &format!("Episode #{id} not found")));
+        };
+        let summary = self.db.get_summary_by_episode(id)?;
+        let sections = match summary {
+            Some(ref s) => self.db.get_summary_sections(s.id)?,
+            None => None,
+        };
+        let detail = EpisodeDetail {
+            podcast: self.db.get_podcast(episode.podcast_id)?,
+            tags: self.db.episode_tag_names(id)?,
+            summary_html: summary.as_ref().map(|s| markdown_to_html(&s.content)),
+            summary,
+            sections,
+            has_transcript: transcript_path(&episode).is_some(),
+            job: self.db.get_job(id)?,
+            episode,
+        };
+        Ok(Response::json(200, &detail))
+    }
+
+    fn transcript(&self, id: i64) -> Result<Response> {
+        let Ok(episode) = self.db.get_episode(id) else {
+            return Ok(Response::error(404, &format!("Episode #{id} not found")));
+        };
+        match transcript_path(&episode) {
+            Some(path) => Ok(Response::text(
+                "text/plain; charset=utf-8",
+                std::fs::read_to_string(path)?,
+            )),
+            None => Ok(Response::error(404, "No transcript for this episode")),
+        }
+    }
+
+    /// Queue the episode (unless it's already due) and hand it to the sync worker.
+    fn sync(&self, id: i64) -> Result<Response> {
+        if self.db.get_episode(id).is_err() {
+            return Ok(Response::error(404, &format!("Episode #{id} not found")));
+        }
+        let due = self
+            .db
+            .get_job(id)?
+            .is_some_and(|job| job.next_run_at.is_some());
+        if !due {
+            // Don't reset a job another process is working on
+            let mut leases = Leases::new(&self.db);
+            if !leases.claim(id)? {
+                let message = format!("Episode #{id} is being synced by {}", leases.holder(id));
+                return Ok(Response::error(409, &message));
+            }
+            pipeline::enqueue(
+                &self.db,
+                id,
+                JobStage::Summarize,
+                false,
+                &SummaryOptions::default(),
+            )?;
+        }
+        self.syncs.send(id)?;
+        Ok(Response::json(
+            202,
+            &serde_json::json!({ "episode_id": id, "queued": true }),
+        ))
+    }
+
+    fn feed(&self, podcast_id: Option<i64>, request: &Request) -> Result<Response> {
+        if let Some(id) = podcast_id
+            && self.db.get_podcast(id).is_err()
+        {
+            return Ok(Response::error(404, &format!("Podcast #{id} not found")));
+        }
+        // The feed of all podcasts links to the web UI; the Host was checked
+        let link = match podcast_id {
+            Some(_) => None,
+            None => Some(format!(
+                "http://{}/",
+                request.header("host").unwrap_or("localhost")
+            )),
+        };
+        let document = crate::rss::build(&self.db, podcast_id, FEED_LIMIT, link.as_deref())?;
+        Ok(Response::text(
+            "application/rss+xml; charset=utf-8",
+            document,
+        ))
+    }
+
+    /// Send the progress of every queued episode, then each change as it
+    /// happens, until the client goes away.
+    async fn stream_progress<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<()> {
+        let mut updates = self.progress.subscribe();
+        http::start_event_stream(writer).await?;
+        for progress in progress_snapshot(&self.db)?.values() {
+            http::write_event(writer, "progress", progress).await?;
+        }
+        let mut keepalive = tokio::time::interval(KEEPALIVE);
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Ok(progress) => http::write_event(writer, "progress", &progress).await?,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = keepalive.tick() => http::write_comment(writer, "keepalive").await?,
+            }
+        }
+    }
+}
+
+fn transcript_path(episode: &Episode) -> Option<&str> {
+    episode
+        .transcript_path
+        .as_deref()
+        .filter(|path| std::path::Path::new(path).exists())
+}
+
+/// Whether a `Host` header names the server: localhost, a loopback address or
+/// the address it listens on. A server listening on all interfaces accepts any
+/// IP address, but no domain other than localhost.
+fn host_allowed(host: &str, addr: SocketAddr) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').map_or(rest, |(ip, _)| ip),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match name.parse::<IpAddr>() {
+        Ok(ip) => ip.is_loopback() || ip == addr.ip() || addr.ip().is_unspecified(),
+        Err(_) => false,
+    }
+}
+
+/// Compare without stopping at the first differing byte, so response times
+/// don't leak how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(token: Option<&str>) -> (Server, mpsc::UnboundedReceiver<i64>) {
+        let (syncs, requests) = mpsc::unbounded_channel();
+        let server = Server {
+            db: Database::open_in_memory().unwrap(),
+            addr: "127.0.0.1:8080".parse().unwrap(),
+            token: token.map(str::to_string),
+            progress: broadcast::channel(16).0,
+            syncs,
+        };
+        (server, requests)
+    }
+
+    async fn request(raw: &str) -> Request {
+        http::read_request(&mut BufReader::new(raw.as_bytes()))
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    fn json(response: &Response) -> serde_json::Value {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn routes() {
+        assert_eq!(Route::parse("GET", "/").unwrap(), Route::Index);
+        assert_eq!(
+            Route::parse("GET", "/api/episodes/12/").unwrap(),
+            Route::Episode(12)
+        );
+        assert_eq!(
+            Route::parse("POST", "/api/episodes/12/sync").unwrap(),
+            Route::Sync(12)
+        );
+        assert_eq!(
+            Route::parse("GET", "/podcasts/3/feed.xml").unwrap(),
+            Route::Feed(Some(3))
+        );
+        assert_eq!(
+            Route::parse("GET", "/api/episodes/x").unwrap_err().status,
+            404
+        );
+        assert_eq!(Route::parse("GET", "/nope").unwrap_err().status, 404);
+        assert_eq!(
+            Route::parse("GET", "/api/episodes/12/sync")
+                .unwrap_err()
+                .status,
+            405
+        );
+        assert_eq!(
+            Route::parse("POST", "/api/podcasts").unwrap_err().status,
+            405
+        );
+    }
+
+    #[tokio::test]
+    async fn feed_of_all_podcasts_links_to_the_ui() {
+        let (server, _) = server(None);
+        let request = request("GET /feed.xml HTTP/1.1\r\nHost: localhost:8080\r\n\r\n").await;
+        let response = server.handle(Route::Feed(None), &request).unwrap();
+        let body = String::from_utf8(response.body).unwrap();
+        assert!(body.contains("<link>http://localhost:8080/</link>"));
+    }
+
+    #[tokio::test]
+    async fn token_from_header_or_query() {
+        let (open, _) = server(None);
+        let (locked, _) = server(Some("s3cret"));
+        let bare = request("GET /api/podcasts HTTP/1.1\r\n\r\n").await;
+        assert!(open.authorized(&bare));
+        assert!(!locked.authorized(&bare));
+        assert!(locked.authorized(
+            &request("GET /api/podcasts HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n").await
+        ));
+        assert!(locked.authorized(&request("GET /api/events?token=s3cret HTTP/1.1\r\n\r\n").await));
+        assert!(!locked.authorized(&request("GET /api/events?token=s3cre HTTP/1.1\r\n\r\n").await));
+    }
+
+    #[tokio::test]
+    async fn rejects_other_hosts_and_cross_origin_posts() {
+        let (server, _) = server(None);
+        let status = |request: Request| server.check_origin(&request).map_err(|r| r.status);
+        let get = |host: &str| format!("GET /api/podcasts HTTP/1.1\r\nHost: {host}\r\n\r\n");
+        assert_eq!(status(request(&get("127.0.0.1:8080")).await), Ok(()));
+        assert_eq!(status(request(&get("localhost:8080")).await), Ok(()));
+        assert_eq!(status(request(&get("[::1]:8080")).await), Ok(()));
+        // DNS rebinding: a foreign domain resolving to us
+        assert_eq!(status(request(&get("evil.example:8080")).await), Err(403));
+        assert_eq!(
+            status(request("GET /api/podcasts HTTP/1.1\r\n\r\n").await),
+            Err(403)
+        );
+
+        let post = |origin: &str| {
+            format!(
+                "POST /api/episodes/1/sync HTTP/1.1\r\nHost: 127.0.0.1:8080\r\nOrigin: {origin}\r\n\r\n"
+            )
+        };
+        assert_eq!(
+            status(request(&post("http://127.0.0.1:8080")).await),
+            Ok(())
+        );
+        assert_eq!(
+            status(request(&post("https://evil.example")).await),
+            Err(403)
+        );
+
+        let any: SocketAddr = "0.0.0.0:8080".parse().unwrap();
+        assert!(host_allowed("192.168.1.5:8080", any));
+        assert!(!host_allowed("evil.example", any));
+        assert!(!host_allowed("192.168.1.5:8080", server.addr));
+    }
+
+    #[tokio::test]
+    async fn lists_podcasts_and_episodes() {
+        let (server, _) = server(None);
+        let p = server
+            .db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        for i in 1..=3 {
+            server
+                .db
+                .insert_episode(
+                    p.id,
+                    &format!("g{i}"),
+                    &format!("Ep {i}"),
+                    None,
+                    "https://ex.com/a.mp3",
+                    None,
+                    None,
+                )
+                .unwrap();
+        }
+
+        let podcasts = json(&server.podcasts().unwrap());
+        assert_eq!(podcasts[0]["title"], "Pod");
+        assert_eq!(podcasts[0]["episodes"], 3);
+
+        let req = request(&format!(
+            "GET /api/episodes?podcast={}&limit=2 HTTP/1.1\r\n\r\n",
+            p.id
+        ))
+        .await;
+        let page = json(&server.episodes(&req).unwrap());
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["episodes"].as_array().unwrap().len(), 2);
+
+        let bad = request("GET /api/episodes?status=bogus HTTP/1.1\r\n\r\n").await;
+        assert_eq!(server.episodes(&bad).unwrap().status, 400);
+        assert_eq!(server.episode(99).unwrap().status, 404);
+    }
+
+    #[tokio::test]
+    async fn sync_queues_episode_and_reports_progress() {
+        let (server, mut requests) = server(None);
+        let p = server
+            .db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        let ep = server
+            .db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/a.mp3", None, None)
+            .unwrap();
+
+        let mut last = BTreeMap::new();
+        assert!(progress_changes(&server.db, &mut last).unwrap().is_empty());
+
+        assert_eq!(server.sync(ep).unwrap().status, 202);
+        assert_eq!(requests.try_recv().unwrap(), ep);
+        let changes = progress_changes(&server.db, &mut last).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].stage, Some("download"));
+        assert!(!changes[0].running);
+        // Unchanged: nothing to send
+        assert!(progress_changes(&server.db, &mut last).unwrap().is_empty());
+
+        let job = server.db.get_job(ep).unwrap().unwrap();
+        server.db.complete_job(job.id).unwrap();
+        let changes = progress_changes(&server.db, &mut last).unwrap();
+        assert_eq!(changes[0].stage, None);
+        assert_eq!(changes[0].status, "new");
+
+        assert_eq!(server.sync(99).unwrap().status, 404);
+    }
+}
@@ -1,11 +1,21 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::models::{Quote, StructuredSummary};
+
 #[derive(Serialize)]
 struct ChatRequest {
     model: String,
     max_tokens: u32,
     messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+#[derive(Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
 }
 
 #[derive(Serialize)]
@@ -41,55 +51,153 @@ pub struct SummaryResult {
     pub model: String,
     pub prompt_tokens: Option<i64>,
     pub output_tokens: Option<i64>,
+    /// Typed sections, when the output could be parsed into them
+    pub structured: Option<StructuredSummary>,
 }
 
-const DEFAULT_SYSTEM_PROMPT: &str = r#"You are a podcast summarizer. Given a transcript of a podcast episode, produce a structured summary with the following sections:
+const JSON_INSTRUCTIONS: &str = r#"Respond with only a JSON object (no prose, no code fences) matching this schema:
+{
+  "topics": ["string"],
+  "summary": "string",
+  "key_takeaways": ["string"],
+  "notable_quotes": [{"text": "string", "speaker": "string or null", "timestamp": "string or null"}]
+}"#;
 
-TOPICS: List the main topics discussed (comma-separated)
-
-SUMMARY: A concise narrative summary (2-3 paragraphs)
+/// Summarize a transcript as free text. Sections in the default text format
+/// (TOPICS/SUMMARY/KEY TAKEAWAYS/NOTABLE QUOTES) are parsed when present.
+pub async fn generate_summary(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    api_key: &str,
+    model: &str,
+    max_tokens: u32,
+    system_prompt: &str,
+    transcript: &str,
+) -> Result<SummaryResult> {
+    let mut result = chat_completion(
+        client,
+        api_base_url,
+        api_key,
+        model,
+        max_tokens,
+        system_prompt,
+        &transcript_message(transcript),
+    )
+    .await?;
+    result.structured = parse_sections(&result.content);
+    Ok(result)
+}
 
-KEY TAKEAWAYS:
-- Bullet points of the most important insights and conclusions
+/// Summarize a transcript in JSON mode and validate it against [`StructuredSummary`].
+///
+/// `content` is the structured summary rendered in the text format. Providers that
+/// reject `response_format` get a plain request instead, and invalid JSON falls back
+/// to parsing the text sections.
+pub async fn generate_structured_summary(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    api_key: &str,
+    model: &str,
+    max_tokens: u32,
+    system_prompt: &str,
+    transcript: &str,
+) -> Result<SummaryResult> {
+    let request = chat_request(
+        model,
+        max_tokens,
+        &format!("{system_prompt}\n\n{JSON_INSTRUCTIONS}"),
+        &transcript_message(transcript),
+        Some(ResponseFormat {
+            kind: "json_object",
+        }),
+    );
 
-NOTABLE QUOTES:
-- Direct quotes with approximate timestamps if available
+    let mut result = match send(client, api_base_url, api_key, &request).await {
+        Ok(result) => result,
+        Err(e) if is_unsupported_json_mode(&e) => {
+            tracing::info!("JSON mode not supported by provider, falling back to text: {e}");
+            return generate_summary(
+                client,
+                api_base_url,
+                api_key,
+                model,
+                max_tokens,
+                system_prompt,
+                transcript,
+            )
+            .await;
+        }
+        Err(e) => return Err(e),
+    };
 
-Be concise but comprehensive. Focus on actionable insights and key information."#;
+    match parse_json_summary(&result.content) {
+        Ok(structured) => {
+            result.content = render_text(&structured);
+            result.structured = Some(structured);
+        }
+        Err(e) => {
+            tracing::warn!("Invalid JSON summary, parsing as text: {e}");
+            result.structured = parse_sections(&result.content);
+        }
+    }
+    Ok(result)
+}
 
-pub async fn generate_summary(
+/// Send a single system + user exchange to the chat completions endpoint.
+pub async fn chat_completion(
     client: &reqwest::Client,
     api_base_url: &str,
     api_key: &str,
     model: &str,
     max_tokens: u32,
-    system_prompt: Option<&str>,
-    transcript: &str,
+    system_prompt: &str,
+    user_message: &str,
 ) -> Result<SummaryResult> {
-    let system = system_prompt.unwrap_or(DEFAULT_SYSTEM_PROMPT);
+    let request = chat_request(model, max_tokens, system_prompt, user_message, None);
+    send(client, api_base_url, api_key, &request).await
+}
+
+fn transcript_message(transcript: &str) -> String {
+    format!("Here is the podcast transcript to summarize:\n\n{transcript}")
+}
 
-    let request = ChatRequest {
+fn chat_request(
+    model: &str,
+    max_tokens: u32,
+    system_prompt: &str,
+    user_message: &str,
+    response_format: Option<ResponseFormat>,
+) -> ChatRequest {
+    ChatRequest {
         model: model.to_string(),
         max_tokens,
         messages: vec![
             Message {
                 role: "system".to_string(),
-                content: system.to_string(),
+                content: system_prompt.to_string(),
             },
             Message {
                 role: "user".to_string(),
-                content: format!("Here is the podcast transcript to summarize:\n\n{transcript}"),
+                content: user_message.to_string(),
             },
         ],
-    };
+        response_format,
+    }
+}
 
+async fn send(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    api_key: &str,
+    request: &ChatRequest,
+) -> Result<SummaryResult> {
     let url = format!("{}/chat/completions", api_base_url.trim_end_matches('/'));
 
     let response = client
         .post(&url)
         .header("Authorization", format!("Bearer {api_key}"))
         .header("content-type", "application/json")
-        .json(&request)
+        .json(request)
         .send()
         .await
         .with_context(|| format!("Failed to call LLM API at {url}"))?;
@@ -118,8 +226,397 @@ pub async fn generate_summary(
 
     Ok(SummaryResult {
         content,
-        model: model.to_string(),
+        model: request.model.clone(),
         prompt_tokens: chat_resp.usage.as_ref().and_then(|u| u.prompt_tokens),
         output_tokens: chat_resp.usage.as_ref().and_then(|u| u.completion_tokens),
+        structured: None,
     })
 }
+
+/// Whether an API error means the provider rejected `response_format`. Other
+/// bad requests (an oversized transcript, a wrong model name) are not retried.
+fn is_unsupported_json_mode(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<crate::error::AppError>() {
+        Some(crate::error::AppError::ClaudeApi {
+            status: 400 | 422,
+            body,
+        }) => {
+            let body = body.to_lowercase();
+            body.contains("response_format") || body.contains("json")
+        }
+        _ => false,
+    }
+}
+
+/// Parse and validate a JSON summary, tolerating a surrounding code fence.
+pub fn parse_json_summary(content: &str) -> Result<StructuredSummary> {
+    let trimmed = content.trim();
+    let json = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|s| s.strip_suffix("```"))
+        .unwrap_or(trimmed);
+
+    let parsed: StructuredSummary =
+        serde_json::from_str(json).context("Summary is not valid JSON for the schema")?;
+    validate(parsed)
+}
+
+/// Trim fields, drop empty entries, and require a narrative summary.
+fn validate(summary: StructuredSummary) -> Result<StructuredSummary> {
+    let clean = |items: Vec<String>| -> Vec<String> {
+        items
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+    let summary = StructuredSummary {
+        topics: clean(summary.topics),
+        summary: summary.summary.trim().to_string(),
+        key_takeaways: clean(summary.key_takeaways),
+        notable_quotes: summary
+            .notable_quotes
+            .into_iter()
+            .filter(|q| !q.text.trim().is_empty())
+            .map(|q| Quote {
+                text: q.text.trim().to_string(),
+                speaker: q.speaker.filter(|s| !s.trim().is_empty()),
+                timestamp: q.timestamp.filter(|s| !s.trim().is_empty()),
+            })
+            .collect(),
+    };
+    if summary.summary.is_empty() {
+        anyhow::bail!("Summary JSON has an empty \"summary\" field");
+    }
+    Ok(summary)
+}
+
+/// Render structured sections in the same text format the default prompt asks for.
+pub fn render_text(summary: &StructuredSummary) -> String {
+    let mut out = format!(
+        "TOPICS: {}\n\nSUMMARY: {}",
+        summary.topics.join(", "),
+        summary.summary
+    );
+    if !summary.key_takeaways.is_empty() {
+        out.push_str("\n\nKEY TAKEAWAYS:");
+        for t in &summary.key_takeaways {
+            out.push_str(&format!("\n- {t}"));
+        }
+    }
+    if !summary.notable_quotes.is_empty() {
+        out.push_str("\n\nNOTABLE QUOTES:");
+        for q in &summary.notable_quotes {
+            out.push_str(&format!("\n- {}", format_quote(q)));
+        }
+    }
+    out
+}
+
+pub fn format_quote(q: &Quote) -> String {
+    let mut s = format!("\"{}\"", q.text);
+    if let Some(ref speaker) = q.speaker {
+        s.push_str(&format!(" — {speaker}"));
+    }
+    if let Some(ref ts) = q.timestamp {
+        s.push_str(&format!(" ({ts})"));
+    }
+    s
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    None,
+    Topics,
+    Summary,
+    Takeaways,
+    Quotes,
+    /// A section we don't store, such as the detailed style's OPEN QUESTIONS.
+    Other,
+}
+
+/// Parse the TOPICS/SUMMARY/KEY TAKEAWAYS/NOTABLE QUOTES text format.
+/// Returns `None` when no section headers are found (e.g. brief styles).
+/// Any other all-caps `HEADER:` line ends the current section, and what
+/// follows it is dropped.
+pub fn parse_sections(content: &str) -> Option<StructuredSummary> {
+    let mut out = StructuredSummary::default();
+    let mut section = Section::None;
+    let mut found = false;
+    let mut narrative: Vec<String> = Vec::new();
+
+    for raw in content.lines() {
+        let line = raw.trim();
+        let (header, rest) = match split_header(line) {
+            Some((header, rest)) => (Some(header), rest),
+            None => (None, line),
+        };
+        if let Some(header) = header {
+            section = header;
+            found |= header != Section::Other;
+        }
+        if rest.is_empty() {
+            // Keep paragraph breaks inside the narrative
+            if section == Section::Summary && header.is_none() && !narrative.is_empty() {
+                narrative.push(String::new());
+            }
+            continue;
+        }
+        match section {
+            Section::None | Section::Other => {}
+            Section::Topics => out.topics.extend(
+                rest.trim_start_matches(['-', '*', '•'])
+                    .split([',', '，', '、'])
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty()),
+            ),
+            Section::Summary => narrative.push(rest.to_string()),
+            Section::Takeaways => out.key_takeaways.push(strip_bullet(rest).to_string()),
+            Section::Quotes => out.notable_quotes.push(parse_quote(strip_bullet(rest))),
+        }
+    }
+
+    if !found {
+        return None;
+    }
+    out.summary = narrative.join("\n");
+    validate(out).ok()
+}
+
+fn split_header(line: &str) -> Option<(Section, &str)> {
+    let stripped = line.trim_start_matches(['#', '*', ' ']);
+    let (name, rest) = stripped.split_once(':')?;
+    let name = name.trim_end_matches('*').trim();
+    let section = match name.to_uppercase().as_str() {
+        "TOPICS" => Section::Topics,
+        "SUMMARY" => Section::Summary,
+        "KEY TAKEAWAYS" => Section::Takeaways,
+        "NOTABLE QUOTES" => Section::Quotes,
+        _ if is_shouted(name) => Section::Other,
+        _ => return None,
+    };
+    Some((section, rest.trim_start_matches('*').trim()))
+}
+
+/// Whether `name` looks like a header: capital letters and spaces only.
+fn is_shouted(name: &str) -> bool {
+    name.chars().any(char::is_alphabetic) && name.chars().all(|c| c.is_uppercase() || c == ' ')
+}
+
+fn strip_bullet(line: &str) -> &str {
+    let line = line.trim_start_matches(['-', '*', '•']).trim_start();
+    // Numbered lists: "1." or "1)"
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0
+        && let Some(rest) = line[digits..]
+            .strip_prefix('.')
+            .or_else(|| line[digits..].strip_prefix(')'))
+    {
+        return rest.trim_start();
+    }
+    line
+}
+
+/// Parse `"text" — speaker (12:34)`; anything that doesn't fit stays in `text`.
+fn parse_quote(line: &str) -> Quote {
+    let mut rest = line.trim();
+    let mut timestamp = None;
+    if rest.ends_with(')')
+        && let Some(open) = rest.rfind('(')
+    {
+        let inner = &rest[open + 1..rest.len() - 1];
+        if inner.contains(':') && inner.chars().all(|c| c.is_ascii_digit() || c == ':') {
+            timestamp = Some(inner.to_string());
+            rest = rest[..open].trim_end();
+        }
+    }
+
+    let mut speaker = None;
+    let open_quote = rest.starts_with(['"', '“', '「']);
+    if open_quote
+        && let Some(close) = rest.rfind(['"', '”', '」'])
+        && close > 0
+    {
+        let after = rest[close..].chars().skip(1).collect::<String>();
+        let after = after.trim().trim_start_matches(['—', '-', '–']).trim();
+        if !after.is_empty() {
+            speaker = Some(after.to_string());
+        }
+        let first_len = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        rest = &rest[first_len..close];
+    }
+
+    Quote {
+        text: rest.trim().to_string(),
+        speaker,
+        timestamp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::stub_http_server;
+
+    async fn structured(url: &str) -> Result<SummaryResult> {
+        generate_structured_summary(
+            &reqwest::Client::new(),
+            url,
+            "key",
+            "model",
+            100,
+            "Summarize.",
+            "transcript",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_text_when_json_mode_is_rejected() {
+        let (url, server) = stub_http_server(vec![
+            (
+                400,
+                r#"{"error":{"message":"response_format is not supported"}}"#,
+            ),
+            (200, r#"{"choices":[{"message":{"content":"Short."}}]}"#),
+        ])
+        .await;
+        let result = structured(&url).await.unwrap();
+        assert!(result.content.contains("Short."));
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("response_format"));
+        assert!(!requests[1].contains("response_format"));
+    }
+
+    #[tokio::test]
+    async fn other_bad_requests_are_not_retried() {
+        let (url, server) = stub_http_server(vec![(
+            400,
+            r#"{"error":{"message":"maximum context length exceeded"}}"#,
+        )])
+        .await;
+        let Err(err) = structured(&url).await else {
+            panic!("expected the 400 to be returned");
+        };
+        assert!(matches!(
+            err.downcast_ref::<crate::error::AppError>(),
+            Some(crate::error::AppError::ClaudeApi { status: 400, .. })
+        ));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn parse_json_summary_valid() {
+        let json = r#"{
+            "topics": ["Rust", " async "],
+            "summary": "A talk about async Rust.",
+            "key_takeaways": ["Use tokio", ""],
+            "notable_quotes": [{"quote": "Futures are lazy", "speaker": "Alice", "timestamp": "12:34"}]
+        }"#;
+        let s = parse_json_summary(json).unwrap();
+        assert_eq!(s.topics, vec!["Rust", "async"]);
+        assert_eq!(s.key_takeaways, vec!["Use tokio"]);
+        assert_eq!(s.notable_quotes[0].text, "Futures are lazy");
+        assert_eq!(s.notable_quotes[0].speaker.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn parse_json_summary_strips_code_fence() {
+        let json = "```json\n{\"summary\": \"ok\"}\n```";
+        let s = parse_json_summary(json).unwrap();
+        assert_eq!(s.summary, "ok");
+        assert!(s.topics.is_empty());
+    }
+
+    #[test]
+    fn parse_json_summary_rejects_invalid() {
+        assert!(parse_json_summary("TOPICS: a, b").is_err());
+        assert!(parse_json_summary(r#"{"topics": ["a"], "summary": "  "}"#).is_err());
+        assert!(parse_json_summary(r#"{"topics": "not a list", "summary": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn parse_sections_default_format() {
+        let text = "TOPICS: Rust, WebAssembly, tooling\n\n\
+SUMMARY: The hosts discuss Rust.\nThey also cover Wasm.\n\n\
+KEY TAKEAWAYS:\n- Rust is fast\n2. Wasm is portable\n\n\
+NOTABLE QUOTES:\n- \"Ship it\" — Bob (01:02:03)\n- Just a line";
+        let s = parse_sections(text).unwrap();
+        assert_eq!(s.topics, vec!["Rust", "WebAssembly", "tooling"]);
+        assert_eq!(s.summary, "The hosts discuss Rust.\nThey also cover Wasm.");
+        assert_eq!(s.key_takeaways, vec!["Rust is fast", "Wasm is portable"]);
+        assert_eq!(
+            s.notable_quotes[0],
+            Quote {
+                text: "Ship it".to_string(),
+                speaker: Some("Bob".to_string()),
+                timestamp: Some("01:02:03".to_string()),
+            }
+        );
+        assert_eq!(s.notable_quotes[1].text, "Just a line");
+    }
+
+    #[test]
+    fn parse_sections_detailed_format() {
+        let text = "TOPICS: Databases, SQLite\n\n\
+SUMMARY: The guest explains how SQLite is tested.\n\n\
+The hosts ask about WAL mode.\n\n\
+KEY TAKEAWAYS:\n- Test coverage matters\n- WAL helps readers\n\n\
+NOTABLE QUOTES:\n- \"Every branch is tested\" — Richard (12:30)\n\n\
+OPEN QUESTIONS:\n- Will SQLite ever support multiple writers?\n- What comes after WAL2?";
+        let s = parse_sections(text).unwrap();
+        assert_eq!(s.topics, vec!["Databases", "SQLite"]);
+        assert_eq!(
+            s.summary,
+            "The guest explains how SQLite is tested.\n\nThe hosts ask about WAL mode."
+        );
+        assert_eq!(
+            s.key_takeaways,
+            vec!["Test coverage matters", "WAL helps readers"]
+        );
+        assert_eq!(
+            s.notable_quotes,
+            vec![Quote {
+                text: "Every branch is tested".to_string(),
+                speaker: Some("Richard".to_string()),
+                timestamp: Some("12:30".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_sections_markdown_headers() {
+        let text = "## **TOPICS:** a, b\n**SUMMARY:**\nBody";
+        let s = parse_sections(text).unwrap();
+        assert_eq!(s.topics, vec!["a", "b"]);
+        assert_eq!(s.summary, "Body");
+    }
+
+    #[test]
+    fn parse_sections_keeps_paragraphs() {
+        let text = "SUMMARY: First.\n\nSecond.\n\nKEY TAKEAWAYS:\n- x";
+        let s = parse_sections(text).unwrap();
+        assert_eq!(s.summary, "First.\n\nSecond.");
+    }
+
+    #[test]
+    fn parse_sections_none_for_free_text() {
+        assert!(parse_sections("Just a brief paragraph about the episode.").is_none());
+    }
+
+    #[test]
+    fn render_text_roundtrips_through_parser() {
+        let s = StructuredSummary {
+            topics: vec!["a".to_string(), "b".to_string()],
+            summary: "Narrative".to_string(),
+            key_takeaways: vec!["one".to_string()],
+            notable_quotes: vec![Quote {
+                text: "hi".to_string(),
+                speaker: Some("Ann".to_string()),
+                timestamp: Some("3:14".to_string()),
+            }],
+        };
+        assert_eq!(parse_sections(&render_text(&s)), Some(s));
+    }
+}
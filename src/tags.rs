@@ -0,0 +1,82 @@
+//! Looking up tags for filters, and tagging episodes automatically from what
+//! their summaries say they are about.
+
+use anyhow::Result;
+
+use crate::db::Database;
+
+/// The ID of an existing tag, for `--tag` filters.
+pub fn resolve(db: &Database, name: &str) -> Result<i64> {
+    db.find_tag(name.trim())?
+        .ok_or_else(|| anyhow::anyhow!("No tag named \"{name}\". See: podcast-summarize tag list"))
+}
+
+/// Tag an episode with every existing tag that one of its summary topics
+/// mentions. Returns the names of the tags added.
+pub fn auto_tag(db: &Database, episode_id: i64, topics: &[String]) -> Result<Vec<String>> {
+    let mut added = Vec::new();
+    for tag in db.list_tags()? {
+        if topics.iter().any(|topic| topic_mentions(topic, &tag.name))
+            && db.tag_episode(episode_id, tag.id, "topic")?
+        {
+            added.push(tag.name);
+        }
+    }
+    Ok(added)
+}
+
+/// Whether `topic` contains `tag` as a whole word, ignoring case. Only ASCII
+/// letters and digits count as word characters, so CJK tags match anywhere.
+fn topic_mentions(topic: &str, tag: &str) -> bool {
+    let topic = topic.to_lowercase();
+    let tag = tag.to_lowercase();
+    if tag.is_empty() {
+        return false;
+    }
+    topic.match_indices(&tag).any(|(i, m)| {
+        let before = topic[..i].chars().next_back();
+        let after = topic[i + m.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric())
+            && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_mentions_whole_words_only() {
+        assert!(topic_mentions("Rust async runtimes", "rust"));
+        assert!(topic_mentions("Machine Learning", "machine learning"));
+        assert!(topic_mentions("AI-safety", "ai"));
+        assert!(!topic_mentions("Trust and safety", "rust"));
+        assert!(!topic_mentions("Rustaceans", "rust"));
+        assert!(!topic_mentions("anything", ""));
+    }
+
+    #[test]
+    fn topic_mentions_cjk_anywhere() {
+        assert!(topic_mentions("分散式資料庫", "資料庫"));
+    }
+
+    #[test]
+    fn auto_tag_adds_existing_tags_only() {
+        let db = Database::open_in_memory().unwrap();
+        let p = db
+            .insert_podcast("https://ex.com/feed", "Pod", None, None)
+            .unwrap();
+        let ep = db
+            .insert_episode(p.id, "g1", "Ep", None, "https://ex.com/1.mp3", None, None)
+            .unwrap();
+        db.ensure_tag("Rust").unwrap();
+        db.ensure_tag("Go").unwrap();
+
+        let topics = vec!["rust in production".to_string(), "Kubernetes".to_string()];
+        assert_eq!(auto_tag(&db, ep, &topics).unwrap(), vec!["Rust"]);
+        // Already tagged: nothing new
+        assert!(auto_tag(&db, ep, &topics).unwrap().is_empty());
+        assert!(db.find_tag("kubernetes").unwrap().is_none());
+        assert_eq!(db.episode_tag_names(ep).unwrap(), vec!["Rust"]);
+    }
+}
@@ -0,0 +1,74 @@
+//! Measuring text in scripts with and without spaces between words, and
+//! escaping it for HTML.
+
+/// Count text length: characters for CJK-heavy text, words for others.
+pub fn count_text_length(s: &str) -> usize {
+    let cjk_count = s.chars().filter(|c| is_cjk(*c)).count();
+    let total_chars = s.chars().filter(|c| !c.is_whitespace()).count();
+    if total_chars > 0 && cjk_count * 100 / total_chars > 30 {
+        total_chars
+    } else {
+        s.split_whitespace().count()
+    }
+}
+
+/// Escape text for HTML and XML, attribute values included.
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}' |
+        '\u{3400}'..='\u{4DBF}' |
+        '\u{F900}'..='\u{FAFF}' |
+        '\u{3000}'..='\u{303F}' |
+        '\u{FF00}'..='\u{FFEF}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_text_length_english() {
+        assert_eq!(count_text_length("hello world foo bar"), 4);
+    }
+
+    #[test]
+    fn count_text_length_cjk() {
+        // All CJK chars - should count characters (excluding whitespace)
+        let text = "今天天氣很好我們去散步";
+        let result = count_text_length(text);
+        assert_eq!(result, 11);
+    }
+
+    #[test]
+    fn count_text_length_mixed_below_threshold() {
+        // Mostly English with a few CJK chars (below 30% threshold)
+        let text = "This is a long English sentence with one 字";
+        let result = count_text_length(text);
+        // CJK ratio is low, so word count
+        assert_eq!(result, text.split_whitespace().count());
+    }
+
+    #[test]
+    fn count_text_length_empty() {
+        assert_eq!(count_text_length(""), 0);
+    }
+
+    #[test]
+    fn count_text_length_whitespace_only() {
+        assert_eq!(count_text_length("   \n\t  "), 0);
+    }
+
+    #[test]
+    fn is_cjk_chinese_char() {
+        assert!(is_cjk('中'));
+        assert!(is_cjk('國'));
+    }
+}
@@ -8,9 +8,18 @@ use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextPar
 
 use crate::audio;
 use crate::config::AppConfig;
+use crate::models::TranscriptSegment;
+use crate::progress::{self, say_err};
 
 const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
 
+/// Transcript text plus whisper's timed segments.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
 /// Transcribe an audio file to text using local whisper.cpp.
 ///
 /// Audio is decoded and processed in chunks to limit peak memory usage,
@@ -19,7 +28,7 @@ pub fn transcribe(
     audio_path: &Path,
     config: &AppConfig,
     progress: Arc<AtomicI32>,
-) -> Result<String> {
+) -> Result<Transcript> {
     let model_path = ensure_model(config)?;
 
     let mut decoder = audio::ChunkedAudioDecoder::open(audio_path)
@@ -48,7 +57,7 @@ pub fn transcribe(
         .map(|n| ((n.get() as u32 * pct / 100).max(1)) as i32)
         .unwrap_or(4);
 
-    let mut transcript = String::new();
+    let mut segments = Vec::new();
     let mut chunk_start_secs: f64 = 0.0;
 
     while let Some(samples) = decoder.next_chunk(chunk_secs)? {
@@ -103,24 +112,71 @@ pub fn transcribe(
             if let Some(segment) = state.get_segment(i)
                 && let Ok(text) = segment.to_str_lossy()
             {
-                transcript.push_str(&text);
+                // Segment timestamps are in centiseconds, relative to the chunk
+                segments.push(TranscriptSegment {
+                    start_secs: chunk_start_secs + segment.start_timestamp() as f64 / 100.0,
+                    end_secs: chunk_start_secs + segment.end_timestamp() as f64 / 100.0,
+                    text: text.replace('\n', " "),
+                });
             }
         }
 
         chunk_start_secs += chunk_duration;
     }
 
-    let transcript = transcript.trim().to_string();
-    let transcript = convert_chinese(&transcript, config)?;
-    Ok(transcript)
+    // Convert segment by segment so none can lose or gain text; the converter
+    // is built once since loading its dictionaries is the slow part
+    if let Some(ref variant) = config.transcription.chinese_conversion {
+        let cc = opencc(variant)?;
+        for segment in &mut segments {
+            segment.text = cc.convert(&segment.text);
+        }
+    }
+
+    let text = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<String>()
+        .trim()
+        .to_string();
+    Ok(Transcript { text, segments })
 }
 
-/// Convert Chinese characters if configured (e.g. Simplified → Traditional).
-fn convert_chinese(text: &str, config: &AppConfig) -> Result<String> {
-    let Some(ref variant) = config.transcription.chinese_conversion else {
-        return Ok(text.to_string());
-    };
+/// Path of the segment timings saved next to a transcript file.
+pub fn segments_path(transcript_path: &Path) -> PathBuf {
+    transcript_path.with_extension("segments.json")
+}
+
+/// Save a transcript and its segment timings.
+pub fn save_transcript(transcript_path: &Path, transcript: &Transcript) -> Result<()> {
+    if let Some(parent) = transcript_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(transcript_path, &transcript.text)?;
+    if !transcript.segments.is_empty() {
+        std::fs::write(
+            segments_path(transcript_path),
+            serde_json::to_string(&transcript.segments)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Load a saved transcript. Transcripts from before segment timings were kept
+/// come back with no segments.
+pub fn load_transcript(transcript_path: &Path) -> Result<Transcript> {
+    let text = std::fs::read_to_string(transcript_path)
+        .with_context(|| format!("Failed to read transcript {}", transcript_path.display()))?;
+    let segments = std::fs::read_to_string(segments_path(transcript_path))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    Ok(Transcript { text, segments })
+}
 
+/// The OpenCC converter for a variant such as `s2twp` (e.g. Simplified → Traditional).
+/// Loading its dictionaries is slow, so build one per batch of text.
+pub fn opencc(variant: &str) -> Result<OpenCC> {
     let builtin = match variant.to_lowercase().as_str() {
         "s2t" => BuiltinConfig::S2t,
         "s2tw" => BuiltinConfig::S2tw,
@@ -138,9 +194,7 @@ fn convert_chinese(text: &str, config: &AppConfig) -> Result<String> {
         ),
     };
 
-    let cc = OpenCC::from_config(builtin)
-        .map_err(|e| anyhow::anyhow!("Failed to initialize OpenCC: {e}"))?;
-    Ok(cc.convert(text))
+    OpenCC::from_config(builtin).map_err(|e| anyhow::anyhow!("Failed to initialize OpenCC: {e}"))
 }
 
 /// Ensure the whisper model file exists, downloading if needed.
@@ -156,7 +210,7 @@ fn ensure_model(config: &AppConfig) -> Result<PathBuf> {
         return Ok(model_path);
     }
 
-    eprintln!(
+    say_err!(
         "Whisper model '{model_name}' not found. Downloading to {}...",
         model_path.display()
     );
@@ -185,7 +239,7 @@ fn download_model(filename: &str, dest: &Path) -> Result<()> {
     }
 
     let total = response.content_length().unwrap_or(0);
-    let pb = indicatif::ProgressBar::new(total);
+    let pb = progress::bar(total);
     pb.set_style(
         indicatif::ProgressStyle::default_bar()
             .template("  [{bar:40.cyan/dim}] {bytes}/{total_bytes} ({eta})")
@@ -212,6 +266,6 @@ fn download_model(filename: &str, dest: &Path) -> Result<()> {
     pb.finish_and_clear();
     std::fs::rename(&tmp, dest)?;
 
-    eprintln!("  Model downloaded successfully.");
+    say_err!("  Model downloaded successfully.");
     Ok(())
 }
@@ -0,0 +1,537 @@
+//! What the TUI shows and the actions its keys run. Nothing here touches the
+//! terminal, so it can be tested against a database.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::path::Path;
+
+use anyhow::Result;
+use ratatui::widgets::ListState;
+
+use crate::daemon::Leases;
+use crate::db::{Database, EpisodeQuery};
+use crate::embed::format_timestamp;
+use crate::models::{Episode, EpisodeStatus, JobStage, Podcast};
+use crate::pipeline::{self, Report};
+use crate::prompts::SummaryOptions;
+use crate::transcribe;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Podcasts,
+    Episodes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReaderView {
+    Summary,
+    Transcript,
+}
+
+/// An episode's summary or transcript, open in place of the lists.
+pub struct Reader {
+    pub episode_id: i64,
+    pub view: ReaderView,
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: usize,
+    /// Lines on screen, set when drawn.
+    pub page: usize,
+}
+
+/// An action waiting for the user to press `y`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confirm {
+    Redo(Vec<i64>),
+    Quit,
+}
+
+/// A subscription with its episode counts.
+pub struct PodcastEntry {
+    pub podcast: Podcast,
+    pub episodes: i64,
+    pub new: i64,
+}
+
+pub struct App {
+    pub podcasts: Vec<PodcastEntry>,
+    pub podcast_list: ListState,
+    pub episodes: Vec<Episode>,
+    pub episode_list: ListState,
+    pub focus: Focus,
+    /// Episodes picked with Space; actions apply to them instead of the selection.
+    pub marked: BTreeSet<i64>,
+    pub reader: Option<Reader>,
+    pub confirm: Option<Confirm>,
+    pub message: Option<String>,
+    /// Episodes handed to the sync worker, the one it's on first.
+    pub syncing: VecDeque<i64>,
+}
+
+impl App {
+    pub fn load(db: &Database) -> Result<Self> {
+        let mut app = Self {
+            podcasts: Vec::new(),
+            podcast_list: ListState::default(),
+            episodes: Vec::new(),
+            episode_list: ListState::default(),
+            focus: Focus::Podcasts,
+            marked: BTreeSet::new(),
+            reader: None,
+            confirm: None,
+            message: None,
+            syncing: VecDeque::new(),
+        };
+        app.refresh(db)?;
+        if app.podcasts.is_empty() {
+            app.message = Some(
+                "No subscriptions yet. Add one with: podcast-summarize add <RSS_URL>".to_string(),
+            );
+        }
+        Ok(app)
+    }
+
+    /// Reload podcasts and episodes, keeping the selections where they were.
+    pub fn refresh(&mut self, db: &Database) -> Result<()> {
+        let selected = self.selected_podcast().map(|p| p.id);
+        self.podcasts = db
+            .list_podcasts()?
+            .into_iter()
+            .map(|podcast| {
+                Ok(PodcastEntry {
+                    episodes: db.episode_count(podcast.id)?,
+                    new: db.episode_count_by_status(podcast.id, "new")?,
+                    podcast,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let index = selected
+            .and_then(|id| self.podcasts.iter().position(|e| e.podcast.id == id))
+            .unwrap_or(0);
+        self.podcast_list
+            .select((!self.podcasts.is_empty()).then_some(index));
+        self.load_episodes(db)
+    }
+
+    fn load_episodes(&mut self, db: &Database) -> Result<()> {
+        let selected = self.selected_episode().map(|e| e.id);
+        self.episodes = match self.selected_podcast() {
+            Some(podcast) => db.query_episodes(&EpisodeQuery {
+                podcast_id: Some(podcast.id),
+                ..Default::default()
+            })?,
+            None => Vec::new(),
+        };
+        let index = selected
+            .and_then(|id| self.episodes.iter().position(|e| e.id == id))
+            .unwrap_or(0);
+        self.episode_list
+            .select((!self.episodes.is_empty()).then_some(index));
+        self.marked
+            .retain(|id| self.episodes.iter().any(|e| e.id == *id));
+        Ok(())
+    }
+
+    pub fn selected_podcast(&self) -> Option<&Podcast> {
+        self.podcast_list
+            .selected()
+            .and_then(|i| self.podcasts.get(i))
+            .map(|e| &e.podcast)
+    }
+
+    pub fn selected_episode(&self) -> Option<&Episode> {
+        self.episode_list
+            .selected()
+            .and_then(|i| self.episodes.get(i))
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Podcasts => Focus::Episodes,
+            Focus::Episodes => Focus::Podcasts,
+        };
+    }
+
+    /// Move the selection in the focused list by `delta`, stopping at the ends.
+    pub fn move_by(&mut self, db: &Database, delta: isize) -> Result<()> {
+        let (list, len) = match self.focus {
+            Focus::Podcasts => (&mut self.podcast_list, self.podcasts.len()),
+            Focus::Episodes => (&mut self.episode_list, self.episodes.len()),
+        };
+        let Some(current) = list.selected() else {
+            return Ok(());
+        };
+        let next = current.saturating_add_signed(delta).min(len - 1);
+        if next == current {
+            return Ok(());
+        }
+        list.select(Some(next));
+        if self.focus == Focus::Podcasts {
+            self.marked.clear();
+            self.episode_list.select(None);
+            *self.episode_list.offset_mut() = 0;
+            self.load_episodes(db)?;
+        }
+        Ok(())
+    }
+
+    /// Mark or unmark the selected episode and move to the next one.
+    pub fn toggle_mark(&mut self, db: &Database) -> Result<()> {
+        if let Some(id) = self.selected_episode().map(|e| e.id) {
+            if !self.marked.remove(&id) {
+                self.marked.insert(id);
+            }
+            self.focus = Focus::Episodes;
+            self.move_by(db, 1)?;
+        }
+        Ok(())
+    }
+
+    /// The episodes an action applies to: the marked ones in list order, or
+    /// the selected one.
+    pub fn targets(&self) -> Vec<i64> {
+        if self.marked.is_empty() {
+            return self.selected_episode().map(|e| e.id).into_iter().collect();
+        }
+        self.episodes
+            .iter()
+            .map(|e| e.id)
+            .filter(|id| self.marked.contains(id))
+            .collect()
+    }
+
+    /// Queue the target episodes for a later sync.
+    pub fn queue(&mut self, db: &Database) -> Result<()> {
+        let mut queued = 0;
+        let mut summarized = 0;
+        for id in self.targets() {
+            let episode = db.get_episode(id)?;
+            if episode.status == EpisodeStatus::Summarized {
+                summarized += 1;
+            } else if pipeline::queue_episode(db, &episode)? {
+                queued += 1;
+            }
+        }
+        let mut message = format!("Queued {queued} episode(s).");
+        if summarized > 0 {
+            message.push_str(&format!(" {summarized} already summarized; r redoes them."));
+        }
+        self.finish_action(db, message)
+    }
+
+    /// Skip the target episodes, or bring back the ones already skipped.
+    pub fn toggle_skip(&mut self, db: &Database) -> Result<()> {
+        let mut skipped = 0;
+        let mut unskipped = 0;
+        for id in self.targets() {
+            if self.syncing.contains(&id) {
+                continue;
+            }
+            let episode = db.get_episode(id)?;
+            if pipeline::unskip_episode(db, &episode)? {
+                unskipped += 1;
+            } else if pipeline::skip_episode(db, &episode)? {
+                skipped += 1;
+            }
+        }
+        self.finish_action(
+            db,
+            format!("Skipped {skipped} episode(s), unskipped and queued {unskipped}."),
+        )
+    }
+
+    /// Queue the target episodes to run now. Returns the ones to hand to the
+    /// sync worker.
+    pub fn sync(&mut self, db: &Database) -> Result<Vec<i64>> {
+        self.start(db, false)
+    }
+
+    /// Ask before redoing the target episodes, which throws away their transcripts.
+    pub fn request_redo(&mut self) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        self.message = Some(format!(
+            "Redo {} episode(s)? Transcripts are made again and summaries get a new version. (y/n)",
+            targets.len()
+        ));
+        self.confirm = Some(Confirm::Redo(targets));
+    }
+
+    /// Clear the transcripts of `ids` and queue them to run now, forcing a new
+    /// summary. Returns the ones to hand to the sync worker.
+    pub fn redo(&mut self, db: &Database, ids: Vec<i64>) -> Result<Vec<i64>> {
+        self.marked = ids.into_iter().collect();
+        self.start(db, true)
+    }
+
+    /// What `sync -e` does for each target: leave episodes another process is
+    /// working on alone, and queue the rest.
+    fn start(&mut self, db: &Database, redo: bool) -> Result<Vec<i64>> {
+        let mut started = Vec::new();
+        let mut busy = 0;
+        for id in self.targets() {
+            if self.syncing.contains(&id) {
+                busy += 1;
+                continue;
+            }
+            let mut leases = Leases::new(db);
+            if !leases.claim(id)? {
+                busy += 1;
+                continue;
+            }
+            if redo {
+                pipeline::clear_episode_results(db, id)?;
+            }
+            pipeline::enqueue(
+                db,
+                id,
+                JobStage::Summarize,
+                redo,
+                &SummaryOptions::default(),
+            )?;
+            self.syncing.push_back(id);
+            started.push(id);
+        }
+        let mut message = format!("Syncing {} episode(s).", started.len());
+        if busy > 0 {
+            message.push_str(&format!(" {busy} already being synced."));
+        }
+        self.finish_action(db, message)?;
+        Ok(started)
+    }
+
+    fn finish_action(&mut self, db: &Database, message: String) -> Result<()> {
+        self.marked.clear();
+        self.message = Some(message);
+        self.refresh(db)
+    }
+
+    /// The sync worker finished an episode.
+    pub fn synced(&mut self, db: &Database, id: i64, result: Result<Report, String>) -> Result<()> {
+        self.syncing.retain(|&s| s != id);
+        self.message = Some(match result {
+            Ok(report) => match report.failed.iter().find(|(e, _)| *e == id) {
+                Some((_, reason)) => format!("Episode #{id} failed at {reason}"),
+                None if report.completed.contains(&id) => format!("Episode #{id} done."),
+                None => format!("Episode #{id} is still queued."),
+            },
+            Err(e) => format!("Sync of episode #{id} failed: {e}"),
+        });
+        self.refresh(db)?;
+        // Show what the sync produced if the episode is open
+        let open = self.reader.as_ref().filter(|r| r.episode_id == id);
+        if let Some(view) = open.map(|r| r.view) {
+            self.open(db, view)?;
+        }
+        Ok(())
+    }
+
+    /// Open the selected episode's summary or transcript.
+    pub fn open(&mut self, db: &Database, view: ReaderView) -> Result<()> {
+        let Some(episode) = self.selected_episode().cloned() else {
+            return Ok(());
+        };
+        let podcast = db.get_podcast(episode.podcast_id)?;
+        let scroll = match &self.reader {
+            Some(r) if r.episode_id == episode.id && r.view == view => r.scroll,
+            _ => 0,
+        };
+        self.reader = Some(Reader {
+            episode_id: episode.id,
+            view,
+            title: format!("{} - {}", episode.title, podcast.title),
+            lines: reader_lines(db, &episode, view)?,
+            scroll,
+            page: 0,
+        });
+        Ok(())
+    }
+
+    pub fn close(&mut self) {
+        self.reader = None;
+    }
+
+    /// Scroll the reader by `delta` lines, stopping at the ends.
+    pub fn scroll_by(&mut self, delta: isize) {
+        if let Some(reader) = &mut self.reader {
+            reader.scroll = reader
+                .scroll
+                .saturating_add_signed(delta)
+                .min(reader.lines.len().saturating_sub(1));
+        }
+    }
+}
+
+/// The text the reader shows: the latest summary, or the transcript with
+/// segment times when it has them.
+fn reader_lines(db: &Database, episode: &Episode, view: ReaderView) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    if let Some(date) = episode.published_at {
+        lines.push(format!("Published {}", date.format("%Y-%m-%d")));
+        lines.push(String::new());
+    }
+    match view {
+        ReaderView::Summary => match db.get_summary_by_episode(episode.id)? {
+            Some(summary) => {
+                lines.extend(summary.content.lines().map(str::to_string));
+                lines.push(String::new());
+                lines.push(format!(
+                    "Version {} | Model: {} | Generated: {}",
+                    summary.version,
+                    summary.model,
+                    summary.created_at.format("%Y-%m-%d %H:%M")
+                ));
+            }
+            None => lines.push("No summary yet. Press s to sync this episode.".to_string()),
+        },
+        ReaderView::Transcript => match episode.transcript_path.as_deref().map(Path::new) {
+            Some(path) if path.exists() => {
+                let transcript = transcribe::load_transcript(path)?;
+                if transcript.segments.is_empty() {
+                    lines.extend(transcript.text.lines().map(str::to_string));
+                } else {
+                    lines.extend(transcript.segments.iter().map(|s| {
+                        format!("{:>8}  {}", format_timestamp(s.start_secs), s.text.trim())
+                    }));
+                }
+            }
+            _ => lines.push("No transcript yet. Press s to sync this episode.".to_string()),
+        },
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> (Database, Vec<i64>) {
+        let db = Database::open_in_memory().unwrap();
+        let a = db
+            .insert_podcast("https://ex.com/a", "Alpha", None, None)
+            .unwrap();
+        db.insert_podcast("https://ex.com/b", "Beta", None, None)
+            .unwrap();
+        let ids = ["1", "2", "3"]
+            .iter()
+            .map(|guid| {
+                db.insert_episode(a.id, guid, guid, None, "https://ex.com/x.mp3", None, None)
+                    .unwrap()
+            })
+            .collect();
+        (db, ids)
+    }
+
+    #[test]
+    fn moving_marking_and_targets() {
+        let (db, ids) = library();
+        let mut app = App::load(&db).unwrap();
+        assert_eq!(app.selected_podcast().unwrap().title, "Alpha");
+        assert_eq!(app.episodes.len(), 3);
+
+        app.focus = Focus::Episodes;
+        app.move_by(&db, -5).unwrap();
+        let first = app.selected_episode().unwrap().id;
+        assert_eq!(app.targets(), vec![first]);
+
+        app.toggle_mark(&db).unwrap();
+        app.move_by(&db, 1).unwrap();
+        app.toggle_mark(&db).unwrap();
+        let mut targets = app.targets();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0], first);
+        targets.sort();
+        assert!(targets.iter().all(|id| ids.contains(id)));
+
+        // Another podcast drops the marks
+        app.toggle_focus();
+        app.move_by(&db, 1).unwrap();
+        assert_eq!(app.selected_podcast().unwrap().title, "Beta");
+        assert!(app.episodes.is_empty());
+        assert!(app.marked.is_empty());
+        assert!(app.targets().is_empty());
+    }
+
+    #[test]
+    fn queue_skip_and_sync() {
+        let (db, ids) = library();
+        let mut app = App::load(&db).unwrap();
+        app.marked = ids[..2].iter().copied().collect();
+        app.queue(&db).unwrap();
+        assert_eq!(db.list_jobs().unwrap().len(), 2);
+        assert!(app.marked.is_empty());
+
+        app.marked.insert(ids[0]);
+        app.toggle_skip(&db).unwrap();
+        assert_eq!(
+            db.get_episode(ids[0]).unwrap().status,
+            EpisodeStatus::Skipped { reason: None }
+        );
+        assert_eq!(db.list_jobs().unwrap().len(), 1);
+        app.marked.insert(ids[0]);
+        app.toggle_skip(&db).unwrap();
+        assert_eq!(db.get_episode(ids[0]).unwrap().status, EpisodeStatus::New);
+
+        app.marked = ids.iter().copied().collect();
+        let started = app.sync(&db).unwrap();
+        assert_eq!(started.len(), 3);
+        assert_eq!(db.list_jobs().unwrap().len(), 3);
+
+        // Episodes already with the worker aren't handed over twice
+        app.marked = ids.iter().copied().collect();
+        assert!(app.sync(&db).unwrap().is_empty());
+
+        let mut report = Report::default();
+        report.completed.push(started[0]);
+        app.synced(&db, started[0], Ok(report)).unwrap();
+        assert_eq!(app.syncing.len(), 2);
+        assert_eq!(
+            app.message.as_deref(),
+            Some(format!("Episode #{} done.", started[0]).as_str())
+        );
+    }
+
+    #[test]
+    fn redo_asks_first_and_forces_a_new_summary() {
+        let (db, ids) = library();
+        let mut app = App::load(&db).unwrap();
+        app.focus = Focus::Episodes;
+        app.request_redo();
+        let Some(Confirm::Redo(targets)) = app.confirm.take() else {
+            panic!("redo didn't ask");
+        };
+        assert!(db.list_jobs().unwrap().is_empty());
+
+        let started = app.redo(&db, targets).unwrap();
+        assert_eq!(started.len(), 1);
+        assert!(ids.contains(&started[0]));
+        assert!(db.get_job(started[0]).unwrap().unwrap().force_summary);
+    }
+
+    #[test]
+    fn reader_shows_summary_or_hint() {
+        let (db, _) = library();
+        let mut app = App::load(&db).unwrap();
+        app.open(&db, ReaderView::Summary).unwrap();
+        let reader = app.reader.as_ref().unwrap();
+        assert!(reader.lines[0].starts_with("No summary yet"));
+
+        let id = reader.episode_id;
+        db.insert_summary(id, "Line one\nLine two", "m", None, None, None, None, None)
+            .unwrap();
+        app.open(&db, ReaderView::Summary).unwrap();
+        let reader = app.reader.as_ref().unwrap();
+        assert_eq!(reader.lines[..2], ["Line one", "Line two"]);
+
+        app.scroll_by(10);
+        assert_eq!(
+            app.reader.as_ref().unwrap().scroll,
+            app.reader.as_ref().unwrap().lines.len() - 1
+        );
+        app.open(&db, ReaderView::Transcript).unwrap();
+        let reader = app.reader.as_ref().unwrap();
+        assert_eq!(reader.scroll, 0);
+        assert!(reader.lines[0].starts_with("No transcript yet"));
+    }
+}
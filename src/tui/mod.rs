@@ -0,0 +1,201 @@
+//! The interactive terminal browser: podcasts and episodes side by side, a
+//! reader for summaries and transcripts, and keys that queue, sync, redo and
+//! skip episodes. Syncs run one episode at a time on a worker thread with its
+//! own database connection; their output goes to a [`Monitor`] the screen
+//! draws from, along with whisper's progress.
+
+mod app;
+mod ui;
+
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::pipeline::{self, Report, Scope};
+use crate::progress::{self, Monitor};
+
+use app::{App, Confirm, Focus, ReaderView};
+
+/// How long to wait for a key before redrawing.
+const TICK: Duration = Duration::from_millis(200);
+/// How often to reload lists, picking up changes made by other processes.
+const REFRESH: Duration = Duration::from_secs(5);
+/// Lines moved by PgUp and PgDn in the lists.
+const LIST_PAGE: isize = 10;
+
+/// An episode the sync worker finished, with what the run reported.
+struct Synced {
+    episode_id: i64,
+    result: Result<Report, String>,
+}
+
+pub fn run(config: &AppConfig) -> Result<()> {
+    let db = Database::open(&config.db_path()?)?;
+    let mut app = App::load(&db)?;
+    let monitor = Arc::new(Monitor::default());
+    progress::install(monitor.clone());
+    let (jobs, synced) = spawn_worker(config)?;
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &db, &mut app, &monitor, &jobs, &synced);
+    ratatui::restore();
+    result
+}
+
+/// Start the thread that syncs the episodes sent to it, in order.
+fn spawn_worker(config: &AppConfig) -> Result<(Sender<i64>, Receiver<Synced>)> {
+    let db = Database::open(&config.db_path()?)?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let config = config.clone();
+    let (jobs, queued) = mpsc::channel::<i64>();
+    let (done, synced) = mpsc::channel();
+    std::thread::spawn(move || {
+        let client = reqwest::Client::new();
+        for episode_id in queued {
+            let result = runtime
+                .block_on(pipeline::run(
+                    &db,
+                    &client,
+                    &Scope::Episode(episode_id),
+                    &config,
+                ))
+                .map_err(|e| format!("{e:#}"));
+            if done.send(Synced { episode_id, result }).is_err() {
+                break;
+            }
+        }
+    });
+    Ok((jobs, synced))
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    db: &Database,
+    app: &mut App,
+    monitor: &Monitor,
+    jobs: &Sender<i64>,
+    synced: &Receiver<Synced>,
+) -> Result<()> {
+    let mut refreshed = Instant::now();
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app, monitor))?;
+
+        if event::poll(TICK)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.message = None;
+            match handle_key(app, db, key) {
+                Ok(Some(Action::Quit)) => return Ok(()),
+                Ok(Some(Action::Sync(ids))) => {
+                    for id in ids {
+                        jobs.send(id)?;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => app.message = Some(format!("Error: {e:#}")),
+            }
+        }
+
+        while let Ok(Synced { episode_id, result }) = synced.try_recv() {
+            app.synced(db, episode_id, result)?;
+        }
+        if refreshed.elapsed() >= REFRESH {
+            app.refresh(db)?;
+            refreshed = Instant::now();
+        }
+    }
+}
+
+/// What a key asks of the event loop.
+enum Action {
+    Quit,
+    Sync(Vec<i64>),
+}
+
+fn handle_key(app: &mut App, db: &Database, key: KeyEvent) -> Result<Option<Action>> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Ok(Some(Action::Quit));
+    }
+
+    if let Some(confirm) = app.confirm.take() {
+        if key.code != KeyCode::Char('y') {
+            app.message = Some("Cancelled.".to_string());
+            return Ok(None);
+        }
+        return Ok(Some(match confirm {
+            Confirm::Redo(ids) => Action::Sync(app.redo(db, ids)?),
+            Confirm::Quit => Action::Quit,
+        }));
+    }
+
+    // Keys that act on episodes work in the lists and the reader alike
+    match key.code {
+        KeyCode::Char('a') => app.queue(db)?,
+        KeyCode::Char('s') => return Ok(Some(Action::Sync(app.sync(db)?))),
+        KeyCode::Char('r') => app.request_redo(),
+        KeyCode::Char('x') => app.toggle_skip(db)?,
+        KeyCode::Char('q') if app.syncing.is_empty() => return Ok(Some(Action::Quit)),
+        KeyCode::Char('q') => {
+            app.message = Some(
+                "A sync is running; quit anyway? Unfinished episodes stay queued. (y/n)"
+                    .to_string(),
+            );
+            app.confirm = Some(Confirm::Quit);
+        }
+        _ if app.reader.is_some() => reader_key(app, db, key.code)?,
+        _ => list_key(app, db, key.code)?,
+    }
+    Ok(None)
+}
+
+fn reader_key(app: &mut App, db: &Database, code: KeyCode) -> Result<()> {
+    let page = app.reader.as_ref().map_or(1, |r| r.page.max(1)) as isize;
+    match code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Left | KeyCode::Char('h') => app.close(),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_by(1),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_by(-1),
+        KeyCode::PageDown | KeyCode::Char(' ') => app.scroll_by(page),
+        KeyCode::PageUp | KeyCode::Char('b') => app.scroll_by(-page),
+        KeyCode::Home | KeyCode::Char('g') => app.scroll_by(isize::MIN),
+        KeyCode::End | KeyCode::Char('G') => app.scroll_by(isize::MAX),
+        KeyCode::Char('t') => {
+            let view = match app.reader.as_ref().map(|r| r.view) {
+                Some(ReaderView::Summary) => ReaderView::Transcript,
+                _ => ReaderView::Summary,
+            };
+            app.open(db, view)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn list_key(app: &mut App, db: &Database, code: KeyCode) -> Result<()> {
+    match code {
+        KeyCode::Esc => app.marked.clear(),
+        KeyCode::Tab | KeyCode::BackTab => app.toggle_focus(),
+        KeyCode::Left | KeyCode::Char('h') => app.focus = Focus::Podcasts,
+        KeyCode::Right | KeyCode::Char('l') => app.focus = Focus::Episodes,
+        KeyCode::Down | KeyCode::Char('j') => app.move_by(db, 1)?,
+        KeyCode::Up | KeyCode::Char('k') => app.move_by(db, -1)?,
+        KeyCode::PageDown => app.move_by(db, LIST_PAGE)?,
+        KeyCode::PageUp => app.move_by(db, -LIST_PAGE)?,
+        KeyCode::Home | KeyCode::Char('g') => app.move_by(db, isize::MIN)?,
+        KeyCode::End | KeyCode::Char('G') => app.move_by(db, isize::MAX)?,
+        KeyCode::Char(' ') => app.toggle_mark(db)?,
+        KeyCode::Enter if app.focus == Focus::Podcasts => app.focus = Focus::Episodes,
+        KeyCode::Enter => app.open(db, ReaderView::Summary)?,
+        KeyCode::Char('t') => app.open(db, ReaderView::Transcript)?,
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! Drawing the TUI: the podcast and episode lists (or the reader), the sync
+//! pane, and a line for messages and key help.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, Paragraph, Wrap};
+
+use crate::models::EpisodeStatus;
+use crate::progress::Monitor;
+
+use super::app::{App, Focus, ReaderView};
+
+/// Height of the sync pane, borders included.
+const SYNC_HEIGHT: u16 = 8;
+
+pub fn draw(frame: &mut Frame, app: &mut App, monitor: &Monitor) {
+    let [main, sync, footer] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(SYNC_HEIGHT),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    if app.reader.is_some() {
+        draw_reader(frame, main, app);
+    } else {
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main);
+        draw_podcasts(frame, left, app);
+        draw_episodes(frame, right, app);
+    }
+    draw_sync(frame, sync, app, monitor);
+    draw_footer(frame, footer, app);
+}
+
+fn pane(title: String, focused: bool) -> Block<'static> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::new().fg(Color::Cyan))
+    } else {
+        block
+    }
+}
+
+fn highlight() -> Style {
+    Style::new().add_modifier(Modifier::REVERSED)
+}
+
+fn draw_podcasts(frame: &mut Frame, area: Rect, app: &mut App) {
+    let items: Vec<ListItem> = app
+        .podcasts
+        .iter()
+        .map(|entry| {
+            let mut spans = vec![Span::raw(entry.podcast.title.clone())];
+            spans.push(Span::raw(format!(" ({})", entry.episodes)).dark_gray());
+            if entry.new > 0 {
+                spans.push(Span::raw(format!(" {} new", entry.new)).green());
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(pane(" Podcasts ".to_string(), app.focus == Focus::Podcasts))
+        .highlight_style(highlight());
+    frame.render_stateful_widget(list, area, &mut app.podcast_list);
+}
+
+fn badge_style(status: &EpisodeStatus) -> Style {
+    match status {
+        EpisodeStatus::New => Style::new().fg(Color::Green),
+        EpisodeStatus::Summarized => Style::new().fg(Color::Blue),
+        EpisodeStatus::Skipped { .. } => Style::new().fg(Color::DarkGray),
+        EpisodeStatus::Failed { .. } => Style::new().fg(Color::Red),
+        EpisodeStatus::Downloaded | EpisodeStatus::Transcribed => Style::new().fg(Color::Yellow),
+    }
+}
+
+fn draw_episodes(frame: &mut Frame, area: Rect, app: &mut App) {
+    let items: Vec<ListItem> = app
+        .episodes
+        .iter()
+        .map(|ep| {
+            let mark = if app.marked.contains(&ep.id) {
+                "*"
+            } else {
+                " "
+            };
+            let date = ep
+                .published_at
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| " ".repeat(10));
+            let mut spans = vec![
+                Span::raw(mark).yellow().bold(),
+                Span::styled(format!("{:<7}", ep.status.badge()), badge_style(&ep.status)),
+                Span::raw(format!("{date} ")).dark_gray(),
+                Span::raw(ep.title.clone()),
+            ];
+            match app.syncing.iter().position(|&id| id == ep.id) {
+                Some(0) => spans.push(Span::raw("  syncing").yellow()),
+                Some(_) => spans.push(Span::raw("  waiting").dark_gray()),
+                None => {}
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let title = match app.selected_podcast() {
+        Some(podcast) if !app.marked.is_empty() => {
+            format!(" {} ({} marked) ", podcast.title, app.marked.len())
+        }
+        Some(podcast) => format!(" {} ", podcast.title),
+        None => " Episodes ".to_string(),
+    };
+    let list = List::new(items)
+        .block(pane(title, app.focus == Focus::Episodes))
+        .highlight_style(highlight());
+    frame.render_stateful_widget(list, area, &mut app.episode_list);
+}
+
+fn draw_reader(frame: &mut Frame, area: Rect, app: &mut App) {
+    let Some(reader) = app.reader.as_mut() else {
+        return;
+    };
+    let kind = match reader.view {
+        ReaderView::Summary => "Summary",
+        ReaderView::Transcript => "Transcript",
+    };
+    reader.page = area.height.saturating_sub(2) as usize;
+    let text: Vec<Line> = reader.lines.iter().map(|l| Line::raw(l.as_str())).collect();
+    let paragraph = Paragraph::new(text)
+        .block(pane(format!(" {kind}: {} ", reader.title), true))
+        .wrap(Wrap { trim: false })
+        .scroll((reader.scroll.min(u16::MAX as usize) as u16, 0));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_sync(frame: &mut Frame, area: Rect, app: &App, monitor: &Monitor) {
+    let title = match app.syncing.len().saturating_sub(1) {
+        0 => " Sync ".to_string(),
+        waiting => format!(" Sync ({waiting} waiting) "),
+    };
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [status, log] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+
+    match (monitor.activity(), app.syncing.front()) {
+        (Some(activity), _) if activity.measured => {
+            let percent = monitor.percent();
+            let gauge = Gauge::default()
+                .gauge_style(Style::new().fg(Color::Cyan))
+                .percent(percent)
+                .label(format!("{percent}% {}", activity.message));
+            frame.render_widget(gauge, status);
+        }
+        (Some(activity), _) => {
+            frame.render_widget(Paragraph::new(format!("{}...", activity.message)), status);
+        }
+        (None, Some(id)) => {
+            frame.render_widget(
+                Paragraph::new(format!("Working on episode #{id}...")),
+                status,
+            );
+        }
+        (None, None) => {
+            frame.render_widget(
+                Paragraph::new("Idle. s syncs the selected or marked episodes.").dark_gray(),
+                status,
+            );
+        }
+    }
+
+    let lines = monitor.lines();
+    let shown = lines.len().saturating_sub(log.height as usize);
+    let text: Vec<Line> = lines[shown..]
+        .iter()
+        .map(|line| {
+            let style = if line.error {
+                Style::new().fg(Color::Red)
+            } else {
+                Style::new().fg(Color::Gray)
+            };
+            Line::styled(line.text.clone(), style)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(text), log);
+}
+
+fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
+    let line = match &app.message {
+        Some(message) => Line::raw(message.clone()).yellow(),
+        None if app.reader.is_some() => Line::raw(
+            "j/k scroll  PgUp/PgDn page  t summary/transcript  s sync  r redo  Esc back  q quit",
+        )
+        .dark_gray(),
+        None => Line::raw(
+            "j/k move  Tab pane  Enter read  t transcript  Space mark  a queue  s sync  r redo  x skip  q quit",
+        )
+        .dark_gray(),
+    };
+    frame.render_widget(line, area);
+}